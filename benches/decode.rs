@@ -0,0 +1,138 @@
+//! Deterministic decoding benchmarks.
+//!
+//! Run with `cargo bench`. Maps are generated in memory from a fixed seed so
+//! results are comparable between runs.
+
+use std::io::Write;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value};
+use tiled_json::{parse, parse_slice_with_options, ParseOptions};
+
+/// Small linear congruential generator, deterministic across platforms.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1);
+        (self.0 >> 33) as u32
+    }
+}
+
+fn tile_layer(name: &str, width: u32, height: u32, rng: &mut Lcg) -> Value {
+    let mut bytes = Vec::with_capacity((width * height * 4) as usize);
+    for _ in 0..width * height {
+        // Runs of identical tiles like in real maps.
+        let gid = match rng.next() % 4 {
+            0 => rng.next() % 64,
+            _ => 1,
+        };
+        bytes.extend_from_slice(&gid.to_le_bytes());
+    }
+
+    let mut encoder = libflate::zlib::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(&bytes).unwrap();
+    let compressed = encoder.finish().into_result().unwrap();
+
+    json!({
+        "type": "tilelayer",
+        "name": name,
+        "opacity": 1,
+        "visible": true,
+        "width": width,
+        "height": height,
+        "encoding": "base64",
+        "compression": "zlib",
+        "data": base64::encode(&compressed),
+    })
+}
+
+fn object_layer(count: u32, rng: &mut Lcg) -> Value {
+    let objects: Vec<Value> = (0..count)
+        .map(|id| {
+            json!({
+                "id": id,
+                "name": format!("object {}", id),
+                "type": "enemy",
+                "x": rng.next() % 4096,
+                "y": rng.next() % 4096,
+                "width": 16,
+                "height": 16,
+                "rotation": 0,
+                "visible": true,
+                "properties": [
+                    { "name": "hp", "type": "int", "value": rng.next() % 100 },
+                    { "name": "speed", "type": "float", "value": 1.5 },
+                ],
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "objectgroup",
+        "name": "objects",
+        "opacity": 1,
+        "visible": true,
+        "objects": objects,
+    })
+}
+
+fn map(width: u32, height: u32, layers: Vec<Value>) -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "version": 1.2,
+        "orientation": "orthogonal",
+        "width": width,
+        "height": height,
+        "tilewidth": 16,
+        "tileheight": 16,
+        "tilesets": [],
+        "layers": layers,
+    }))
+    .unwrap()
+}
+
+fn decode(c: &mut Criterion) {
+    let mut rng = Lcg(0x5eed);
+
+    let huge = map(2048, 2048, vec![tile_layer("huge", 2048, 2048, &mut rng)]);
+    let many = map(
+        64,
+        64,
+        (0..500)
+            .map(|i| tile_layer(&format!("layer {}", i), 64, 64, &mut rng))
+            .collect(),
+    );
+    let objects = map(64, 64, vec![object_layer(20_000, &mut rng)]);
+
+    let cases = [
+        ("huge base64+zlib layer", &huge),
+        ("many small layers", &many),
+        ("object heavy", &objects),
+    ];
+
+    let mut group = c.benchmark_group("decode");
+    for (name, data) in cases.iter() {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("reader", name), data, |b, data| {
+            b.iter(|| parse(&data[..]).unwrap())
+        });
+
+        let options = ParseOptions::new().preallocate_hint(data.len());
+        group.bench_with_input(BenchmarkId::new("slice", name), data, |b, data| {
+            b.iter(|| parse_slice_with_options(data, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // The huge map takes a while to decode.
+    config = Criterion::default().sample_size(15);
+    targets = decode
+}
+criterion_main!(benches);
@@ -0,0 +1,601 @@
+//! Draw a map in a window with wgpu.
+//!
+//! Run with `cargo run --example render --features wgpu-render [map.json]`.
+//! Drag with the left mouse button to pan and scroll to zoom.
+//!
+//! The renderer covers the fields of the map that change how it looks: flip
+//! flags of tiles (through `TileLayer::build_mesh`), image layer offsets,
+//! parallax factors and origin (through `Camera::layer_translation`), layer
+//! tint and opacity (as vertex color) and blend modes (as pipeline blend
+//! state). Object layers aren't drawn.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use tiled_json::{
+    parse, BlendMode, Camera, Color, ImageHandles, LayerType, Map, MeshOptions, TiledError, Vertex,
+};
+use wgpu::util::DeviceExt;
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+const SHADER: &str = r#"
+struct View {
+    // Translation in screen pixels and zoom.
+    transform: vec4<f32>,
+    // Viewport size in screen pixels.
+    viewport: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> view: View;
+@group(1) @binding(0) var image: texture_2d<f32>;
+@group(1) @binding(1) var image_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    let screen = position * view.transform.z + view.transform.xy;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        screen.x * 2.0 / view.viewport.x,
+        -screen.y * 2.0 / view.viewport.y,
+        0.0,
+        1.0,
+    );
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, in.uv) * in.color;
+}
+"#;
+
+/// Blend states of the pipelines, see `blend_index`. Colors aren't
+/// premultiplied.
+const BLEND_STATES: [wgpu::BlendState; 5] = [
+    // Normal
+    wgpu::BlendState::ALPHA_BLENDING,
+    // Add
+    wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::SrcAlpha,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+    },
+    // Multiply
+    wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Dst,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+    },
+    // Darken
+    wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Min,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+    },
+    // Lighten
+    wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Max,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+    },
+];
+
+/// Pipeline for a blend mode. Modes that fixed function blending can't do
+/// are drawn like normal.
+fn blend_index(mode: &BlendMode) -> usize {
+    match mode {
+        BlendMode::Add => 1,
+        BlendMode::Multiply => 2,
+        BlendMode::Darken => 3,
+        BlendMode::Lighten => 4,
+        _ => 0,
+    }
+}
+
+/// Image uploaded to the GPU.
+struct Texture {
+    bind_group: wgpu::BindGroup,
+    size: (u32, u32),
+}
+
+/// Triangles drawn with one image.
+struct Batch {
+    image: String,
+    vertices: wgpu::Buffer,
+    indices: wgpu::Buffer,
+    count: u32,
+}
+
+/// Visible layer of the map with its buffers.
+struct DrawLayer {
+    index: usize,
+    pipeline: usize,
+    view: wgpu::Buffer,
+    view_group: wgpu::BindGroup,
+    batches: Vec<Batch>,
+}
+
+struct Renderer {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    pipelines: Vec<wgpu::RenderPipeline>,
+    images: ImageHandles<Texture>,
+    layers: Vec<DrawLayer>,
+    background: wgpu::Color,
+}
+
+/// Raw bytes of a slice for uploading to the GPU.
+fn as_bytes<T: Copy>(slice: &[T]) -> &[u8] {
+    // Safety: only used with `Vertex`, `u32` and `f32`, which are plain
+    // `repr(C)` data without padding.
+    unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, std::mem::size_of_val(slice)) }
+}
+
+/// Layer tint and opacity as vertex color.
+fn layer_color(tint: Option<Color>, opacity: f32) -> [f32; 4] {
+    let [r, g, b, a] = tint.map_or([u8::MAX; 4], |tint| tint.rgba());
+    let channel = |value: u8| f32::from(value) / 255.0;
+    [channel(r), channel(g), channel(b), channel(a) * opacity]
+}
+
+impl Renderer {
+    fn new(window: Arc<Window>, map: &Map, base_dir: &Path) -> Result<Self, TiledError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(|err| TiledError::Other(err.to_string()))?;
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }))
+        .ok_or_else(|| TiledError::Other("No graphics adapter found".to_string()))?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .map_err(|err| TiledError::Other(err.to_string()))?;
+
+        let size = window.inner_size();
+        let config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .ok_or_else(|| TiledError::Other("Surface isn't supported".to_string()))?;
+        surface.configure(&device, &config);
+
+        let view_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("view"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let image_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("image"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("map"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("map"),
+            bind_group_layouts: &[&view_layout, &image_layout],
+            push_constant_ranges: &[],
+        });
+        let vertex_layout = wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4],
+        };
+        let pipelines = BLEND_STATES
+            .iter()
+            .map(|blend| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("map"),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[vertex_layout.clone()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: "fs_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: config.format,
+                            blend: Some(*blend),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+            })
+            .collect();
+
+        // Nearest filtering keeps pixel art sharp when zoomed in.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let images = map.load_images(base_dir, |source| {
+            let image = image::open(&source.path)
+                .map_err(|err| TiledError::Other(format!("{}: {}", source.source, err)))?
+                .to_rgba8();
+            let (width, height) = image.dimensions();
+            let extent = wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(source.source.as_str()),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &image,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                extent,
+            );
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(source.source.as_str()),
+                layout: &image_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            });
+
+            Ok(Texture {
+                bind_group,
+                size: (width, height),
+            })
+        })?;
+
+        let mut renderer = Renderer {
+            window,
+            surface,
+            device,
+            queue,
+            config,
+            pipelines,
+            images,
+            layers: Vec::new(),
+            background: wgpu::Color::BLACK,
+        };
+        renderer.build_layers(map, &view_layout);
+
+        if let Some(color) = map.background_colour {
+            // The surface is sRGB, clear colors are linear.
+            let [r, g, b, a] = color.rgba();
+            let linear = |value: u8| (f64::from(value) / 255.0).powf(2.2);
+            renderer.background = wgpu::Color {
+                r: linear(r),
+                g: linear(g),
+                b: linear(b),
+                a: f64::from(a) / 255.0,
+            };
+        }
+
+        Ok(renderer)
+    }
+
+    /// Buffers of the visible tile and image layers.
+    fn build_layers(&mut self, map: &Map, view_layout: &wgpu::BindGroupLayout) {
+        let image_sizes: Vec<_> = map
+            .tilesets
+            .iter()
+            .map(|tileset| {
+                self.images
+                    .get(&tileset.image)
+                    .map_or((0, 0), |image| image.size)
+            })
+            .collect();
+        let table = map.build_gid_table(&image_sizes);
+
+        for (index, render) in map.render_layers().enumerate() {
+            if !render.visible {
+                continue;
+            }
+
+            let color = layer_color(render.layer.tint_color, render.opacity);
+            let mut batches = Vec::new();
+            match &render.layer.data {
+                LayerType::TileLayer(layer) => {
+                    let mut options =
+                        MeshOptions::new(map.tile_width as f32, map.tile_height as f32);
+                    options.color = color;
+
+                    // One batch per tileset, since each has its own image.
+                    for (tileset_index, tileset) in map.tilesets.iter().enumerate() {
+                        let tileset_table: Vec<_> = table
+                            .iter()
+                            .map(|resolved| match resolved.tileset {
+                                Some(owner) if owner == tileset_index => *resolved,
+                                _ => Default::default(),
+                            })
+                            .collect();
+                        let mesh = layer.build_mesh(&tileset_table, &options);
+                        batches.extend(self.batch(&tileset.image, &mesh.vertices, &mesh.indices));
+                    }
+                }
+                LayerType::ImageLayer(layer) => {
+                    let (width, height) = match self.images.get(&layer.image) {
+                        Some(image) => (image.size.0 as f32, image.size.1 as f32),
+                        None => continue,
+                    };
+                    // The offset is part of the layer translation.
+                    let corners = [[0.0, 0.0], [width, 0.0], [width, height], [0.0, height]];
+                    let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+                    let vertices: Vec<_> = corners
+                        .iter()
+                        .zip(uvs.iter())
+                        .map(|(position, uv)| Vertex {
+                            position: *position,
+                            uv: *uv,
+                            color,
+                        })
+                        .collect();
+                    batches.extend(self.batch(&layer.image, &vertices, &[0, 1, 2, 0, 2, 3]));
+                }
+                _ => continue,
+            }
+
+            let view = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(render.layer.name.as_str()),
+                size: 8 * std::mem::size_of::<f32>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let view_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(render.layer.name.as_str()),
+                layout: view_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: view.as_entire_binding(),
+                }],
+            });
+
+            self.layers.push(DrawLayer {
+                index,
+                pipeline: blend_index(render.blend_mode),
+                view,
+                view_group,
+                batches,
+            });
+        }
+    }
+
+    fn batch(&self, image: &str, vertices: &[Vertex], indices: &[u32]) -> Option<Batch> {
+        if indices.is_empty() {
+            return None;
+        }
+
+        let buffer = |contents: &[u8], usage| {
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(image),
+                    contents,
+                    usage,
+                })
+        };
+
+        Some(Batch {
+            image: image.to_string(),
+            vertices: buffer(as_bytes(vertices), wgpu::BufferUsages::VERTEX),
+            indices: buffer(as_bytes(indices), wgpu::BufferUsages::INDEX),
+            count: indices.len() as u32,
+        })
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.config.width = size.width.max(1);
+        self.config.height = size.height.max(1);
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    fn draw(&mut self, map: &Map, camera: &Camera) {
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost) | Err(wgpu::SurfaceError::Outdated) => {
+                self.resize(self.window.inner_size());
+                return;
+            }
+            Err(err) => {
+                eprintln!("Skipped frame: {}", err);
+                return;
+            }
+        };
+
+        let viewport = (self.config.width as f32, self.config.height as f32);
+        for layer in &self.layers {
+            let (x, y) = camera.layer_translation(map, &map.layers[layer.index]);
+            let view = [x, y, camera.zoom, 0.0, viewport.0, viewport.1, 0.0, 0.0];
+            self.queue.write_buffer(&layer.view, 0, as_bytes(&view));
+        }
+
+        let target = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("map"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.background),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            for layer in &self.layers {
+                pass.set_pipeline(&self.pipelines[layer.pipeline]);
+                pass.set_bind_group(0, &layer.view_group, &[]);
+
+                for batch in &layer.batches {
+                    let image = match self.images.get(&batch.image) {
+                        Some(image) => image,
+                        None => continue,
+                    };
+                    pass.set_bind_group(1, &image.bind_group, &[]);
+                    pass.set_vertex_buffer(0, batch.vertices.slice(..));
+                    pass.set_index_buffer(batch.indices.slice(..), wgpu::IndexFormat::Uint32);
+                    pass.draw_indexed(0..batch.count, 0, 0..1);
+                }
+            }
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+        frame.present();
+    }
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "assets/map.json".to_string());
+    let path = Path::new(&path);
+    let file = File::open(path).unwrap();
+    let map = parse(BufReader::new(file)).unwrap();
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = WindowBuilder::new()
+        .with_title(path.display().to_string())
+        .with_inner_size(LogicalSize::new(960.0, 640.0))
+        .build(&event_loop)
+        .unwrap();
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut renderer = Renderer::new(Arc::new(window), &map, base_dir).unwrap();
+
+    // Start looking at the middle of the map.
+    let mut camera = Camera::new(
+        (map.width * map.tile_width) as f32 / 2.0,
+        (map.height * map.tile_height) as f32 / 2.0,
+        1.0,
+    );
+    let mut dragging = false;
+    let mut cursor = PhysicalPosition::new(0.0, 0.0);
+
+    event_loop
+        .run(move |event, target| {
+            let event = match event {
+                Event::WindowEvent { event, .. } => event,
+                _ => return,
+            };
+
+            match event {
+                WindowEvent::CloseRequested => target.exit(),
+                WindowEvent::Resized(size) => renderer.resize(size),
+                WindowEvent::RedrawRequested => renderer.draw(&map, &camera),
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => dragging = state == ElementState::Pressed,
+                WindowEvent::CursorMoved { position, .. } => {
+                    if dragging {
+                        camera.x -= (position.x - cursor.x) as f32 / camera.zoom;
+                        camera.y -= (position.y - cursor.y) as f32 / camera.zoom;
+                        renderer.window.request_redraw();
+                    }
+                    cursor = position;
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let lines = match delta {
+                        MouseScrollDelta::LineDelta(_, lines) => lines,
+                        MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / 40.0,
+                    };
+                    camera.zoom = (camera.zoom * 1.1f32.powf(lines)).clamp(0.1, 16.0);
+                    renderer.window.request_redraw();
+                }
+                _ => {}
+            }
+        })
+        .unwrap();
+}
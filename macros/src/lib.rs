@@ -0,0 +1,59 @@
+//! `include_tiled_map!` embeds a Tiled map in the binary.
+//!
+//! The map is parsed with `tiled_json::parse` at build time, so broken maps
+//! fail the build. The validated JSON is embedded with `include_bytes!` and
+//! parsed again when the macro expression is evaluated.
+//!
+//! ```ignore
+//! use tiled_json_macros::include_tiled_map;
+//!
+//! let map: tiled_json::Map = include_tiled_map!("assets/map.json");
+//! ```
+
+extern crate proc_macro;
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Embed a Tiled map. The path is relative to the directory of the
+/// `Cargo.toml` of the crate using the macro. Expands to an expression of
+/// type `tiled_json::Map`.
+#[proc_macro]
+pub fn include_tiled_map(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    let mut path = std::env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    path.push(literal.value());
+
+    let error = |message: String| {
+        syn::Error::new(literal.span(), message)
+            .to_compile_error()
+            .into()
+    };
+
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) => return error(format!("Can't open {}: {}", path.display(), err)),
+    };
+
+    if let Err(err) = tiled_json::parse(file) {
+        return error(format!("Invalid Tiled map {}: {}", path.display(), err));
+    }
+
+    // Absolute path so the crate is rebuilt when the map changes.
+    let path = path.to_string_lossy().into_owned();
+
+    TokenStream::from(quote! {
+        {
+            const BYTES: &[u8] = include_bytes!(#path);
+            ::tiled_json::parse_slice_with_options(BYTES, &::tiled_json::ParseOptions::new())
+                .expect("map was validated at build time")
+        }
+    })
+}
@@ -0,0 +1,9 @@
+use tiled_json_macros::include_tiled_map;
+
+#[test]
+fn test_include_tiled_map() {
+    let map = include_tiled_map!("../assets/map_csv.json");
+    let parsed = tiled_json::parse_file(std::path::Path::new("../assets/map_csv.json")).unwrap();
+
+    assert_eq!(map, parsed);
+}
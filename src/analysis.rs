@@ -0,0 +1,126 @@
+//! Statistics extracted from existing maps, eg. for procedural generation.
+
+use std::collections::BTreeMap;
+
+use crate::{LayerType, Map, TileLayer};
+
+/// Direction from a tile to its neighbor.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+}
+
+/// Which GIDs appear next to each other and how often, see
+/// `Map::extract_adjacency`. Rules can be accumulated from many layers with
+/// `add_layer`. Empty cells are GID 0 and flipped tiles are separate GIDs.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct AdjacencyRules {
+    frequencies: BTreeMap<u32, usize>,
+    pairs: BTreeMap<(u32, Direction, u32), usize>,
+}
+
+impl AdjacencyRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count tiles and neighboring tile pairs of a layer. Layers that are
+    /// still encoded have no tiles to count, see `TileLayer::decode`.
+    pub fn add_layer(&mut self, layer: &TileLayer) {
+        let tiles = layer.tiles();
+        let width = layer.width() as usize;
+
+        for (index, &gid) in tiles.iter().enumerate() {
+            *self.frequencies.entry(gid).or_default() += 1;
+
+            // Pairs are recorded in both directions.
+            if (index + 1) % width != 0 {
+                self.add_pair(gid, Direction::Right, tiles[index + 1]);
+            }
+            if let Some(&below) = tiles.get(index + width) {
+                self.add_pair(gid, Direction::Down, below);
+            }
+        }
+    }
+
+    fn add_pair(&mut self, gid: u32, direction: Direction, neighbor: u32) {
+        *self.pairs.entry((gid, direction, neighbor)).or_default() += 1;
+        *self
+            .pairs
+            .entry((neighbor, direction.opposite(), gid))
+            .or_default() += 1;
+    }
+
+    /// How many times the GID appears.
+    pub fn frequency(&self, gid: u32) -> usize {
+        self.frequencies.get(&gid).copied().unwrap_or(0)
+    }
+
+    /// All GIDs with the number of times they appear, by GID.
+    pub fn frequencies(&self) -> &BTreeMap<u32, usize> {
+        &self.frequencies
+    }
+
+    /// How many times `neighbor` is next to `gid` in given direction.
+    pub fn count(&self, gid: u32, direction: Direction, neighbor: u32) -> usize {
+        self.pairs
+            .get(&(gid, direction, neighbor))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Neighbors of `gid` in given direction with their counts, by GID.
+    pub fn neighbors(
+        &self,
+        gid: u32,
+        direction: Direction,
+    ) -> impl Iterator<Item = (u32, usize)> + '_ {
+        self.pairs
+            .range((gid, direction, 0)..=(gid, direction, u32::MAX))
+            .map(|((_, _, b), count)| (*b, *count))
+    }
+
+    /// Whether `neighbor` is allowed next to `gid` in given direction.
+    pub fn allows(&self, gid: u32, direction: Direction, neighbor: u32) -> bool {
+        self.pairs.contains_key(&(gid, direction, neighbor))
+    }
+}
+
+impl Map {
+    /// Adjacency rules of the first tile layer with given name. Returns
+    /// `None` if there is no such layer.
+    pub fn extract_adjacency(&self, layer_name: &str) -> Option<AdjacencyRules> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.name == layer_name)
+            .find_map(|layer| match &layer.data {
+                LayerType::TileLayer(tile_layer) => {
+                    let mut rules = AdjacencyRules::new();
+                    rules.add_layer(tile_layer);
+                    Some(rules)
+                }
+                _ => None,
+            })
+    }
+}
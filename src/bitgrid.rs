@@ -0,0 +1,247 @@
+use crate::TileLayer;
+
+/// Connected set cells of a `BitGrid`, see `BitGrid::connected_components`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Region {
+    /// Bounding box `[x, y, width, height]` in cells.
+    pub bounds: [u32; 4],
+    /// Cells of the region in the order they were found.
+    pub cells: Vec<(u32, u32)>,
+}
+
+impl Region {
+    /// Number of cells in the region.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Grid of booleans stored as bits, eg. a mask of water or buildable tiles
+/// created with `TileLayer::mask`. Coordinates are columns and rows from the
+/// top-left corner, like `TileLayer::get_tile`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BitGrid {
+    width: u32,
+    height: u32,
+    bits: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Grid with all cells unset.
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = width as usize * height as usize;
+
+        BitGrid {
+            width,
+            height,
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    /// Whether a cell is set. Cells outside the grid are unset.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.index(x, y)
+            .is_some_and(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Set or clear a cell. Cells outside the grid are ignored.
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        if let Some(index) = self.index(x, y) {
+            if value {
+                self.bits[index / 64] |= 1 << (index % 64);
+            } else {
+                self.bits[index / 64] &= !(1 << (index % 64));
+            }
+        }
+    }
+
+    /// Number of set cells.
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// Whether no cell is set.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|bits| *bits == 0)
+    }
+
+    /// Coordinates of set cells in row order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width.max(1);
+        (0..self.width as usize * self.height as usize)
+            .filter(move |index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+            .map(move |index| (index as u32 % width, index as u32 / width))
+    }
+
+    /// Cells set in either grid. Grids of different sizes are combined in
+    /// the area they share.
+    pub fn union(&self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Cells set in both grids.
+    pub fn intersect(&self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Cells set in this grid but not in `other`.
+    pub fn difference(&self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Whether any of the 4 neighbors of a cell is set.
+    fn any_neighbor(&self, x: u32, y: u32) -> bool {
+        (x > 0 && self.get(x - 1, y))
+            || self.get(x + 1, y)
+            || (y > 0 && self.get(x, y - 1))
+            || self.get(x, y + 1)
+    }
+
+    /// Whether all 4 neighbors of a cell are set. Cells outside the grid
+    /// are unset.
+    fn all_neighbors(&self, x: u32, y: u32) -> bool {
+        x > 0
+            && y > 0
+            && self.get(x - 1, y)
+            && self.get(x + 1, y)
+            && self.get(x, y - 1)
+            && self.get(x, y + 1)
+    }
+
+    /// Grow the set cells by one cell in the 4 directions.
+    pub fn dilate(&self) -> BitGrid {
+        let mut grid = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.get(x, y) && self.any_neighbor(x, y) {
+                    grid.set(x, y, true);
+                }
+            }
+        }
+        grid
+    }
+
+    /// Shrink the set cells by one cell. Cells next to the grid edge are
+    /// cleared.
+    pub fn erode(&self) -> BitGrid {
+        let mut grid = self.clone();
+        for (x, y) in self.iter() {
+            if !self.all_neighbors(x, y) {
+                grid.set(x, y, false);
+            }
+        }
+        grid
+    }
+
+    /// Set cells that have an unset neighbor or are at the grid edge.
+    pub fn outline(&self) -> BitGrid {
+        self.difference(&self.erode())
+    }
+
+    /// Groups of set cells connected in the 4 directions, in row order of
+    /// their first cell.
+    pub fn connected_components(&self) -> Vec<Region> {
+        let mut visited = BitGrid::new(self.width, self.height);
+        let mut regions = Vec::new();
+
+        for start in self.iter() {
+            if visited.get(start.0, start.1) {
+                continue;
+            }
+
+            visited.set(start.0, start.1, true);
+            let mut cells = Vec::new();
+            let mut stack = vec![start];
+            let (mut min, mut max) = (start, start);
+
+            while let Some((x, y)) = stack.pop() {
+                cells.push((x, y));
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+
+                let neighbors = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbors {
+                    if self.get(nx, ny) && !visited.get(nx, ny) {
+                        visited.set(nx, ny, true);
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+
+            regions.push(Region {
+                bounds: [min.0, min.1, max.0 - min.0 + 1, max.1 - min.1 + 1],
+                cells,
+            });
+        }
+
+        regions
+    }
+
+    fn combine<F: Fn(u64, u64) -> u64>(&self, other: &BitGrid, f: F) -> BitGrid {
+        if (self.width, self.height) == (other.width, other.height) {
+            return BitGrid {
+                width: self.width,
+                height: self.height,
+                bits: self
+                    .bits
+                    .iter()
+                    .zip(&other.bits)
+                    .map(|(a, b)| f(*a, *b))
+                    .collect(),
+            };
+        }
+
+        let mut grid = BitGrid::new(self.width.min(other.width), self.height.min(other.height));
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let (a, b) = (self.get(x, y) as u64, other.get(x, y) as u64);
+                grid.set(x, y, f(a, b) & 1 != 0);
+            }
+        }
+        grid
+    }
+}
+
+impl TileLayer {
+    /// Cells for which `predicate` returns true. The predicate gets the GID
+    /// with flip flags. Encoded layers give an empty mask.
+    pub fn mask<F: Fn(u32) -> bool>(&self, predicate: F) -> BitGrid {
+        let mut grid = BitGrid::new(self.width(), self.height());
+
+        for (index, gid) in self.tiles().iter().enumerate() {
+            if predicate(*gid) {
+                grid.bits[index / 64] |= 1 << (index % 64);
+            }
+        }
+
+        grid
+    }
+}
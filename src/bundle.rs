@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::TiledError;
+use crate::paths::resolve;
+use crate::{parse_file_with_externals, LayerType, Map, ParseOptions, Properties, Property};
+
+impl Map {
+    /// Files the map needs when loaded from `base_dir`: external tilesets,
+    /// object templates, images and files of file properties. Paths are
+    /// sorted and listed once. External tilesets are only known for maps
+    /// parsed with `parse_file_with_externals`.
+    pub fn collect_dependencies(&self, base_dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut add = |dir: &Path, path: &str| {
+            if !path.is_empty() {
+                files.push(resolve(dir, path));
+            }
+        };
+
+        add_properties(&mut add, base_dir, &self.properties);
+
+        for tileset in &self.tilesets {
+            let dir = match &tileset.source {
+                Some(source) => {
+                    add(base_dir, source);
+                    resolve(base_dir, source)
+                        .parent()
+                        .map_or_else(|| base_dir.to_path_buf(), Path::to_path_buf)
+                }
+                None => base_dir.to_path_buf(),
+            };
+
+            add(&dir, &tileset.image);
+            add_properties(&mut add, &dir, &tileset.properties);
+            for tile in tileset.tiles.iter().flatten() {
+                add_properties(&mut add, &dir, &tile.properties);
+            }
+        }
+
+        for layer in &self.layers {
+            add_properties(&mut add, base_dir, &layer.properties);
+            match &layer.data {
+                LayerType::ImageLayer(image) => add(base_dir, &image.image),
+                LayerType::ObjectGroup(group) => {
+                    for object in &group.objects {
+                        add(base_dir, object.template.as_deref().unwrap_or(""));
+                        add_properties(&mut add, base_dir, &object.properties);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+fn add_properties<F: FnMut(&Path, &str)>(add: &mut F, dir: &Path, properties: &Option<Properties>) {
+    for property in properties.iter().flat_map(|properties| properties.values()) {
+        if let Property::File(path) = property {
+            add(dir, path);
+        }
+    }
+}
+
+/// Copy the map at `path` and its dependencies (see
+/// `Map::collect_dependencies`) into `out_dir`, eg. to share a map with all
+/// the files it needs. Files keep their layout relative to the deepest
+/// directory that contains all of them, so references stay valid. Returns
+/// the copied files.
+pub fn bundle(path: &Path, out_dir: &Path) -> Result<Vec<PathBuf>, TiledError> {
+    let map = parse_file_with_externals(path, &ParseOptions::new())?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut files = vec![path.to_path_buf()];
+    files.extend(map.collect_dependencies(base_dir));
+
+    let files = files
+        .iter()
+        .map(|file| {
+            fs::canonicalize(file).map_err(|err| TiledError::External {
+                path: file.clone(),
+                source: Box::new(TiledError::Other(format!("{:?}", err))),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut root = files[0].parent().unwrap_or(&files[0]).to_path_buf();
+    while !files.iter().all(|file| file.starts_with(&root)) {
+        root = match root.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => break,
+        };
+    }
+
+    let io_error = |err| TiledError::Other(format!("{:?}", err));
+    let mut copied = Vec::new();
+    for file in &files {
+        let target = out_dir.join(file.strip_prefix(&root).unwrap_or(file));
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::copy(file, &target).map_err(io_error)?;
+        copied.push(target);
+    }
+
+    Ok(copied)
+}
@@ -0,0 +1,50 @@
+use crate::{Layer, Map};
+
+/// Camera looking at a map.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Camera {
+    /// X coordinate of the view center in map pixels.
+    pub x: f32,
+    /// Y coordinate of the view center in map pixels.
+    pub y: f32,
+    /// Scale from map pixels to screen pixels.
+    pub zoom: f32,
+}
+
+impl Camera {
+    pub fn new(x: f32, y: f32, zoom: f32) -> Self {
+        Camera { x, y, zoom }
+    }
+
+    /// Translation in screen pixels, relative to the center of the viewport,
+    /// to apply when drawing a layer of the map. Takes layer offset and
+    /// parallax factor into account the same way the Tiled editor does.
+    pub fn layer_translation(&self, map: &Map, layer: &Layer) -> (f32, f32) {
+        let (offset_x, offset_y) = layer.offset();
+
+        self.translation(
+            (layer.parallax_x, layer.parallax_y),
+            (offset_x, offset_y),
+            (map.parallax_origin_x, map.parallax_origin_y),
+        )
+    }
+
+    /// Translation in screen pixels, relative to the center of the viewport,
+    /// for content with given parallax factor, offset and parallax origin.
+    ///
+    /// Parallax factor 1 moves with the camera, 0 stays fixed on the screen.
+    pub fn translation(
+        &self,
+        parallax: (f32, f32),
+        offset: (f32, f32),
+        origin: (f32, f32),
+    ) -> (f32, f32) {
+        let parallax_x = (self.x - origin.0) * (1.0 - parallax.0);
+        let parallax_y = (self.y - origin.1) * (1.0 - parallax.1);
+
+        (
+            (offset.0 + parallax_x - self.x) * self.zoom,
+            (offset.1 + parallax_y - self.y) * self.zoom,
+        )
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{LayerType, Map, Object};
+
+/// Position in the objects of a map, see `Map::objects_chunked`. The cursor
+/// doesn't borrow the map, so it can be kept between frames. It's only
+/// valid as long as objects aren't added or removed.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct ObjectCursor {
+    layer: usize,
+    object: usize,
+}
+
+impl ObjectCursor {
+    /// Cursor at the first object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Up to `count` objects from the cursor in layer order, and move the
+    /// cursor past them. Empty when all objects have been returned.
+    pub fn next_chunk<'a>(&mut self, map: &'a Map, count: usize) -> Vec<&'a Object> {
+        let mut chunk = Vec::with_capacity(count.min(64));
+
+        while chunk.len() < count {
+            let objects = match map.layers.get(self.layer).map(|layer| &layer.data) {
+                Some(LayerType::ObjectGroup(group)) => &group.objects,
+                Some(_) => &[][..],
+                None => break,
+            };
+
+            let end = objects.len().min(self.object + count - chunk.len());
+            chunk.extend(objects.get(self.object..end).into_iter().flatten());
+
+            if end < objects.len() {
+                self.object = end;
+            } else {
+                self.layer += 1;
+                self.object = 0;
+            }
+        }
+
+        chunk
+    }
+
+    /// Whether all objects of `map` have been returned.
+    pub fn is_done(&self, map: &Map) -> bool {
+        map.layers
+            .iter()
+            .skip(self.layer)
+            .enumerate()
+            .all(|(i, layer)| {
+                let skip = if i == 0 { self.object } else { 0 };
+                match &layer.data {
+                    LayerType::ObjectGroup(group) => group.objects.len() <= skip,
+                    _ => true,
+                }
+            })
+    }
+}
+
+/// Iterator of object chunks, see `Map::objects_chunked`.
+#[derive(Debug, Clone)]
+pub struct ObjectChunks<'a> {
+    map: &'a Map,
+    cursor: ObjectCursor,
+    size: usize,
+}
+
+impl<'a> ObjectChunks<'a> {
+    /// Position of the next chunk, to continue later with
+    /// `ObjectCursor::next_chunk` without borrowing the map.
+    pub fn cursor(&self) -> ObjectCursor {
+        self.cursor
+    }
+}
+
+impl<'a> Iterator for ObjectChunks<'a> {
+    type Item = Vec<&'a Object>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.cursor.next_chunk(self.map, self.size)).filter(|chunk| !chunk.is_empty())
+    }
+}
+
+impl Map {
+    /// Objects of all object layers in chunks of `size`, eg. for spawning a
+    /// chunk of entities per frame.
+    ///
+    /// # Panics
+    ///
+    /// If the size is zero.
+    pub fn objects_chunked(&self, size: usize) -> ObjectChunks<'_> {
+        assert!(size > 0, "empty chunk size");
+
+        ObjectChunks {
+            map: self,
+            cursor: ObjectCursor::new(),
+            size,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Physics colliders of objects for the rapier2d engine, enabled with the
+//! `rapier2d` feature. Objects are marked as colliders with a bool property,
+//! `"collides"` by default, which can be changed with `Colliders`.
+
+use rapier2d::parry::transformation::vhacd::{VHACDParameters, VHACD};
+use rapier2d::prelude::{ColliderBuilder, Isometry, Point as RapierPoint, SharedShape, Vector};
+
+use crate::measure::signed_area;
+use crate::{Map, Object, ObjectId, ObjectShape, Point, Property};
+
+/// Points on the outline of ellipses that aren't circles.
+const ELLIPSE_POINTS: usize = 16;
+
+/// Which objects become colliders, see `to_rapier`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Colliders {
+    property: String,
+}
+
+impl Default for Colliders {
+    fn default() -> Self {
+        Colliders {
+            property: "collides".to_string(),
+        }
+    }
+}
+
+impl Colliders {
+    /// Objects with a true `"collides"` property.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bool object property that marks colliders.
+    pub fn property(mut self, name: &str) -> Self {
+        self.property = name.to_string();
+        self
+    }
+
+    /// Colliders of the marked objects with their IDs, in layer order.
+    ///
+    /// Positions are map pixels multiplied by `scale`, with y pointing down
+    /// like in Tiled. Rectangles become cuboids, circles balls, other
+    /// ellipses convex hulls of their outline, polygons the convex parts they
+    /// are decomposed to and polylines segments. Points, texts and shapes
+    /// without an area are skipped.
+    pub fn to_rapier(&self, map: &Map, scale: f32) -> Vec<(ObjectId, ColliderBuilder)> {
+        map.objects()
+            .filter(|object| self.is_collider(object))
+            .filter_map(|object| Some((object.id, collider(object, scale)?)))
+            .collect()
+    }
+
+    fn is_collider(&self, object: &Object) -> bool {
+        object
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.get(&self.property))
+            == Some(&Property::Bool(true))
+    }
+}
+
+/// Colliders of the objects with a true `"collides"` property, see
+/// `Colliders::to_rapier`.
+pub fn to_rapier(map: &Map, scale: f32) -> Vec<(ObjectId, ColliderBuilder)> {
+    Colliders::default().to_rapier(map, scale)
+}
+
+fn collider(object: &Object, scale: f32) -> Option<ColliderBuilder> {
+    let angle = object.rotation.to_radians();
+    let at = |point: Point| Isometry::new(Vector::new(point.x * scale, point.y * scale), angle);
+    let scaled = |point: &Point| RapierPoint::new(point.x * scale, point.y * scale);
+
+    match &object.shape {
+        ObjectShape::Rect { width, height } => {
+            if *width <= 0.0 || *height <= 0.0 {
+                return None;
+            }
+
+            // Tile objects are aligned to their bottom left corner.
+            let top = if object.gid.is_some() { -height } else { 0.0 };
+            let center = object.to_world(&Point {
+                x: width / 2.0,
+                y: top + height / 2.0,
+            });
+            let (hx, hy) = (width / 2.0 * scale, height / 2.0 * scale);
+
+            Some(ColliderBuilder::cuboid(hx, hy).position(at(center)))
+        }
+        ObjectShape::Ellipse { .. } => {
+            let (rx, ry) = object.shape.radii()?;
+            if rx <= 0.0 || ry <= 0.0 {
+                return None;
+            }
+
+            let center = object.to_world(&Point { x: rx, y: ry });
+            let builder = if object.shape.is_circle() {
+                ColliderBuilder::ball(rx * scale)
+            } else {
+                let outline: Vec<_> = (0..ELLIPSE_POINTS)
+                    .map(|i| {
+                        let t = i as f32 / ELLIPSE_POINTS as f32 * std::f32::consts::TAU;
+                        scaled(&Point {
+                            x: rx * t.cos(),
+                            y: ry * t.sin(),
+                        })
+                    })
+                    .collect();
+                ColliderBuilder::convex_hull(&outline)?
+            };
+
+            Some(builder.position(at(center)))
+        }
+        ObjectShape::Polygon { points } if points.len() > 2 => {
+            // Decomposing a polygon without an area, eg. with all points on
+            // a line, panics in parry.
+            if signed_area(points).abs() <= f32::EPSILON {
+                return None;
+            }
+
+            let vertices: Vec<_> = points.iter().map(scaled).collect();
+            let count = vertices.len() as u32;
+            let indices: Vec<_> = (0..count).map(|i| [i, (i + 1) % count]).collect();
+            let parts: Vec<_> =
+                VHACD::decompose(&VHACDParameters::default(), &vertices, &indices, true)
+                    .compute_exact_convex_hulls(&vertices, &indices)
+                    .into_iter()
+                    .filter_map(SharedShape::convex_polyline)
+                    .map(|part| (Isometry::identity(), part))
+                    .collect();
+            if parts.is_empty() {
+                return None;
+            }
+
+            let origin = Point {
+                x: object.x,
+                y: object.y,
+            };
+
+            Some(ColliderBuilder::compound(parts).position(at(origin)))
+        }
+        ObjectShape::Polyline { points } if points.len() > 1 => {
+            let vertices = points.iter().map(scaled).collect();
+            let origin = Point {
+                x: object.x,
+                y: object.y,
+            };
+
+            Some(ColliderBuilder::polyline(vertices, None).position(at(origin)))
+        }
+        _ => None,
+    }
+}
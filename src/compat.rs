@@ -0,0 +1,287 @@
+//! Views of parsed maps with the API of the `tiled` crate (rs-tiled), enabled
+//! with the `compat` feature. Game code written against `tiled::Map` can be
+//! moved over by wrapping a parsed map in `compat::Map::new`, mostly by
+//! changing imports. The views borrow the map and don't copy anything.
+//!
+//! Fields that are public in the `tiled` crate are methods here, eg.
+//! `layer.name()` instead of `layer.name`.
+
+use crate::{Color, Orientation, Properties, Tileset};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const FLIP_FLAGS: u32 = FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY;
+
+/// Map with the API of `tiled::Map`.
+#[derive(Debug, Clone, Copy)]
+pub struct Map<'map> {
+    map: &'map crate::Map,
+}
+
+impl<'map> Map<'map> {
+    pub fn new(map: &'map crate::Map) -> Self {
+        Map { map }
+    }
+
+    /// The parsed map behind the view.
+    pub fn inner(&self) -> &'map crate::Map {
+        self.map
+    }
+
+    pub fn width(&self) -> u32 {
+        self.map.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.map.height
+    }
+
+    pub fn tile_width(&self) -> u32 {
+        self.map.tile_width
+    }
+
+    pub fn tile_height(&self) -> u32 {
+        self.map.tile_height
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.map.orientation
+    }
+
+    pub fn background_color(&self) -> Option<Color> {
+        self.map.background_colour
+    }
+
+    pub fn properties(&self) -> Option<&'map Properties> {
+        self.map.properties.as_ref()
+    }
+
+    pub fn tilesets(&self) -> &'map [Tileset] {
+        &self.map.tilesets
+    }
+
+    /// Top-level layers in the order they are drawn.
+    pub fn layers(&self) -> impl ExactSizeIterator<Item = Layer<'map>> + 'map {
+        let map = self.map;
+        map.layers.iter().map(move |layer| Layer { map, layer })
+    }
+
+    pub fn get_layer(&self, index: usize) -> Option<Layer<'map>> {
+        let map = self.map;
+        map.layers.get(index).map(|layer| Layer { map, layer })
+    }
+}
+
+/// Layer with the API of `tiled::Layer`.
+#[derive(Debug, Clone, Copy)]
+pub struct Layer<'map> {
+    map: &'map crate::Map,
+    layer: &'map crate::Layer,
+}
+
+impl<'map> Layer<'map> {
+    pub fn name(&self) -> &'map str {
+        &self.layer.name
+    }
+
+    pub fn visible(&self) -> bool {
+        self.layer.visible
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.layer.opacity
+    }
+
+    pub fn properties(&self) -> Option<&'map Properties> {
+        self.layer.properties.as_ref()
+    }
+
+    pub fn layer_type(&self) -> LayerType<'map> {
+        let map = self.map;
+        match &self.layer.data {
+            crate::LayerType::TileLayer(layer) => LayerType::Tiles(TileLayer { map, layer }),
+            crate::LayerType::ObjectGroup(group) => LayerType::Objects(ObjectLayer { group }),
+            crate::LayerType::ImageLayer(layer) => LayerType::Image(ImageLayer { layer }),
+            crate::LayerType::Invalid { .. } => LayerType::Invalid,
+        }
+    }
+
+    /// The parsed layer behind the view.
+    pub fn inner(&self) -> &'map crate::Layer {
+        self.layer
+    }
+}
+
+/// Layer data with the variants of `tiled::LayerType`.
+#[derive(Debug, Clone, Copy)]
+pub enum LayerType<'map> {
+    Tiles(TileLayer<'map>),
+    Objects(ObjectLayer<'map>),
+    Image(ImageLayer<'map>),
+    /// Layer that failed to parse, see `parse_with_errors`. Not in the
+    /// `tiled` crate.
+    Invalid,
+}
+
+/// Tile layer with the API of `tiled::TileLayer`.
+#[derive(Debug, Clone, Copy)]
+pub struct TileLayer<'map> {
+    map: &'map crate::Map,
+    layer: &'map crate::TileLayer,
+}
+
+impl<'map> TileLayer<'map> {
+    /// Always set, unlike in the `tiled` crate where infinite layers have
+    /// no width.
+    pub fn width(&self) -> Option<u32> {
+        Some(self.layer.width())
+    }
+
+    /// Always set, see `width`.
+    pub fn height(&self) -> Option<u32> {
+        Some(self.layer.height())
+    }
+
+    /// Tile at map coordinates, which can be negative in infinite maps.
+    /// Empty cells and tiles that aren't in any tileset are `None`.
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<LayerTile<'map>> {
+        LayerTile::new(self.map, self.layer.get_tile_signed(x, y)?)
+    }
+}
+
+/// Tile of a tile layer with the API of `tiled::LayerTile`.
+#[derive(Debug, Clone, Copy)]
+pub struct LayerTile<'map> {
+    map: &'map crate::Map,
+    tileset_index: usize,
+    id: u32,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_d: bool,
+}
+
+impl<'map> LayerTile<'map> {
+    fn new(map: &'map crate::Map, gid: u32) -> Option<Self> {
+        let id = gid & !FLIP_FLAGS;
+        if id == 0 {
+            return None;
+        }
+
+        let (tileset_index, tileset) = map
+            .tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, tileset)| tileset.first_gid <= id)
+            .max_by_key(|(_, tileset)| tileset.first_gid)?;
+
+        Some(LayerTile {
+            map,
+            tileset_index,
+            id: id - tileset.first_gid,
+            flip_h: gid & FLIPPED_HORIZONTALLY != 0,
+            flip_v: gid & FLIPPED_VERTICALLY != 0,
+            flip_d: gid & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+
+    /// Local ID of the tile in its tileset.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Index of the tileset in `Map::tilesets`.
+    pub fn tileset_index(&self) -> usize {
+        self.tileset_index
+    }
+
+    pub fn get_tileset(&self) -> &'map Tileset {
+        &self.map.tilesets[self.tileset_index]
+    }
+}
+
+/// Object layer with the API of `tiled::ObjectLayer`.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectLayer<'map> {
+    group: &'map crate::ObjectGroup,
+}
+
+impl<'map> ObjectLayer<'map> {
+    pub fn objects(&self) -> impl ExactSizeIterator<Item = Object<'map>> + 'map {
+        self.group.objects.iter().map(|object| Object { object })
+    }
+
+    pub fn get_object(&self, index: usize) -> Option<Object<'map>> {
+        self.group
+            .objects
+            .get(index)
+            .map(|object| Object { object })
+    }
+
+    pub fn colour(&self) -> Option<Color> {
+        self.group.color
+    }
+}
+
+/// Object with the API of `tiled::Object`.
+#[derive(Debug, Clone, Copy)]
+pub struct Object<'map> {
+    object: &'map crate::Object,
+}
+
+impl<'map> Object<'map> {
+    pub fn id(&self) -> u32 {
+        self.object.id
+    }
+
+    pub fn name(&self) -> &'map str {
+        &self.object.name
+    }
+
+    /// Type of the object, called class since Tiled 1.9.
+    pub fn user_type(&self) -> &'map str {
+        &self.object.r#type
+    }
+
+    pub fn x(&self) -> f32 {
+        self.object.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.object.y
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.object.rotation
+    }
+
+    pub fn visible(&self) -> bool {
+        self.object.visible
+    }
+
+    pub fn shape(&self) -> &'map crate::ObjectShape {
+        &self.object.shape
+    }
+
+    pub fn properties(&self) -> Option<&'map Properties> {
+        self.object.properties.as_ref()
+    }
+
+    /// The parsed object behind the view.
+    pub fn inner(&self) -> &'map crate::Object {
+        self.object
+    }
+}
+
+/// Image layer with the API of `tiled::ImageLayer`.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageLayer<'map> {
+    layer: &'map crate::ImageLayer,
+}
+
+impl<'map> ImageLayer<'map> {
+    /// Path of the image relative to the map.
+    pub fn image(&self) -> &'map str {
+        &self.layer.image
+    }
+}
@@ -0,0 +1,71 @@
+//! Layer attributes accumulated from parent layers, so renderers don't have
+//! to fold offsets, parallax, opacity, tint and visibility themselves.
+
+use crate::{Color, Layer, Map};
+
+/// Values that accumulate from parent layers to their children, see
+/// `Map::layers_with_context`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LayerContext {
+    /// Offset in pixels.
+    pub offset: (f32, f32),
+    /// Horizontal and vertical parallax factors.
+    pub parallax: (f32, f32),
+    pub opacity: f32,
+    /// Color the layer is multiplied with, `None` for white.
+    pub tint: Option<Color>,
+    pub visible: bool,
+}
+
+impl Default for LayerContext {
+    fn default() -> Self {
+        LayerContext {
+            offset: (0.0, 0.0),
+            parallax: (1.0, 1.0),
+            opacity: 1.0,
+            tint: None,
+            visible: true,
+        }
+    }
+}
+
+impl LayerContext {
+    /// Context of `layer` inside this context. Offsets add up, parallax
+    /// factors, opacities and tints multiply and a layer is visible only if
+    /// all of its parents are.
+    pub fn child(&self, layer: &Layer) -> LayerContext {
+        let offset = layer.offset();
+
+        LayerContext {
+            offset: (self.offset.0 + offset.0, self.offset.1 + offset.1),
+            parallax: (
+                self.parallax.0 * layer.parallax_x,
+                self.parallax.1 * layer.parallax_y,
+            ),
+            opacity: self.opacity * layer.opacity,
+            tint: match (self.tint, layer.tint_color) {
+                (Some(a), Some(b)) => Some(multiply(a, b)),
+                (a, b) => a.or(b),
+            },
+            visible: self.visible && layer.visible,
+        }
+    }
+}
+
+fn multiply(a: Color, b: Color) -> Color {
+    let (a, b) = (a.rgba(), b.rgba());
+    let channel = |i: usize| (u16::from(a[i]) * u16::from(b[i]) / 255) as u8;
+
+    Color::new(channel(0), channel(1), channel(2), channel(3))
+}
+
+impl Map {
+    /// Iterate layers with their accumulated context, bottom first. Maps
+    /// don't have group layers yet, so the context of a layer is its own.
+    pub fn layers_with_context(&self) -> impl Iterator<Item = (&Layer, LayerContext)> {
+        let root = LayerContext::default();
+        self.layers
+            .iter()
+            .map(move |layer| (layer, root.child(layer)))
+    }
+}
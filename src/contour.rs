@@ -0,0 +1,183 @@
+//! Outline polygons of `BitGrid` masks.
+
+use std::collections::HashMap;
+
+use crate::{BitGrid, Point};
+
+type Vertex = (i64, i64);
+
+impl BitGrid {
+    /// Outline polygons of the set cells in pixels, for tiles of
+    /// `tile_width` x `tile_height`. Outer boundaries go clockwise and holes
+    /// counter-clockwise (y down). Cells touching only at a corner get
+    /// separate polygons. Points are not repeated to close the polygon.
+    pub fn to_polygons(&self, tile_width: f32, tile_height: f32) -> Vec<Vec<Point>> {
+        self.to_polygons_simplified(tile_width, tile_height, 0.0)
+    }
+
+    /// Like `to_polygons` but staircase edges are simplified with
+    /// Douglas-Peucker, dropping points closer than `tolerance` cells to the
+    /// simplified outline.
+    pub fn to_polygons_simplified(
+        &self,
+        tile_width: f32,
+        tile_height: f32,
+        tolerance: f32,
+    ) -> Vec<Vec<Point>> {
+        trace(self)
+            .into_iter()
+            .map(|ring| {
+                simplify(&ring, tolerance)
+                    .into_iter()
+                    .map(|(x, y)| Point {
+                        x: x * tile_width,
+                        y: y * tile_height,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Closed rings of cell corners between set and unset cells, with set cells
+/// on the right side.
+fn trace(grid: &BitGrid) -> Vec<Vec<Vertex>> {
+    let set = |x: i64, y: i64| x >= 0 && y >= 0 && grid.get(x as u32, y as u32);
+
+    let mut edges: HashMap<Vertex, Vec<Vertex>> = HashMap::new();
+    let mut starts = Vec::new();
+    for (x, y) in grid.iter() {
+        let (x, y) = (i64::from(x), i64::from(y));
+        let sides = [
+            (!set(x, y - 1), (x, y), (x + 1, y)),
+            (!set(x + 1, y), (x + 1, y), (x + 1, y + 1)),
+            (!set(x, y + 1), (x + 1, y + 1), (x, y + 1)),
+            (!set(x - 1, y), (x, y + 1), (x, y)),
+        ];
+        for (open, from, to) in sides.iter() {
+            if *open {
+                edges.entry(*from).or_default().push(*to);
+                starts.push(*from);
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+    for start in starts {
+        // The first edge stays in place until the ring comes back to it.
+        let first = match edges.get(&start).and_then(|targets| targets.first()) {
+            Some(first) => *first,
+            None => continue,
+        };
+
+        let (mut from, mut to) = (start, first);
+        let mut ring = vec![start];
+        loop {
+            let direction = (to.0 - from.0, to.1 - from.1);
+            from = to;
+            to = take_edge(&mut edges, from, direction).expect("boundary edges form closed rings");
+            if (from, to) == (start, first) {
+                break;
+            }
+            ring.push(from);
+        }
+        rings.push(ring);
+    }
+
+    rings
+}
+
+/// Remove an edge starting from `from`. At corners shared by diagonal cells
+/// the right turn is taken, which keeps the cells apart.
+fn take_edge(
+    edges: &mut HashMap<Vertex, Vec<Vertex>>,
+    from: Vertex,
+    direction: Vertex,
+) -> Option<Vertex> {
+    let targets = edges.get_mut(&from)?;
+    if targets.is_empty() {
+        return None;
+    }
+
+    let right = (from.0 - direction.1, from.1 + direction.0);
+    let index = targets.iter().position(|to| *to == right).unwrap_or(0);
+    Some(targets.swap_remove(index))
+}
+
+/// Drop collinear points, then points within `tolerance` of the outline.
+fn simplify(ring: &[Vertex], tolerance: f32) -> Vec<(f32, f32)> {
+    let len = ring.len();
+    let corners: Vec<(f32, f32)> = (0..len)
+        .filter(|i| {
+            let (prev, point, next) = (ring[(i + len - 1) % len], ring[*i], ring[(i + 1) % len]);
+            (point.0 - prev.0) * (next.1 - point.1) != (point.1 - prev.1) * (next.0 - point.0)
+        })
+        .map(|i| (ring[i].0 as f32, ring[i].1 as f32))
+        .collect();
+
+    if tolerance <= 0.0 || corners.len() <= 3 {
+        return corners;
+    }
+
+    // Split the ring at the point farthest from the first one.
+    let far = (1..corners.len())
+        .max_by(|a, b| {
+            distance(corners[0], corners[*a])
+                .partial_cmp(&distance(corners[0], corners[*b]))
+                .unwrap()
+        })
+        .unwrap();
+
+    let mut keep = vec![false; corners.len()];
+    keep[0] = true;
+    keep[far] = true;
+    douglas_peucker(&corners, 0, far, tolerance, &mut keep);
+    let mut tail: Vec<(f32, f32)> = corners[far..].to_vec();
+    tail.push(corners[0]);
+    let mut keep_tail = vec![false; tail.len()];
+    douglas_peucker(&tail, 0, tail.len() - 1, tolerance, &mut keep_tail);
+    for (i, kept) in keep_tail.iter().enumerate().take(tail.len() - 1) {
+        keep[far + i] |= *kept;
+    }
+
+    corners
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, kept)| *kept)
+        .map(|(point, _)| point)
+        .collect()
+}
+
+fn douglas_peucker(
+    points: &[(f32, f32)],
+    first: usize,
+    last: usize,
+    tolerance: f32,
+    keep: &mut [bool],
+) {
+    let farthest = (first + 1..last)
+        .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+    if let Some((index, distance)) = farthest {
+        if distance > tolerance {
+            keep[index] = true;
+            douglas_peucker(points, first, index, tolerance, keep);
+            douglas_peucker(points, index, last, tolerance, keep);
+        }
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// Distance from `point` to the line through `a` and `b`.
+fn segment_distance(point: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let length = distance(a, b);
+    if length == 0.0 {
+        return distance(point, a);
+    }
+
+    ((b.0 - a.0) * (a.1 - point.1) - (a.0 - point.0) * (b.1 - a.1)).abs() / length
+}
@@ -0,0 +1,116 @@
+//! Structures that many games encode in maps the same way: spawn points,
+//! exits to other maps and background music. The names of types and
+//! properties can be changed with `Conventions`, the `Map` methods use the
+//! defaults.
+
+use crate::{Map, Object, ObjectId, ObjectShape};
+
+/// Names of the object types and properties used by the conventions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Conventions {
+    spawn_type: String,
+    target_map: String,
+    target_spawn: String,
+    music: String,
+}
+
+impl Default for Conventions {
+    fn default() -> Self {
+        Conventions {
+            spawn_type: "spawn".to_string(),
+            target_map: "target_map".to_string(),
+            target_spawn: "target_spawn".to_string(),
+            music: "music".to_string(),
+        }
+    }
+}
+
+/// Object that leads to another map, see `Map::exits`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Exit {
+    /// ID of the exit object.
+    pub id: ObjectId,
+    /// Path or name of the target map.
+    pub target: String,
+    /// Name of the spawn point in the target map.
+    pub target_spawn: Option<String>,
+}
+
+impl Conventions {
+    /// Default names: type `"spawn"` and properties `"target_map"`,
+    /// `"target_spawn"` and `"music"`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Type (class) of spawn point objects.
+    pub fn spawn_type(mut self, name: &str) -> Self {
+        self.spawn_type = name.to_string();
+        self
+    }
+
+    /// Object property with the map an exit leads to.
+    pub fn target_map_property(mut self, name: &str) -> Self {
+        self.target_map = name.to_string();
+        self
+    }
+
+    /// Object property with the spawn point an exit leads to.
+    pub fn target_spawn_property(mut self, name: &str) -> Self {
+        self.target_spawn = name.to_string();
+        self
+    }
+
+    /// Map property with the background music.
+    pub fn music_property(mut self, name: &str) -> Self {
+        self.music = name.to_string();
+        self
+    }
+
+    /// Point objects of the spawn type, in layer order.
+    pub fn spawn_points<'a>(&self, map: &'a Map) -> Vec<&'a Object> {
+        map.objects()
+            .filter(|object| object.shape == ObjectShape::Point)
+            .filter(|object| object.r#type.as_str() == self.spawn_type)
+            .collect()
+    }
+
+    /// Objects with a string or file target map property, in layer order.
+    pub fn exits(&self, map: &Map) -> Vec<Exit> {
+        map.objects()
+            .filter_map(|object| {
+                let properties = object.properties.as_ref()?;
+                let string = |name: &str| Some(properties.get(name)?.as_str()?.to_string());
+
+                Some(Exit {
+                    id: object.id,
+                    target: string(&self.target_map)?,
+                    target_spawn: string(&self.target_spawn),
+                })
+            })
+            .collect()
+    }
+
+    /// Value of the string or file music property of the map.
+    pub fn music<'a>(&self, map: &'a Map) -> Option<&'a str> {
+        map.properties.as_ref()?.get(&self.music)?.as_str()
+    }
+}
+
+impl Map {
+    /// Spawn points with the default conventions, see
+    /// `Conventions::spawn_points`.
+    pub fn spawn_points(&self) -> Vec<&Object> {
+        Conventions::default().spawn_points(self)
+    }
+
+    /// Exits with the default conventions, see `Conventions::exits`.
+    pub fn exits(&self) -> Vec<Exit> {
+        Conventions::default().exits(self)
+    }
+
+    /// Music with the default conventions, see `Conventions::music`.
+    pub fn music(&self) -> Option<&str> {
+        Conventions::default().music(self)
+    }
+}
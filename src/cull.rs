@@ -0,0 +1,180 @@
+use crate::{Map, Orientation, StaggerAxis, StaggerIndex, TileLayer};
+
+/// Axis-aligned rectangle in map pixels.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct ViewRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        ViewRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn intersects(&self, x: f32, y: f32, width: f32, height: f32) -> bool {
+        x < self.x + self.width
+            && self.x < x + width
+            && y < self.y + self.height
+            && self.y < y + height
+    }
+}
+
+/// Tile placement of a map, same as the renderers of the Tiled editor.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Grid {
+    orientation: Orientation,
+    tile_width: f32,
+    tile_height: f32,
+    /// Height of the map in tiles, isometric maps are shifted right by it.
+    map_height: f32,
+    stagger_x: bool,
+    stagger_even: bool,
+    side_length: f32,
+}
+
+impl Grid {
+    pub(crate) fn new(map: &Map) -> Self {
+        Grid {
+            orientation: map.orientation,
+            tile_width: map.tile_width.max(1) as f32,
+            tile_height: map.tile_height.max(1) as f32,
+            map_height: map.height as f32,
+            stagger_x: map.stagger_axis == Some(StaggerAxis::X),
+            stagger_even: map.stagger_index == Some(StaggerIndex::Even),
+            side_length: match map.orientation {
+                Orientation::Hexagonal => map.hex_side_length.unwrap_or(0) as f32,
+                _ => 0.0,
+            },
+        }
+    }
+
+    /// Distance between columns and rows of staggered and hexagonal maps.
+    fn stagger_steps(&self) -> (f32, f32) {
+        if self.stagger_x {
+            ((self.tile_width + self.side_length) / 2.0, self.tile_height)
+        } else {
+            (self.tile_width, (self.tile_height + self.side_length) / 2.0)
+        }
+    }
+
+    fn is_staggered(&self, index: i32) -> bool {
+        (index & 1 == 1) != self.stagger_even
+    }
+
+    /// Top-left corner of the bounding box of a tile.
+    pub(crate) fn tile_origin(&self, x: i32, y: i32) -> (f32, f32) {
+        let (tx, ty) = (x as f32, y as f32);
+
+        match self.orientation {
+            Orientation::Orthogonal => (tx * self.tile_width, ty * self.tile_height),
+            Orientation::Isometric => (
+                (tx - ty) * self.tile_width / 2.0 + (self.map_height - 1.0) * self.tile_width / 2.0,
+                (tx + ty) * self.tile_height / 2.0,
+            ),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (column_width, row_height) = self.stagger_steps();
+                if self.stagger_x {
+                    let shift = if self.is_staggered(x) {
+                        row_height / 2.0
+                    } else {
+                        0.0
+                    };
+                    (tx * column_width, ty * row_height + shift)
+                } else {
+                    let shift = if self.is_staggered(y) {
+                        column_width / 2.0
+                    } else {
+                        0.0
+                    };
+                    (tx * column_width + shift, ty * row_height)
+                }
+            }
+        }
+    }
+
+    /// Tile coordinates of a pixel, not rounded.
+    pub(crate) fn tile_at(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.orientation {
+            Orientation::Orthogonal => (x / self.tile_width, y / self.tile_height),
+            Orientation::Isometric => {
+                let x = (x - self.map_height * self.tile_width / 2.0) / self.tile_width;
+                let y = y / self.tile_height;
+                (y + x, y - x)
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (column_width, row_height) = self.stagger_steps();
+                (x / column_width, y / row_height)
+            }
+        }
+    }
+
+    /// Tile range covering the view, with a margin for staggered rows.
+    fn range(&self, view: &ViewRect) -> ((i32, i32), (i32, i32)) {
+        let corners = [
+            self.tile_at(view.x, view.y),
+            self.tile_at(view.x + view.width, view.y),
+            self.tile_at(view.x, view.y + view.height),
+            self.tile_at(view.x + view.width, view.y + view.height),
+        ];
+
+        let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        (
+            (min_x.floor() as i32 - 1, max_x.ceil() as i32 + 1),
+            (min_y.floor() as i32 - 1, max_y.ceil() as i32 + 1),
+        )
+    }
+}
+
+impl TileLayer {
+    /// Non-empty tiles whose bounding box intersects `view`, as `(x, y, gid)`
+    /// in map tile coordinates (see `get_tile_signed`). Tile placement
+    /// follows the orientation of `map`. Tiles are assumed to be the map tile
+    /// size; grow the view for tilesets with larger tiles. Layers that are
+    /// still encoded have no tiles.
+    pub fn tiles_in_view(
+        &self,
+        view: ViewRect,
+        map: &Map,
+    ) -> impl Iterator<Item = (i32, i32, u32)> + '_ {
+        let grid = Grid::new(map);
+        let ((min_x, max_x), (min_y, max_y)) = grid.range(&view);
+
+        // Clamp to the layer so huge views don't iterate empty space.
+        let min_x = min_x.max(self.start_x());
+        let min_y = min_y.max(self.start_y());
+        let max_x = max_x.min(self.start_x() + self.width() as i32 - 1);
+        let mut max_y = max_y.min(self.start_y() + self.height() as i32 - 1);
+        if self.is_encoded() {
+            max_y = min_y - 1;
+        }
+
+        (min_y..=max_y)
+            .flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+            .filter(move |&(x, y)| {
+                let (left, top) = grid.tile_origin(x, y);
+                view.intersects(left, top, grid.tile_width, grid.tile_height)
+            })
+            .filter_map(move |(x, y)| {
+                let gid = self.get_tile_signed(x, y)?;
+                Some((x, y, gid)).filter(|_| gid != 0)
+            })
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::error::TiledError;
+use crate::{LayerType, Map};
+
+/// Progress of a `DecodeJob`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct DecodeProgress {
+    /// Layers decoded so far, including layers that failed.
+    pub decoded: usize,
+    /// Layers that were encoded when the job started.
+    pub total: usize,
+}
+
+impl DecodeProgress {
+    pub fn is_done(&self) -> bool {
+        self.decoded == self.total
+    }
+
+    /// Decoded part from 0.0 to 1.0, eg. for a progress bar.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.decoded as f32 / self.total as f32
+        }
+    }
+}
+
+/// Decodes tile layers kept by `ParseOptions::keep_encoded` a few at a time,
+/// so a single-threaded game can load a big map over several frames. A layer
+/// is decoded in one go, so a step takes at least as long as its largest
+/// layer.
+#[derive(Debug, Clone)]
+pub struct DecodeJob {
+    map: Map,
+    /// Indexes of layers left to decode, last one first.
+    pending: Vec<usize>,
+    total: usize,
+}
+
+impl DecodeJob {
+    pub fn new(map: Map) -> Self {
+        let mut pending: Vec<_> = map
+            .layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| match &layer.data {
+                LayerType::TileLayer(layer) => layer.is_encoded(),
+                _ => false,
+            })
+            .map(|(index, _)| index)
+            .collect();
+        pending.reverse();
+
+        DecodeJob {
+            total: pending.len(),
+            map,
+            pending,
+        }
+    }
+
+    pub fn progress(&self) -> DecodeProgress {
+        DecodeProgress {
+            decoded: self.total - self.pending.len(),
+            total: self.total,
+        }
+    }
+
+    /// Decode layers until `max_micros` microseconds have passed, at least
+    /// one layer per step. `std::time::Instant` isn't available on
+    /// `wasm32-unknown-unknown`, use `step_layers` there.
+    pub fn step(&mut self, max_micros: u64) -> Result<DecodeProgress, TiledError> {
+        let start = Instant::now();
+        let budget = Duration::from_micros(max_micros);
+
+        loop {
+            self.step_layers(1)?;
+            if self.pending.is_empty() || start.elapsed() >= budget {
+                return Ok(self.progress());
+            }
+        }
+    }
+
+    /// Decode up to `count` layers. A layer that fails to decode is reported
+    /// as `TiledError::Layer` and skipped, later steps continue with the
+    /// next layer.
+    pub fn step_layers(&mut self, count: usize) -> Result<DecodeProgress, TiledError> {
+        for _ in 0..count {
+            let index = match self.pending.pop() {
+                Some(index) => index,
+                None => break,
+            };
+
+            if let LayerType::TileLayer(layer) = &mut self.map.layers[index].data {
+                layer.decode().map_err(|err| TiledError::Layer {
+                    index,
+                    source: Box::new(err),
+                })?;
+            }
+        }
+
+        Ok(self.progress())
+    }
+
+    /// The map, with the layers decoded so far.
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// The map, with the layers decoded so far. Layers that aren't decoded
+    /// yet can be decoded later with `TileLayer::decode`.
+    pub fn into_map(self) -> Map {
+        self.map
+    }
+}
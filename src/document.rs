@@ -0,0 +1,383 @@
+//! Writing edited maps back with the text of unchanged parts preserved.
+
+use std::io::Write;
+use std::ops::Range;
+
+use serde_json::{json, Map as JsonObject, Value};
+
+use crate::error::TiledError;
+use crate::template::object_fields;
+use crate::utils::{Compression, Encoding};
+use crate::{parse_slice_with_options, LayerType, Map, Object, ParseOptions};
+
+/// Map together with the JSON text it was parsed from, for editors that
+/// write maps back to version controlled files. Changed fields of objects
+/// and tile data are rendered again when writing, everything else is copied
+/// from the original text so diffs stay small.
+///
+/// Only objects and tiles can be edited this way. Other changes, such as
+/// adding layers or objects or changing properties of layers, fail with
+/// `TiledError::Other` when writing.
+#[derive(Debug, Clone)]
+pub struct MapDocument {
+    source: String,
+    original: Map,
+    map: Map,
+    layers: Vec<LayerSpans>,
+}
+
+/// Where the parts of a layer that can be rendered again are in the text.
+#[derive(Debug, Clone, Default)]
+struct LayerSpans {
+    data: Option<Range<usize>>,
+    encoding: Option<Encoding>,
+    compression: Option<Compression>,
+    objects: Vec<Range<usize>>,
+}
+
+impl MapDocument {
+    /// Parse a map and remember its text.
+    pub fn parse(source: String) -> Result<Self, TiledError> {
+        let map = parse_slice_with_options(source.as_bytes(), &ParseOptions::default())?;
+        let layers = layer_spans(source.as_bytes());
+
+        if layers.len() != map.layers.len() {
+            return Err(TiledError::Other("Layers of the map not found".to_string()));
+        }
+
+        Ok(MapDocument {
+            source,
+            original: map.clone(),
+            map,
+            layers,
+        })
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut Map {
+        &mut self.map
+    }
+
+    /// Text the map was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The original text with changed objects and tile data replaced.
+    pub fn to_json_string(&self) -> Result<String, TiledError> {
+        let (original, map) = (&self.original, &self.map);
+        if !header_eq(original, map) || original.layers.len() != map.layers.len() {
+            return Err(TiledError::Other(
+                "Only objects and tiles can be written incrementally".to_string(),
+            ));
+        }
+
+        let mut edits = Vec::new();
+        for ((before, after), spans) in original.layers.iter().zip(&map.layers).zip(&self.layers) {
+            let unsupported =
+                || TiledError::Other(format!("Can't write changes of layer {:?}", after.name));
+
+            let same_layer = before.id == after.id
+                && before.name == after.name
+                && before.opacity == after.opacity
+                && before.visible == after.visible
+                && before.parallax_x == after.parallax_x
+                && before.parallax_y == after.parallax_y
+                && before.blend_mode == after.blend_mode
+                && before.tint_color == after.tint_color
+                && before.offset() == after.offset()
+                && before.properties == after.properties;
+            if !same_layer {
+                return Err(unsupported());
+            }
+
+            match (&before.data, &after.data) {
+                (LayerType::TileLayer(before), LayerType::TileLayer(after)) => {
+                    if before == after {
+                        continue;
+                    }
+
+                    let same_size = (before.width, before.height, before.start_x, before.start_y)
+                        == (after.width, after.height, after.start_x, after.start_y);
+                    let data = spans.data.clone().ok_or_else(unsupported)?;
+                    if !same_size {
+                        return Err(unsupported());
+                    }
+
+                    let text = match spans.encoding {
+                        Some(Encoding::Base64) => {
+                            let encoded = after.encode(spans.compression, None)?;
+                            json!(encoded.to_base64()).to_string()
+                        }
+                        _ => json!(after.tiles()).to_string(),
+                    };
+                    edits.push((data, text));
+                }
+                (LayerType::ObjectGroup(before), LayerType::ObjectGroup(after)) => {
+                    let same_group = before.color == after.color
+                        && (before.x, before.y) == (after.x, after.y)
+                        && (before.offset_x, before.offset_y) == (after.offset_x, after.offset_y)
+                        && before.objects.len() == after.objects.len()
+                        && before.objects.len() == spans.objects.len();
+                    if !same_group {
+                        return Err(unsupported());
+                    }
+
+                    for ((before, after), span) in before
+                        .objects
+                        .iter()
+                        .zip(&after.objects)
+                        .zip(&spans.objects)
+                    {
+                        if before != after {
+                            let bytes = self.source.as_bytes();
+                            edits.extend(object_edits(bytes, span, before, after)?);
+                        }
+                    }
+                }
+                (before, after) if before == after => {}
+                _ => return Err(unsupported()),
+            }
+        }
+
+        edits.sort_by_key(|(span, _)| span.start);
+        let mut text = String::with_capacity(self.source.len());
+        let mut position = 0;
+        for (span, replacement) in edits {
+            text.push_str(&self.source[position..span.start]);
+            text.push_str(&replacement);
+            position = span.end;
+        }
+        text.push_str(&self.source[position..]);
+
+        Ok(text)
+    }
+
+    /// Write the map, see `to_json_string`.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), TiledError> {
+        writer
+            .write_all(self.to_json_string()?.as_bytes())
+            .map_err(|err| TiledError::Other(format!("{:?}", err)))
+    }
+}
+
+/// Whether the fields of maps outside of layers are equal.
+fn header_eq(a: &Map, b: &Map) -> bool {
+    a.version == b.version
+        && a.orientation == b.orientation
+        && (a.width, a.height) == (b.width, b.height)
+        && (a.tile_width, a.tile_height) == (b.tile_width, b.tile_height)
+        && a.stagger_axis == b.stagger_axis
+        && a.stagger_index == b.stagger_index
+        && a.hex_side_length == b.hex_side_length
+        && a.tilesets == b.tilesets
+        && a.background_colour == b.background_colour
+        && (a.parallax_origin_x, a.parallax_origin_y) == (b.parallax_origin_x, b.parallax_origin_y)
+        && a.compression_level == b.compression_level
+        && a.class == b.class
+        && a.properties == b.properties
+}
+
+/// Object as Tiled JSON.
+fn object_json(object: &Object) -> Result<JsonObject<String, Value>, TiledError> {
+    let mut fields = object_fields(object)?;
+    fields.insert("id".to_string(), json!(object.id));
+    fields.insert("x".to_string(), json!(object.x));
+    fields.insert("y".to_string(), json!(object.y));
+    if let Some(gid) = object.gid {
+        fields.insert("gid".to_string(), json!(gid));
+    }
+
+    Ok(fields)
+}
+
+/// Replacements of the values of changed fields of an object. Fields can't
+/// be added or removed.
+fn object_edits(
+    bytes: &[u8],
+    span: &Range<usize>,
+    before: &Object,
+    after: &Object,
+) -> Result<Vec<(Range<usize>, String)>, TiledError> {
+    let (before_fields, after_fields) = (object_json(before)?, object_json(after)?);
+    let mut edits = Vec::new();
+    field_edits(bytes, span, &before_fields, &after_fields, &mut edits).map_err(|key| {
+        TiledError::Other(format!(
+            "Can't add or remove field {:?} of object {}",
+            key, after.id
+        ))
+    })?;
+
+    Ok(edits)
+}
+
+/// Replacements of changed fields of a JSON object. Only the changed members
+/// of nested objects, eg. the string of a text, are replaced so that other
+/// members are kept as written. Fails with the key of an added or removed
+/// field.
+fn field_edits(
+    bytes: &[u8],
+    span: &Range<usize>,
+    before: &JsonObject<String, Value>,
+    after: &JsonObject<String, Value>,
+    edits: &mut Vec<(Range<usize>, String)>,
+) -> Result<(), String> {
+    let members = members(bytes, span.clone());
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let value = after.get(key);
+        if before.get(key) == value {
+            continue;
+        }
+
+        let span = members
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, span)| span.clone());
+        match (span, before.get(key), value) {
+            (Some(span), Some(Value::Object(before)), Some(Value::Object(after))) => {
+                field_edits(bytes, &span, before, after, edits)?
+            }
+            (Some(span), _, Some(value)) => edits.push((span, integral(value).to_string())),
+            _ => return Err(key.clone()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Write whole numbers without fraction like Tiled does.
+fn integral(value: &Value) -> Value {
+    match value {
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if float.fract() == 0.0 && float.abs() < 1e15 => json!(float as i64),
+            _ => value.clone(),
+        },
+        Value::Array(values) => values.iter().map(integral).collect(),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), integral(value)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn layer_spans(bytes: &[u8]) -> Vec<LayerSpans> {
+    let root = value_span(bytes, skip_whitespace(bytes, 0));
+    let layers = match members(bytes, root)
+        .into_iter()
+        .find(|(key, _)| key == "layers")
+    {
+        Some((_, layers)) => layers,
+        None => return Vec::new(),
+    };
+
+    elements(bytes, layers)
+        .into_iter()
+        .map(|layer| {
+            let mut spans = LayerSpans::default();
+            for (key, value) in members(bytes, layer) {
+                match key.as_str() {
+                    "data" => spans.data = Some(value),
+                    "encoding" => spans.encoding = serde_json::from_slice(&bytes[value]).ok(),
+                    "compression" => spans.compression = serde_json::from_slice(&bytes[value]).ok(),
+                    "objects" => spans.objects = elements(bytes, value),
+                    _ => {}
+                }
+            }
+            spans
+        })
+        .collect()
+}
+
+fn skip_whitespace(bytes: &[u8], mut index: usize) -> usize {
+    while bytes.get(index).is_some_and(u8::is_ascii_whitespace) {
+        index += 1;
+    }
+    index
+}
+
+/// Span of the JSON value starting at `start`. The text must be valid JSON.
+fn value_span(bytes: &[u8], start: usize) -> Range<usize> {
+    let literal = !matches!(bytes.get(start), Some(b'"' | b'{' | b'['));
+    let mut index = start;
+    let mut depth = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => break,
+            b'}' | b']' => depth -= 1,
+            b',' if depth == 0 => break,
+            byte if depth == 0 && byte.is_ascii_whitespace() => break,
+            _ => {}
+        }
+        index += 1;
+
+        if depth == 0 && !literal {
+            break;
+        }
+    }
+
+    start..index.min(bytes.len())
+}
+
+/// Keys and value spans of an object.
+fn members(bytes: &[u8], object: Range<usize>) -> Vec<(String, Range<usize>)> {
+    let mut members = Vec::new();
+    let mut index = object.start + 1;
+
+    loop {
+        index = skip_whitespace(bytes, index);
+        if index >= object.end || bytes[index] != b'"' {
+            break;
+        }
+
+        let key_span = value_span(bytes, index);
+        let key = serde_json::from_slice(&bytes[key_span.clone()]).unwrap_or_default();
+        index = skip_whitespace(bytes, key_span.end);
+        index = skip_whitespace(bytes, index + 1);
+
+        let value = value_span(bytes, index);
+        index = skip_whitespace(bytes, value.end);
+        members.push((key, value));
+
+        if bytes.get(index) == Some(&b',') {
+            index += 1;
+        }
+    }
+
+    members
+}
+
+/// Spans of the elements of an array.
+fn elements(bytes: &[u8], array: Range<usize>) -> Vec<Range<usize>> {
+    let mut elements = Vec::new();
+    let mut index = skip_whitespace(bytes, array.start + 1);
+
+    while index < array.end && bytes[index] != b']' {
+        let value = value_span(bytes, index);
+        index = skip_whitespace(bytes, value.end);
+        elements.push(value);
+
+        if bytes.get(index) == Some(&b',') {
+            index = skip_whitespace(bytes, index + 1);
+        }
+    }
+
+    elements
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::io::Error;
+use std::path::PathBuf;
 
 /// Errors which occured when parsing the file
 #[derive(Debug)]
@@ -9,15 +10,111 @@ pub enum TiledError {
     DecompressingError(Error),
     ParsingError(serde_json::error::Error),
     Base64DecodingError(base64::DecodeError),
+    /// Compressed tile data is larger than allowed, see
+    /// `ParseOptions::max_decompressed_bytes`.
+    DecompressionLimitExceeded {
+        limit: usize,
+    },
+    /// A number field that couldn't be read as a finite number.
+    InvalidNumber {
+        field: String,
+        value: String,
+    },
+    /// The document isn't of the expected type, eg. a tileset was parsed as a
+    /// map.
+    WrongDocumentType {
+        expected: String,
+        found: String,
+    },
+    /// Error in a layer, see `parse_with_errors`.
+    Layer {
+        index: usize,
+        source: Box<TiledError>,
+    },
+    /// Error in an object of an object layer, see `parse_with_errors`.
+    Object {
+        index: usize,
+        source: Box<TiledError>,
+    },
+    /// Error in the value at `path`, eg. `layers[3].objects[17].width`.
+    Path {
+        path: String,
+        source: Box<TiledError>,
+    },
+    /// Error in an external tileset or template, see
+    /// `parse_file_with_externals`.
+    External {
+        path: PathBuf,
+        source: Box<TiledError>,
+    },
+    /// A field that isn't part of the Tiled format, see `ParseOptions::strict`.
+    UnknownField(String),
+    /// A property with a list value, which Tiled doesn't write, see
+    /// `ParseOptions::strict`.
+    NonStandardProperty(String),
+    /// Text encoding of the input isn't UTF-8 or UTF-16, see `parse_bytes`.
+    UnsupportedEncoding(String),
+    /// Something other than whitespace after the JSON document.
+    TrailingData {
+        line: usize,
+        column: usize,
+    },
     Other(String),
 }
 
+impl TiledError {
+    /// The error without context added by `Layer`, `Object`, `Path` and
+    /// `External`.
+    pub fn root_cause(&self) -> &TiledError {
+        match self {
+            TiledError::Layer { source, .. }
+            | TiledError::Object { source, .. }
+            | TiledError::Path { source, .. }
+            | TiledError::External { source, .. } => source.root_cause(),
+            err => err,
+        }
+    }
+}
+
 impl fmt::Display for TiledError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
             TiledError::DecompressingError(ref e) => write!(fmt, "{}", e),
             TiledError::ParsingError(ref e) => write!(fmt, "{}", e),
             TiledError::Base64DecodingError(ref e) => write!(fmt, "{}", e),
+            TiledError::DecompressionLimitExceeded { limit } => {
+                write!(fmt, "Decompressed tile data is larger than {} bytes", limit)
+            }
+            TiledError::InvalidNumber {
+                ref field,
+                ref value,
+            } => write!(fmt, "Invalid number {} for field {:?}", value, field),
+            TiledError::WrongDocumentType {
+                ref expected,
+                ref found,
+            } => write!(fmt, "Expected {} document, found {}", expected, found),
+            TiledError::Layer { index, ref source } => write!(fmt, "layer {}: {}", index, source),
+            TiledError::Object { index, ref source } => write!(fmt, "object {}: {}", index, source),
+            TiledError::Path {
+                ref path,
+                ref source,
+            } => write!(fmt, "{}: {}", path, source),
+            TiledError::External {
+                ref path,
+                ref source,
+            } => write!(fmt, "{}: {}", path.display(), source),
+            TiledError::UnknownField(ref field) => write!(fmt, "Unknown field {:?}", field),
+            TiledError::NonStandardProperty(ref name) => {
+                write!(fmt, "Property {:?} has a non-standard list value", name)
+            }
+            TiledError::UnsupportedEncoding(ref encoding) => {
+                write!(fmt, "Unsupported text encoding {}", encoding)
+            }
+            TiledError::TrailingData { line, column } => write!(
+                fmt,
+                "Unexpected data after the document at line {} column {}",
+                line, column
+            ),
             TiledError::Other(ref s) => write!(fmt, "{}", s),
         }
     }
@@ -25,20 +122,15 @@ impl fmt::Display for TiledError {
 
 // This is a skeleton implementation, which should probably be extended in the future.
 impl std::error::Error for TiledError {
-    fn description(&self) -> &str {
-        match *self {
-            TiledError::DecompressingError(ref e) => e.description(),
-            TiledError::ParsingError(ref e) => e.description(),
-            TiledError::Base64DecodingError(ref e) => e.description(),
-            TiledError::Other(ref s) => s.as_ref(),
-        }
-    }
-
-    fn cause(&self) -> Option<&dyn std::error::Error> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
-            TiledError::ParsingError(ref e) => Some(e as &dyn std::error::Error),
-            TiledError::DecompressingError(ref e) => Some(e as &dyn std::error::Error),
-            TiledError::Base64DecodingError(ref e) => Some(e as &dyn std::error::Error),
+            TiledError::ParsingError(ref e) => Some(e),
+            TiledError::DecompressingError(ref e) => Some(e),
+            TiledError::Base64DecodingError(ref e) => Some(e),
+            TiledError::Layer { ref source, .. }
+            | TiledError::Object { ref source, .. }
+            | TiledError::Path { ref source, .. }
+            | TiledError::External { ref source, .. } => Some(source.as_ref()),
             _ => None,
         }
     }
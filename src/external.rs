@@ -0,0 +1,278 @@
+//! Resolution of external tilesets and object templates.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use serde_json::{Map as JsonObject, Value};
+
+use crate::error::TiledError;
+#[cfg(feature = "http")]
+use crate::http;
+use crate::options::with_options;
+use crate::paths;
+use crate::tileset::GID_FLAGS;
+use crate::trace;
+use crate::utils::json_from_slice;
+use crate::{add_warnings, finish_map, map_from_value, Map, ParseOptions};
+
+/// Parse a map file and the external tilesets and object templates it
+/// references. Referenced files are loaded concurrently, each file once.
+/// Errors in a referenced file are reported as `TiledError::External`.
+///
+/// Objects take the fields of their template unless they override them.
+/// Properties are merged per name.
+pub fn parse_file_with_externals(path: &Path, options: &ParseOptions) -> Result<Map, TiledError> {
+    let mut value = read_json(path, options).map_err(|err| external_error(path, err))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut sources = Vec::new();
+    for tileset in tilesets_mut(&mut value) {
+        if let Some(source) = tileset.get("source").and_then(Value::as_str) {
+            sources.push(resolve(base, source));
+        }
+    }
+    for object in objects_mut(&mut value) {
+        if let Some(template) = object.get("template").and_then(Value::as_str) {
+            sources.push(resolve(base, template));
+        }
+    }
+    sources.sort();
+    sources.dedup();
+
+    let mut loaded = HashMap::new();
+    for (path, result) in sources.iter().zip(load_all(&sources, options)) {
+        loaded.insert(
+            path.clone(),
+            result.map_err(|err| external_error(path, err))?,
+        );
+    }
+
+    // First GIDs of map tilesets by file, to remap GIDs of tile templates.
+    let mut first_gids = HashMap::new();
+    for tileset in tilesets_mut(&mut value) {
+        let source = match tileset.get("source").and_then(Value::as_str) {
+            Some(source) => resolve(base, source),
+            None => continue,
+        };
+
+        if let Some(Value::Object(external)) = loaded.get(&source) {
+            let first_gid = tileset.get("firstgid").cloned().unwrap_or(Value::Null);
+            first_gids.insert(source, first_gid.as_u64().unwrap_or(0));
+
+            for (key, value) in external {
+                tileset.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            tileset.insert("firstgid".to_string(), first_gid);
+        }
+    }
+
+    for object in objects_mut(&mut value) {
+        let path = match object.get("template").and_then(Value::as_str) {
+            Some(template) => resolve(base, template),
+            None => continue,
+        };
+
+        apply_template(object, &path, &loaded[&path], &first_gids)
+            .map_err(|err| external_error(&path, err))?;
+    }
+
+    with_options(options, || map_from_value(value, options).map(add_warnings))
+        .and_then(|map| finish_map(map, options))
+}
+
+fn external_error(path: &Path, err: TiledError) -> TiledError {
+    TiledError::External {
+        path: path.to_path_buf(),
+        source: Box::new(err),
+    }
+}
+
+fn read_json(path: &Path, options: &ParseOptions) -> Result<Value, TiledError> {
+    trace::load(path, || {
+        #[cfg(feature = "http")]
+        {
+            let url = path.to_string_lossy();
+            if http::is_url(&url) {
+                let resolver = options.http.as_ref().ok_or_else(|| {
+                    TiledError::Other(format!("Can't load {} without ParseOptions::http", url))
+                })?;
+                return json_from_slice(&resolver.get(&url)?);
+            }
+        }
+        #[cfg(not(feature = "http"))]
+        let _ = options;
+
+        let bytes = fs::read(path).map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+        json_from_slice(&bytes)
+    })
+}
+
+/// Path of a file referenced from a file in `base`. URLs are kept as paths
+/// with the `http` feature.
+fn resolve(base: &Path, source: &str) -> PathBuf {
+    #[cfg(feature = "http")]
+    {
+        let base = base.to_string_lossy();
+        if http::is_url(source) || http::is_url(&base) {
+            return PathBuf::from(http::join(&base, source));
+        }
+    }
+
+    let path = paths::resolve(base, source);
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+/// Read files on up to one thread per core. Results are in the order of
+/// `paths`.
+fn load_all(paths: &[PathBuf], options: &ParseOptions) -> Vec<Result<Value, TiledError>> {
+    let workers = thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<_> = paths.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut loaded = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        match paths.get(index) {
+                            Some(path) => loaded.push((index, read_json(path, options))),
+                            None => return loaded,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            for (index, result) in handle.join().expect("loader thread panicked") {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every path is loaded"))
+        .collect()
+}
+
+fn tilesets_mut(map: &mut Value) -> impl Iterator<Item = &mut JsonObject<String, Value>> + '_ {
+    map.get_mut("tilesets")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// Objects of all object layers, including layers nested in groups.
+fn objects_mut(map: &mut Value) -> Vec<&mut JsonObject<String, Value>> {
+    fn visit<'a>(layers: &'a mut Value, objects: &mut Vec<&'a mut JsonObject<String, Value>>) {
+        for layer in layers.as_array_mut().into_iter().flatten() {
+            let layer = match layer.as_object_mut() {
+                Some(layer) => layer,
+                None => continue,
+            };
+
+            if layer.contains_key("objects") {
+                let layer_objects = layer.get_mut("objects").and_then(Value::as_array_mut);
+                objects.extend(
+                    layer_objects
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_object_mut),
+                );
+            } else if let Some(nested) = layer.get_mut("layers") {
+                visit(nested, objects);
+            }
+        }
+    }
+
+    let mut objects = Vec::new();
+    if let Some(layers) = map.get_mut("layers") {
+        visit(layers, &mut objects);
+    }
+    objects
+}
+
+fn apply_template(
+    object: &mut JsonObject<String, Value>,
+    path: &Path,
+    template: &Value,
+    first_gids: &HashMap<PathBuf, u64>,
+) -> Result<(), TiledError> {
+    let fields = template
+        .get("object")
+        .and_then(Value::as_object)
+        .ok_or_else(|| TiledError::Other("Template has no object".to_string()))?;
+
+    for (key, value) in fields {
+        match key.as_str() {
+            // Objects of templates have no ID of their own.
+            "id" => {}
+            "gid" if !object.contains_key("gid") => {
+                let gid = value.as_u64().unwrap_or(0);
+                object.insert(
+                    key.clone(),
+                    remap_gid(gid, path, template, first_gids)?.into(),
+                );
+            }
+            "properties" => match object.get_mut("properties") {
+                Some(Value::Array(properties)) => merge_properties(properties, value),
+                _ => {
+                    object.insert(key.clone(), value.clone());
+                }
+            },
+            _ => {
+                object.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// GID of a tile template in the map. Templates refer to tiles with GIDs of
+/// their own tileset reference.
+fn remap_gid(
+    gid: u64,
+    path: &Path,
+    template: &Value,
+    first_gids: &HashMap<PathBuf, u64>,
+) -> Result<u64, TiledError> {
+    let tileset = template.get("tileset");
+    let source = tileset
+        .and_then(|tileset| tileset.get("source"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| TiledError::Other("Tile template has no tileset".to_string()))?;
+    let template_first_gid = tileset
+        .and_then(|tileset| tileset.get("firstgid"))
+        .and_then(Value::as_u64)
+        .unwrap_or(1);
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let first_gid = first_gids
+        .get(&resolve(base, source))
+        .ok_or_else(|| TiledError::Other(format!("Tileset {} isn't used by the map", source)))?;
+
+    let flags = gid & u64::from(GID_FLAGS);
+    let local = (gid & !u64::from(GID_FLAGS)).saturating_sub(template_first_gid);
+    Ok((first_gid + local) | flags)
+}
+
+/// Add properties of a template that the object doesn't override.
+fn merge_properties(properties: &mut Vec<Value>, template: &Value) {
+    let name = |property: &Value| property.get("name").cloned();
+
+    for property in template.as_array().into_iter().flatten() {
+        if !properties.iter().any(|own| name(own) == name(property)) {
+            properties.push(property.clone());
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Field of view on tile layers.
+
+use crate::TileLayer;
+
+/// Tiles visible from a point, see `compute`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Visibility {
+    width: u32,
+    height: u32,
+    start_x: i32,
+    start_y: i32,
+    bits: Vec<u64>,
+}
+
+impl Visibility {
+    fn new(layer: &TileLayer) -> Self {
+        let len = layer.width() as usize * layer.height() as usize;
+
+        Visibility {
+            width: layer.width(),
+            height: layer.height(),
+            start_x: layer.start_x(),
+            start_y: layer.start_y(),
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// Bit index of a tile in map coordinates.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let x = i64::from(x) - i64::from(self.start_x);
+        let y = i64::from(y) - i64::from(self.start_y);
+
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return None;
+        }
+
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    fn set(&mut self, x: i32, y: i32) {
+        if let Some(index) = self.index(x, y) {
+            self.bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    /// Whether the tile at map coordinates is visible.
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        self.index(x, y)
+            .is_some_and(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Number of visible tiles.
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// Visibility as a bitmask with one bit per tile in row order, starting
+    /// from the least significant bit.
+    pub fn bits(&self) -> &[u64] {
+        &self.bits
+    }
+}
+
+/// Maps `(depth, column)` of a quadrant to `(x, y)` relative to the origin.
+type Transform = fn(i64, i64) -> (i64, i64);
+
+/// Slope `numerator / denominator` with a positive denominator.
+#[derive(Debug, Copy, Clone)]
+struct Slope(i64, i64);
+
+/// Row of tiles at `depth` from the origin between two slopes.
+#[derive(Debug, Copy, Clone)]
+struct Row {
+    depth: i64,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    fn columns(&self) -> std::ops::RangeInclusive<i64> {
+        // Round ties towards the center of the row.
+        let (Slope(n, d), depth) = (self.start, self.depth);
+        let min = (2 * depth * n + d).div_euclid(2 * d);
+        let Slope(n, d) = self.end;
+        let max = -(d - 2 * depth * n).div_euclid(2 * d);
+        min..=max
+    }
+
+    fn next(&self) -> Row {
+        Row {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+
+    /// Whether the center of the tile is inside the row, which keeps the
+    /// field of view symmetric.
+    fn is_symmetric(&self, column: i64) -> bool {
+        let (Slope(sn, sd), Slope(en, ed)) = (self.start, self.end);
+        column * sd >= self.depth * sn && column * ed <= self.depth * en
+    }
+}
+
+/// Compute tiles visible from `origin` (map coordinates) within `radius`
+/// tiles using symmetric shadowcasting. Tiles for which `is_opaque` returns
+/// true block vision but are visible themselves. Tiles outside the layer are
+/// opaque.
+pub fn compute<F>(layer: &TileLayer, origin: (i32, i32), radius: u32, is_opaque: F) -> Visibility
+where
+    F: Fn(u32) -> bool,
+{
+    let mut visibility = Visibility::new(layer);
+    visibility.set(origin.0, origin.1);
+
+    let radius = i64::from(radius);
+    let (ox, oy) = (i64::from(origin.0), i64::from(origin.1));
+
+    // North, east, south and west quadrants.
+    let quadrants: [Transform; 4] = [
+        |depth, column| (column, -depth),
+        |depth, column| (depth, column),
+        |depth, column| (column, depth),
+        |depth, column| (-depth, column),
+    ];
+
+    for transform in quadrants.iter() {
+        let tile = |depth: i64, column: i64| {
+            let (x, y) = transform(depth, column);
+            ((ox + x) as i32, (oy + y) as i32)
+        };
+        let is_wall = |depth: i64, column: i64| {
+            let (x, y) = tile(depth, column);
+            layer.get_tile_signed(x, y).is_none_or(&is_opaque)
+        };
+
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope(-1, 1),
+            end: Slope(1, 1),
+        }];
+
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+
+            let mut previous_wall = None;
+
+            for column in row.columns() {
+                let wall = is_wall(row.depth, column);
+                let in_radius = row.depth * row.depth + column * column <= radius * radius;
+
+                if in_radius && (wall || row.is_symmetric(column)) {
+                    let (x, y) = tile(row.depth, column);
+                    visibility.set(x, y);
+                }
+
+                let slope = Slope(2 * column - 1, 2 * row.depth);
+                match (previous_wall, wall) {
+                    (Some(true), false) => row.start = slope,
+                    (Some(false), true) => rows.push(Row {
+                        end: slope,
+                        ..row.next()
+                    }),
+                    _ => {}
+                }
+
+                previous_wall = Some(wall);
+            }
+
+            if previous_wall == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+
+    visibility
+}
@@ -0,0 +1,121 @@
+use serde_json::{json, Map as JsonMap, Value};
+
+use crate::{Object, ObjectGroup, ObjectShape, Point, Property};
+
+/// Number of segments used to approximate ellipses.
+const ELLIPSE_SEGMENTS: usize = 32;
+
+impl ObjectGroup {
+    /// Convert objects to a GeoJSON `FeatureCollection`.
+    ///
+    /// Coordinates are map pixels with y axis pointing down, like in Tiled.
+    /// Object rotation is applied, ellipses are approximated with polygons
+    /// and text objects are exported as their bounding rectangle.
+    pub fn to_geojson(&self) -> Value {
+        let features: Vec<Value> = self.objects.iter().map(Object::to_geojson).collect();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+}
+
+impl Object {
+    /// Convert object to a GeoJSON `Feature`. Name, type, id and custom
+    /// properties are written to feature properties.
+    pub fn to_geojson(&self) -> Value {
+        let mut properties = JsonMap::new();
+        properties.insert("id".to_string(), json!(self.id));
+        properties.insert("name".to_string(), json!(self.name));
+        properties.insert("type".to_string(), json!(self.r#type));
+
+        for (name, property) in self.properties.iter().flatten() {
+            properties.insert(name.clone(), property_to_json(property));
+        }
+
+        json!({
+            "type": "Feature",
+            "id": self.id,
+            "geometry": self.geometry(),
+            "properties": properties,
+        })
+    }
+
+    fn geometry(&self) -> Value {
+        let rect = |width: f32, height: f32| {
+            vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+        };
+
+        match &self.shape {
+            ObjectShape::Point => json!({
+                "type": "Point",
+                "coordinates": self.transform((0.0, 0.0)),
+            }),
+            ObjectShape::Rect { width, height } | ObjectShape::Text { width, height, .. } => {
+                self.polygon(rect(*width, *height))
+            }
+            ObjectShape::Ellipse { width, height } => {
+                let (rx, ry) = (width / 2.0, height / 2.0);
+                let points = (0..ELLIPSE_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::PI * 2.0;
+                        (rx + rx * angle.cos(), ry + ry * angle.sin())
+                    })
+                    .collect();
+
+                self.polygon(points)
+            }
+            ObjectShape::Polygon { points } => self.polygon(points_to_tuples(points)),
+            ObjectShape::Polyline { points } => {
+                let coordinates: Vec<Value> = points_to_tuples(points)
+                    .into_iter()
+                    .map(|point| self.transform(point))
+                    .collect();
+
+                json!({
+                    "type": "LineString",
+                    "coordinates": coordinates,
+                })
+            }
+            ObjectShape::Unknown(_) | ObjectShape::Invalid { .. } => Value::Null,
+        }
+    }
+
+    /// Closed polygon from points relative to object position.
+    fn polygon(&self, points: Vec<(f32, f32)>) -> Value {
+        let mut ring: Vec<Value> = points.iter().map(|point| self.transform(*point)).collect();
+
+        // GeoJSON rings are closed.
+        if let Some(first) = ring.first().cloned() {
+            ring.push(first);
+        }
+
+        json!({
+            "type": "Polygon",
+            "coordinates": [ring],
+        })
+    }
+
+    /// Point relative to the object in map coordinates, see
+    /// `Object::to_world`.
+    fn transform(&self, (x, y): (f32, f32)) -> Value {
+        let point = self.to_world(&Point { x, y });
+        json!([point.x, point.y])
+    }
+}
+
+fn points_to_tuples(points: &[Point]) -> Vec<(f32, f32)> {
+    points.iter().map(|point| (point.x, point.y)).collect()
+}
+
+fn property_to_json(property: &Property) -> Value {
+    match property {
+        Property::Bool(value) => json!(value),
+        Property::Float(value) => json!(value),
+        Property::Int(value) => json!(value),
+        Property::Color(value) => json!(value.to_hex()),
+        Property::String(value) | Property::File(value) => json!(value),
+        Property::List(values) => values.iter().map(property_to_json).collect(),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::TiledError;
+use crate::{Map, Tileset};
+
+/// Maximum length of a GID table. Tilesets written by Tiled are far smaller,
+/// larger tile counts come from broken or hostile files.
+const MAX_TABLE_LEN: u32 = 1 << 20;
+
+/// Everything needed to draw a GID, see `Map::build_gid_table`.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct ResolvedGid {
+    /// Index of the tileset in `Map::tilesets`. `None` for empty cells and
+    /// GIDs that don't belong to any tileset.
+    pub tileset: Option<usize>,
+    /// ID of the tile in the tileset.
+    pub local_id: u32,
+    /// Source rectangle in the tileset image, see `Tileset::tile_rect`.
+    pub rect: [u32; 4],
+    /// Texture coordinates, see `Tileset::tile_uv`.
+    pub uv: [f32; 4],
+    /// Index of the animated tile in `Tileset::tiles`.
+    pub animation: Option<usize>,
+}
+
+/// Number of tiles in a tileset, from the image size if the tile count is
+/// missing.
+fn tile_count(tileset: &Tileset, image_size: (u32, u32)) -> Option<u32> {
+    if let Some(count) = tileset.tile_count {
+        return Some(count);
+    }
+
+    let stride = tileset.tile_height.checked_add(tileset.spacing)?;
+    let rows = match stride {
+        0 => 0,
+        _ => {
+            let height = image_size
+                .1
+                .saturating_sub(tileset.margin.saturating_mul(2));
+            height.checked_add(tileset.spacing)? / stride
+        }
+    };
+    tileset.columns(image_size.0).checked_mul(rows)
+}
+
+impl Map {
+    /// Lookup table indexed by GID without flip flags, eg. the values of
+    /// `TileLayer::gids_masked`, so rendering doesn't need to search tilesets
+    /// for every cell. `image_sizes` are the sizes of tileset images in the
+    /// order of `tilesets`; tilesets without a size get zero rectangles and
+    /// texture coordinates. Flip flags are left to the cells.
+    ///
+    /// Fails if the tilesets have GIDs above 2^20, which would need a huge
+    /// table.
+    pub fn build_gid_table(
+        &self,
+        image_sizes: &[(u32, u32)],
+    ) -> Result<Vec<ResolvedGid>, TiledError> {
+        let mut order: Vec<usize> = (0..self.tilesets.len()).collect();
+        // Tilesets with a higher first GID win, same as `tileset_for_gid`.
+        order.sort_by_key(|index| self.tilesets[*index].first_gid);
+
+        let mut table = Vec::new();
+
+        for index in order {
+            let tileset = &self.tilesets[index];
+            let image_size = image_sizes.get(index).copied();
+            let too_large = || {
+                TiledError::Other(format!(
+                    "Tileset {:?} has GIDs above {}",
+                    tileset.name, MAX_TABLE_LEN
+                ))
+            };
+            let count = tile_count(tileset, image_size.unwrap_or((0, 0))).ok_or_else(too_large)?;
+            let end = tileset
+                .first_gid
+                .checked_add(count)
+                .filter(|end| *end <= MAX_TABLE_LEN)
+                .ok_or_else(too_large)? as usize;
+
+            if table.len() < end {
+                table.resize(end, ResolvedGid::default());
+            }
+
+            for local_id in 0..count {
+                table[(tileset.first_gid + local_id) as usize] = ResolvedGid {
+                    tileset: Some(index),
+                    local_id,
+                    rect: image_size.map_or([0; 4], |size| tileset.tile_rect(local_id, size)),
+                    uv: image_size.map_or([0.0; 4], |size| tileset.tile_uv(local_id, size)),
+                    animation: None,
+                };
+            }
+
+            for (tile_index, tile) in tileset.tiles.iter().flatten().enumerate() {
+                if tile.animation.is_some() && tile.id < count {
+                    table[(tileset.first_gid + tile.id) as usize].animation = Some(tile_index);
+                }
+            }
+        }
+
+        Ok(table)
+    }
+}
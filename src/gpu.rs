@@ -0,0 +1,78 @@
+use crate::{LayerType, Map};
+
+/// Data for drawing a tile layer in a shader from textures, see
+/// `Map::gpu_pack`.
+///
+/// A shader reads the GID of a cell from `cells`, swaps it for the current
+/// frame if `animations[gid]` has frames and then looks up the texture
+/// coordinates from `uvs`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct GpuPack {
+    /// Columns of `cells`.
+    pub width: u32,
+    /// Rows of `cells`.
+    pub height: u32,
+    /// GIDs of the cells in rows from the top, with flip flags in the top
+    /// bits as Tiled writes them. Fits a `R32Uint` texture.
+    pub cells: Vec<u32>,
+    /// Texture coordinates `[u0, v0, u1, v1]` indexed by GID without flip
+    /// flags, zero for GIDs without a tileset.
+    pub uvs: Vec<[f32; 4]>,
+    /// `[first_frame, frame_count, duration_ms]` indexed by GID without flip
+    /// flags, all zero for tiles that aren't animated. `first_frame` is an
+    /// index to `frames`.
+    pub animations: Vec<[u32; 3]>,
+    /// `[gid, end_ms]` of animation frames, where `end_ms` is the time the
+    /// frame ends since the start of its animation. The frame shown at
+    /// `time % duration_ms` is the first one that ends after it.
+    pub frames: Vec<[u32; 2]>,
+}
+
+impl Map {
+    /// Pack a tile layer for texture based rendering. `image_sizes` are the
+    /// sizes of tileset images, see `build_gid_table`. Returns `None` if
+    /// the layer isn't a tile layer, its tiles are still encoded or the GID
+    /// table can't be built.
+    pub fn gpu_pack(&self, layer: usize, image_sizes: &[(u32, u32)]) -> Option<GpuPack> {
+        let tile_layer = match &self.layers.get(layer)?.data {
+            LayerType::TileLayer(tile_layer) if !tile_layer.is_encoded() => tile_layer,
+            _ => return None,
+        };
+
+        let uvs: Vec<[f32; 4]> = self
+            .build_gid_table(image_sizes)
+            .ok()?
+            .iter()
+            .map(|resolved| resolved.uv)
+            .collect();
+
+        let mut animations = vec![[0; 3]; uvs.len()];
+        let mut frames = Vec::new();
+        for animation in self.animations() {
+            let slot = match animations.get_mut(animation.gid as usize) {
+                Some(slot) => slot,
+                None => continue,
+            };
+
+            *slot = [
+                frames.len() as u32,
+                animation.frames.len() as u32,
+                animation.duration_ms(),
+            ];
+            let mut end = 0;
+            for (gid, duration) in &animation.frames {
+                end += duration;
+                frames.push([*gid, end]);
+            }
+        }
+
+        Some(GpuPack {
+            width: tile_layer.width(),
+            height: tile_layer.height(),
+            cells: tile_layer.tiles().to_vec(),
+            uvs,
+            animations,
+            frames,
+        })
+    }
+}
@@ -0,0 +1,438 @@
+use std::hash::{Hash, Hasher};
+
+use crate::tileset::{Tile, Tileset};
+use crate::wang::{Terrain, WangColor, WangSet};
+use crate::{
+    Color, ImageLayer, Layer, LayerType, Map, Object, ObjectGroup, ObjectShape, Point, Properties,
+    Property, Text, TileLayer,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a hasher.
+///
+/// Unlike `DefaultHasher` the output doesn't depend on the platform or on the
+/// Rust version, so content hashes can be persisted between builds and
+/// compared between peers, eg. in lockstep multiplayer games.
+#[derive(Debug, Clone, Copy)]
+pub struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        ContentHasher(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // Integers are always written as little endian to keep hashes portable.
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+}
+
+/// Hash content of a type in a way that is stable across runs.
+///
+/// `Hash` can't be derived for most of the map types because they contain
+/// floats and hash maps.
+pub(crate) trait ContentHash {
+    fn hash_content<H: Hasher>(&self, state: &mut H);
+}
+
+/// Compute content hash of a value with `ContentHasher`.
+pub(crate) fn content_hash<T: ContentHash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = ContentHasher::default();
+    value.hash_content(&mut hasher);
+    hasher.finish()
+}
+
+impl ContentHash for f32 {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        // Treat 0.0 and -0.0 as the same value, and all NaNs as one NaN.
+        let value = if *self == 0.0 {
+            0.0f32
+        } else if self.is_nan() {
+            f32::NAN
+        } else {
+            *self
+        };
+        value.to_bits().hash(state);
+    }
+}
+
+impl<T: ContentHash> ContentHash for Option<T> {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Some(value) => {
+                state.write_u8(1);
+                value.hash_content(state);
+            }
+            None => state.write_u8(0),
+        }
+    }
+}
+
+impl<T: ContentHash> ContentHash for [T] {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.hash_content(state);
+        }
+    }
+}
+
+impl<T: ContentHash> ContentHash for Vec<T> {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self[..].hash_content(state);
+    }
+}
+
+impl ContentHash for Color {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.hash(state);
+    }
+}
+
+impl ContentHash for Property {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Property::Bool(value) => {
+                state.write_u8(0);
+                value.hash(state);
+            }
+            Property::Float(value) => {
+                state.write_u8(1);
+                value.hash_content(state);
+            }
+            Property::Int(value) => {
+                state.write_u8(2);
+                value.hash(state);
+            }
+            Property::Color(value) => {
+                state.write_u8(3);
+                value.hash_content(state);
+            }
+            Property::String(value) => {
+                state.write_u8(4);
+                value.hash(state);
+            }
+            Property::File(value) => {
+                state.write_u8(5);
+                value.hash(state);
+            }
+            Property::List(values) => {
+                state.write_u8(6);
+                values.hash_content(state);
+            }
+        }
+    }
+}
+
+impl ContentHash for Properties {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        // Sort by name so that hash doesn't depend on the iteration order.
+        let mut properties: Vec<_> = self.iter().collect();
+        properties.sort_by(|a, b| a.0.cmp(b.0));
+
+        properties.len().hash(state);
+        for (name, property) in properties {
+            name.hash(state);
+            property.hash_content(state);
+        }
+    }
+}
+
+impl ContentHash for Point {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.x.hash_content(state);
+        self.y.hash_content(state);
+    }
+}
+
+impl ContentHash for Text {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.text.hash(state);
+        self.wrap.hash(state);
+        self.font_family.hash(state);
+        self.pixel_size.hash(state);
+        // Skipped when empty so that hashes of plain texts don't change.
+        if !self.other.is_empty() {
+            serde_json::Value::Object(self.other.clone())
+                .to_string()
+                .hash(state);
+        }
+    }
+}
+
+impl ContentHash for ObjectShape {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ObjectShape::Point => state.write_u8(0),
+            ObjectShape::Rect { width, height } => {
+                state.write_u8(1);
+                width.hash_content(state);
+                height.hash_content(state);
+            }
+            ObjectShape::Ellipse { width, height } => {
+                state.write_u8(2);
+                width.hash_content(state);
+                height.hash_content(state);
+            }
+            ObjectShape::Polyline { points } => {
+                state.write_u8(3);
+                points.hash_content(state);
+            }
+            ObjectShape::Polygon { points } => {
+                state.write_u8(4);
+                points.hash_content(state);
+            }
+            ObjectShape::Text {
+                text,
+                width,
+                height,
+            } => {
+                state.write_u8(5);
+                text.hash_content(state);
+                width.hash_content(state);
+                height.hash_content(state);
+            }
+            ObjectShape::Unknown(value) => {
+                state.write_u8(6);
+                value.to_string().hash(state);
+            }
+            ObjectShape::Invalid { index } => {
+                state.write_u8(7);
+                index.hash(state);
+            }
+        }
+    }
+}
+
+impl ContentHash for Object {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.name.hash(state);
+        self.r#type.hash(state);
+        self.x.hash_content(state);
+        self.y.hash_content(state);
+        self.rotation.hash_content(state);
+        self.visible.hash(state);
+        self.gid.hash(state);
+        self.template.hash(state);
+        self.shape.hash_content(state);
+        self.properties.hash_content(state);
+    }
+}
+
+impl ContentHash for ObjectGroup {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.objects.hash_content(state);
+        self.color.hash_content(state);
+        self.x.hash_content(state);
+        self.y.hash_content(state);
+        self.offset_x.hash_content(state);
+        self.offset_y.hash_content(state);
+    }
+}
+
+impl ContentHash for ImageLayer {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.offset_x.hash_content(state);
+        self.offset_y.hash_content(state);
+        self.transparent_color.hash_content(state);
+        self.image.hash(state);
+    }
+}
+
+impl ContentHash for TileLayer {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        self.start_x.hash(state);
+        self.start_y.hash(state);
+        self.offset.0.hash_content(state);
+        self.offset.1.hash_content(state);
+        hash_tiles(&self.tiles, state);
+        match &self.encoded {
+            Some(encoded) => {
+                state.write_u8(1);
+                (encoded.encoding as u8).hash(state);
+                encoded.compression.map(|c| c as u8).hash(state);
+                encoded.bytes.hash(state);
+            }
+            None => state.write_u8(0),
+        }
+    }
+}
+
+fn hash_tiles<H: Hasher>(tiles: &[u32], state: &mut H) {
+    // `Hash` for integer slices writes native endian bytes.
+    tiles.len().hash(state);
+    for tile in tiles {
+        state.write_u32(*tile);
+    }
+}
+
+/// Hash of the size and tiles of a tile layer without its position, see
+/// `Map::dedup_identical_layers`.
+pub(crate) fn tile_data_hash(layer: &TileLayer) -> u64 {
+    let mut hasher = ContentHasher::default();
+    layer.width.hash(&mut hasher);
+    layer.height.hash(&mut hasher);
+    hash_tiles(&layer.tiles, &mut hasher);
+    hasher.finish()
+}
+
+impl ContentHash for LayerType {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        match self {
+            LayerType::TileLayer(layer) => {
+                state.write_u8(0);
+                layer.hash_content(state);
+            }
+            LayerType::ImageLayer(layer) => {
+                state.write_u8(1);
+                layer.hash_content(state);
+            }
+            LayerType::ObjectGroup(group) => {
+                state.write_u8(2);
+                group.hash_content(state);
+            }
+            LayerType::Invalid { index } => {
+                state.write_u8(3);
+                index.hash(state);
+            }
+        }
+    }
+}
+
+impl ContentHash for Layer {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.name.hash(state);
+        self.opacity.hash_content(state);
+        self.visible.hash(state);
+        self.parallax_x.hash_content(state);
+        self.parallax_y.hash_content(state);
+        self.blend_mode.hash(state);
+        self.tint_color.hash_content(state);
+        self.data.hash_content(state);
+        self.properties.hash_content(state);
+    }
+}
+
+impl ContentHash for Tile {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.properties.hash_content(state);
+        self.animation.hash(state);
+        // `Hash` for integer arrays writes native endian bytes.
+        match self.terrain {
+            Some(terrain) => {
+                state.write_u8(1);
+                for corner in terrain.iter() {
+                    state.write_i32(*corner);
+                }
+            }
+            None => state.write_u8(0),
+        }
+        self.object_group.hash_content(state);
+    }
+}
+
+impl ContentHash for Terrain {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.tile.hash(state);
+        self.properties.hash_content(state);
+    }
+}
+
+impl ContentHash for WangColor {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.color.hash_content(state);
+        self.tile.hash(state);
+        self.probability.hash_content(state);
+        self.properties.hash_content(state);
+    }
+}
+
+impl ContentHash for WangSet {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.wang_type.hash(state);
+        self.tile.hash(state);
+        self.colors.hash_content(state);
+        self.wang_tiles.hash(state);
+        self.properties.hash_content(state);
+    }
+}
+
+impl ContentHash for Tileset {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.first_gid.hash(state);
+        self.name.hash(state);
+        self.tile_width.hash(state);
+        self.tile_height.hash(state);
+        self.spacing.hash(state);
+        self.margin.hash(state);
+        self.image.hash(state);
+        self.tile_count.hash(state);
+        self.tiles.hash_content(state);
+        self.properties.hash_content(state);
+        self.terrains.hash_content(state);
+        self.wang_sets.hash_content(state);
+        self.source.hash(state);
+    }
+}
+
+impl ContentHash for Map {
+    fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.version.hash(state);
+        (self.orientation as u8).hash(state);
+        self.width.hash(state);
+        self.height.hash(state);
+        self.tile_width.hash(state);
+        self.tile_height.hash(state);
+        self.stagger_axis.hash(state);
+        self.stagger_index.hash(state);
+        self.hex_side_length.hash(state);
+        self.tilesets.hash_content(state);
+        self.layers.hash_content(state);
+        self.background_colour.hash_content(state);
+        self.parallax_origin_x.hash_content(state);
+        self.parallax_origin_y.hash_content(state);
+        self.compression_level.hash(state);
+        self.class.hash(state);
+        self.properties.hash_content(state);
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::Read;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::error::TiledError;
+use crate::options::{with_options, ParseOptions};
+use crate::utils::{deserialize_version, json_from_reader, TypeChecked};
+use crate::Orientation;
+
+/// Map metadata without tile data or objects, see `parse_header`.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(remote = "Self")]
+pub struct MapHeader {
+    /// File format version
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version: String,
+    pub orientation: Orientation,
+    /// Number of tile columns
+    pub width: u32,
+    /// Number of tile rows
+    pub height: u32,
+    /// Map grid width
+    #[serde(rename = "tilewidth")]
+    pub tile_width: u32,
+    /// Map grid height
+    #[serde(rename = "tileheight")]
+    pub tile_height: u32,
+    /// Whether the map is infinite. Defaults to false.
+    #[serde(default)]
+    pub infinite: bool,
+    /// Class of the map, since Tiled 1.9.
+    #[serde(default)]
+    pub class: Option<String>,
+    pub tilesets: Vec<TilesetHeader>,
+    pub layers: Vec<LayerHeader>,
+}
+
+impl<'de> Deserialize<'de> for MapHeader {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        MapHeader::deserialize(TypeChecked(deserializer))
+    }
+}
+
+/// Tileset reference of a map header.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct TilesetHeader {
+    /// GID corresponding to the first tile in the set
+    #[serde(rename = "firstgid")]
+    pub first_gid: u32,
+    /// Name of an embedded tileset
+    pub name: Option<String>,
+    /// Path of an external tileset
+    pub source: Option<String>,
+}
+
+/// Layer of a map header.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct LayerHeader {
+    /// The name of the layer.
+    pub name: String,
+    /// Layer type as written by Tiled, eg. `tilelayer` or `objectgroup`.
+    #[serde(rename = "type")]
+    pub layer_type: String,
+    /// Whether the layer is shown or hidden.
+    pub visible: bool,
+}
+
+/// Read only the metadata of a map: dimensions, orientation, tileset
+/// references and layer names and types. Tile data isn't decoded and objects
+/// aren't parsed, which makes this much cheaper than `parse` for listing maps.
+pub fn parse_header<R: Read>(reader: R) -> Result<MapHeader, TiledError> {
+    with_options(&ParseOptions::default(), || json_from_reader(reader))
+}
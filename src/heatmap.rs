@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::error::TiledError;
+use crate::tileset::GID_FLAGS;
+use crate::{LayerType, Map, TileLayer};
+
+/// Output format of `Map::export_gid_heatmap`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum HeatmapFormat {
+    /// One row of counts per tile row.
+    Csv,
+    /// 8-bit grayscale PNG with one pixel per tile. The cell with the most
+    /// uses is white.
+    Png,
+}
+
+impl Map {
+    /// Number of cells using each GID in all tile layers, flip flags
+    /// ignored. Empty cells aren't counted. Layers that are still encoded
+    /// are ignored.
+    pub fn gid_histogram(&self) -> BTreeMap<u32, usize> {
+        let mut histogram = BTreeMap::new();
+        for layer in self.decoded_tile_layers() {
+            for gid in layer.gids_masked().filter(|gid| *gid != 0) {
+                *histogram.entry(gid).or_insert(0) += 1;
+            }
+        }
+        histogram
+    }
+
+    /// How many tile layers use `gid` (flip flags ignored) in each cell of
+    /// the map, row by row. Cells of infinite layers outside the map are
+    /// ignored.
+    pub fn gid_heatmap(&self, gid: u32) -> Vec<u32> {
+        let gid = gid & !GID_FLAGS;
+        let mut counts = vec![0; self.width as usize * self.height as usize];
+
+        for layer in self.decoded_tile_layers() {
+            for (index, count) in counts.iter_mut().enumerate() {
+                let x = (index % self.width as usize) as i32;
+                let y = (index / self.width as usize) as i32;
+                if layer.get_tile_signed(x, y).map(|tile| tile & !GID_FLAGS) == Some(gid) {
+                    *count += 1;
+                }
+            }
+        }
+
+        counts
+    }
+
+    /// Write the heatmap of `gid`, see `gid_heatmap`, eg. to find stray
+    /// tiles of the wrong tileset.
+    pub fn export_gid_heatmap<W: Write>(
+        &self,
+        gid: u32,
+        mut writer: W,
+        format: HeatmapFormat,
+    ) -> Result<(), TiledError> {
+        let counts = self.gid_heatmap(gid);
+        let width = self.width.max(1) as usize;
+
+        match format {
+            HeatmapFormat::Csv => counts.chunks(width).try_for_each(|row| {
+                let row: Vec<_> = row.iter().map(u32::to_string).collect();
+                writeln!(writer, "{}", row.join(","))
+            }),
+            HeatmapFormat::Png => {
+                let max = counts.iter().copied().max().unwrap_or(0).max(1);
+                let pixels: Vec<u8> = counts
+                    .iter()
+                    .map(|count| (count * 255 / max) as u8)
+                    .collect();
+                write_png(&mut writer, self.width, self.height, &pixels)
+            }
+        }
+        .map_err(|err| TiledError::Other(format!("{:?}", err)))
+    }
+
+    fn decoded_tile_layers(&self) -> impl Iterator<Item = &TileLayer> {
+        self.layers.iter().filter_map(|layer| match &layer.data {
+            LayerType::TileLayer(tiles) if !tiles.is_encoded() => Some(tiles),
+            _ => None,
+        })
+    }
+}
+
+/// Write an 8-bit grayscale PNG.
+fn write_png<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> std::io::Result<()> {
+    // Each row starts with filter type 0 (none).
+    let mut raw = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width.max(1) as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut encoder = libflate::zlib::Encoder::new(Vec::new())?;
+    encoder.write_all(&raw)?;
+    let data = encoder.finish().into_result()?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, grayscale, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_chunk(writer, b"IHDR", &header)?;
+    write_chunk(writer, b"IDAT", &data)?;
+    write_chunk(writer, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+/// CRC-32 as used by PNG.
+fn crc32<'a, I: Iterator<Item = &'a u8>>(bytes: I) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,83 @@
+use crate::{Object, ObjectShape, Point};
+
+/// Distance in pixels within which points and polylines are hit by
+/// `Object::contains_point`.
+const DEFAULT_TOLERANCE: f32 = 1.0;
+
+impl Object {
+    /// Whether a point in map pixels is inside the object. Object rotation
+    /// is taken into account. Points and polylines have no area, they are hit
+    /// within one pixel; use `hit_test` for another tolerance.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        self.hit_test(x, y, DEFAULT_TOLERANCE)
+    }
+
+    /// Like `contains_point`, but points and polylines are hit within
+    /// `tolerance` pixels.
+    pub fn hit_test(&self, x: f32, y: f32, tolerance: f32) -> bool {
+        let (x, y) = self.to_local(x, y);
+
+        match &self.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Text { width, height, .. } => {
+                // Tile objects are aligned to their bottom left corner.
+                let y = if self.gid.is_some() { y + height } else { y };
+                (0.0..=*width).contains(&x) && (0.0..=*height).contains(&y)
+            }
+            ObjectShape::Ellipse { width, height } => {
+                if *width <= 0.0 || *height <= 0.0 {
+                    return false;
+                }
+
+                let (rx, ry) = (width / 2.0, height / 2.0);
+                let (dx, dy) = ((x - rx) / rx, (y - ry) / ry);
+                dx * dx + dy * dy <= 1.0
+            }
+            ObjectShape::Polygon { points } => winding_number(points, x, y) != 0,
+            ObjectShape::Polyline { points } => points
+                .windows(2)
+                .any(|segment| distance_to_segment(&segment[0], &segment[1], x, y) <= tolerance),
+            ObjectShape::Point => x.hypot(y) <= tolerance,
+            ObjectShape::Unknown(_) | ObjectShape::Invalid { .. } => false,
+        }
+    }
+
+    /// Move a point in map pixels to unrotated coordinates relative to the
+    /// object position.
+    fn to_local(&self, x: f32, y: f32) -> (f32, f32) {
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (dx * cos + dy * sin, dy * cos - dx * sin)
+    }
+}
+
+/// Non-zero for points inside the polygon.
+fn winding_number(points: &[Point], x: f32, y: f32) -> i32 {
+    let mut winding = 0;
+
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let side = (b.x - a.x) * (y - a.y) - (x - a.x) * (b.y - a.y);
+
+        if a.y <= y {
+            if b.y > y && side > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding
+}
+
+fn distance_to_segment(a: &Point, b: &Point, x: f32, y: f32) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+
+    let t = if length > 0.0 {
+        (((x - a.x) * dx + (y - a.y) * dy) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (x - (a.x + t * dx)).hypot(y - (a.y + t * dy))
+}
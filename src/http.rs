@@ -0,0 +1,108 @@
+//! External tilesets and templates referenced by URL, enabled with the `http`
+//! feature. The crate has no HTTP client of its own, requests are made by a
+//! function given by the application.
+
+use std::fmt;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::TiledError;
+use crate::hash::ContentHasher;
+
+type Fetch = dyn Fn(&str) -> Result<Vec<u8>, TiledError> + Send + Sync;
+
+/// Loads files referenced with `http://` or `https://` URLs in
+/// `parse_file_with_externals`, see `ParseOptions::http`.
+#[derive(Clone)]
+pub struct HttpResolver {
+    fetch: Arc<Fetch>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl HttpResolver {
+    /// `fetch` returns the body of a successful GET request of the URL.
+    pub fn new<F>(fetch: F) -> Self
+    where
+        F: Fn(&str) -> Result<Vec<u8>, TiledError> + Send + Sync + 'static,
+    {
+        HttpResolver {
+            fetch: Arc::new(fetch),
+            cache_dir: None,
+        }
+    }
+
+    /// Keep downloaded files in `dir` and only fetch files that aren't there.
+    /// The directory is created when needed.
+    pub fn cache_dir(mut self, dir: &Path) -> Self {
+        self.cache_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Contents of the file at `url`.
+    pub(crate) fn get(&self, url: &str) -> Result<Vec<u8>, TiledError> {
+        let cached = self.cache_dir.as_ref().map(|dir| {
+            let mut hasher = ContentHasher::default();
+            hasher.write(url.as_bytes());
+            dir.join(format!("{:016x}", hasher.finish()))
+        });
+
+        if let Some(bytes) = cached.as_ref().and_then(|path| fs::read(path).ok()) {
+            return Ok(bytes);
+        }
+
+        let bytes = (self.fetch)(url)?;
+        if let Some(path) = cached {
+            let io_error = |err| TiledError::Other(format!("{:?}", err));
+            fs::create_dir_all(path.parent().unwrap_or(&path)).map_err(io_error)?;
+            fs::write(&path, &bytes).map_err(io_error)?;
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl fmt::Debug for HttpResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpResolver")
+            .field("cache_dir", &self.cache_dir)
+            .finish()
+    }
+}
+
+/// Whether `source` is an absolute `http` or `https` URL.
+pub(crate) fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// URL of `source` referenced from a file in the `base` URL directory.
+pub(crate) fn join(base: &str, source: &str) -> String {
+    if is_url(source) {
+        return source.to_string();
+    }
+
+    let (origin, path) = match base.find("://").map(|scheme| scheme + 3) {
+        Some(start) => match base[start..].find('/') {
+            Some(slash) => base.split_at(start + slash),
+            None => (base, ""),
+        },
+        None => ("", base),
+    };
+
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if source.starts_with('/') {
+        segments.clear();
+    }
+    for segment in source.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    format!("{}/{}", origin, segments.join("/"))
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::TiledError;
+use crate::paths;
+use crate::{LayerType, Map};
+
+/// Image referenced by a tileset or an image layer, see `Map::load_images`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ImageSource {
+    /// Path as written in the map.
+    pub source: String,
+    /// Path resolved against the directory given to `Map::load_images`, see
+    /// `paths::resolve`.
+    pub path: PathBuf,
+}
+
+/// Loaded images by source, returned by `Map::load_images`.
+#[derive(Debug, Clone)]
+pub struct ImageHandles<T> {
+    indexes: HashMap<String, usize>,
+    handles: Vec<T>,
+}
+
+impl<T> ImageHandles<T> {
+    /// Handle of an image by its path as written in the map, eg.
+    /// `tileset.image`.
+    pub fn get(&self, source: &str) -> Option<&T> {
+        self.indexes.get(source).map(|index| &self.handles[*index])
+    }
+
+    /// Number of unique images.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Handles in the order the images were loaded.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.handles.iter()
+    }
+}
+
+impl Map {
+    /// Load images of tilesets and image layers with `loader`, eg. to create
+    /// textures. Paths are resolved against `base_dir`, usually the directory
+    /// of the map file. The loader is called once per image, even if the
+    /// image is used several times or with differently written paths.
+    pub fn load_images<T, F>(
+        &self,
+        base_dir: &Path,
+        mut loader: F,
+    ) -> Result<ImageHandles<T>, TiledError>
+    where
+        F: FnMut(&ImageSource) -> Result<T, TiledError>,
+    {
+        let tileset_images = self.tilesets.iter().map(|tileset| &tileset.image);
+        let layer_images = self.layers.iter().filter_map(|layer| match &layer.data {
+            LayerType::ImageLayer(image) => Some(&image.image),
+            _ => None,
+        });
+
+        let mut handles = ImageHandles {
+            indexes: HashMap::new(),
+            handles: Vec::new(),
+        };
+        let mut by_path = HashMap::new();
+
+        for source in tileset_images.chain(layer_images) {
+            if source.is_empty() || handles.indexes.contains_key(source) {
+                continue;
+            }
+
+            let image = ImageSource {
+                source: source.clone(),
+                path: paths::resolve(base_dir, source),
+            };
+
+            let index = match by_path.get(&image.path) {
+                Some(index) => *index,
+                None => {
+                    handles.handles.push(loader(&image)?);
+                    by_path.insert(image.path, handles.handles.len() - 1);
+                    handles.handles.len() - 1
+                }
+            };
+            handles.indexes.insert(image.source, index);
+        }
+
+        Ok(handles)
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::{Layer, LayerType, Object};
+
+/// Unique ID of an object, see `Object::id`.
+pub type ObjectId = u32;
+
+/// Key used to look up objects from `ObjectIndex`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ObjectKey<'a> {
+    Name(&'a str),
+    Type(&'a str),
+    /// Objects that have a custom property with the name.
+    Property(&'a str),
+}
+
+/// Indexes of objects by name, type and property names.
+///
+/// The index must be rebuilt after objects are added, removed or renamed.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ObjectIndex {
+    by_name: HashMap<String, Vec<ObjectId>>,
+    by_type: HashMap<String, Vec<ObjectId>>,
+    by_property: HashMap<String, Vec<ObjectId>>,
+    /// Layer and object index of each object.
+    locations: HashMap<ObjectId, (usize, usize)>,
+}
+
+impl ObjectIndex {
+    /// Build indexes of objects in object layers.
+    pub fn build(layers: &[Layer]) -> Self {
+        let mut index = ObjectIndex::default();
+
+        for (layer_index, layer) in layers.iter().enumerate() {
+            let objects = match &layer.data {
+                LayerType::ObjectGroup(group) => &group.objects,
+                _ => continue,
+            };
+
+            for (object_index, object) in objects.iter().enumerate() {
+                index.insert(object, (layer_index, object_index));
+            }
+        }
+
+        index
+    }
+
+    fn insert(&mut self, object: &Object, location: (usize, usize)) {
+        let id = object.id;
+
+        self.by_name
+            .entry(object.name.to_string())
+            .or_default()
+            .push(id);
+        self.by_type
+            .entry(object.r#type.to_string())
+            .or_default()
+            .push(id);
+
+        for name in object.properties.iter().flat_map(|p| p.keys()) {
+            self.by_property.entry(name.clone()).or_default().push(id);
+        }
+
+        self.locations.insert(id, location);
+    }
+
+    /// IDs of objects matching the key.
+    pub fn ids(&self, key: ObjectKey) -> &[ObjectId] {
+        let ids = match key {
+            ObjectKey::Name(name) => self.by_name.get(name),
+            ObjectKey::Type(r#type) => self.by_type.get(r#type),
+            ObjectKey::Property(name) => self.by_property.get(name),
+        };
+
+        ids.map_or(&[], |ids| &ids[..])
+    }
+
+    /// Layer and object index of an object.
+    pub fn location(&self, id: ObjectId) -> Option<(usize, usize)> {
+        self.locations.get(&id).cloned()
+    }
+}
@@ -0,0 +1,97 @@
+use std::hash::Hasher;
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::error::TiledError;
+use crate::hash::ContentHasher;
+use crate::options::{with_options, ParseOptions};
+use crate::properties::deserialize_properties;
+use crate::utils::json_from_slice;
+use crate::{Properties, Property};
+
+/// Summary of a map for indexing many maps, eg. on a game server, see
+/// `MapInfo::from_reader`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MapInfo {
+    /// Value of the `name` string property of the map.
+    pub name: Option<String>,
+    /// Number of tile columns
+    pub width: u32,
+    /// Number of tile rows
+    pub height: u32,
+    /// Map grid width
+    pub tile_width: u32,
+    /// Map grid height
+    pub tile_height: u32,
+    /// Number of objects of the spawn type in all object layers.
+    pub spawn_count: usize,
+    /// Hash of the file contents. Unlike `Map::content_hash` formatting
+    /// changes the hash.
+    pub content_hash: u64,
+}
+
+#[derive(Deserialize)]
+struct InfoDocument {
+    width: u32,
+    height: u32,
+    #[serde(rename = "tilewidth")]
+    tile_width: u32,
+    #[serde(rename = "tileheight")]
+    tile_height: u32,
+    #[serde(default, deserialize_with = "deserialize_properties")]
+    properties: Option<Properties>,
+    layers: Vec<InfoLayer>,
+}
+
+/// Layer with only object types. Tile data is skipped without decoding.
+#[derive(Deserialize)]
+struct InfoLayer {
+    #[serde(default)]
+    objects: Vec<InfoObject>,
+}
+
+#[derive(Deserialize)]
+struct InfoObject {
+    #[serde(default, rename = "type", alias = "class")]
+    object_type: String,
+}
+
+impl MapInfo {
+    /// Read the summary of a map. Objects with type (class) `spawn_type` are
+    /// counted as player spawns. Tile data isn't decoded and objects aren't
+    /// parsed beyond their type.
+    pub fn from_reader<R: Read>(mut reader: R, spawn_type: &str) -> Result<MapInfo, TiledError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+
+        let document: InfoDocument =
+            with_options(&ParseOptions::default(), || json_from_slice(&bytes))?;
+
+        let mut hasher = ContentHasher::default();
+        hasher.write(&bytes);
+
+        let name = match document.properties.as_ref().and_then(|p| p.get("name")) {
+            Some(Property::String(name)) => Some(name.clone()),
+            _ => None,
+        };
+        let spawn_count = document
+            .layers
+            .iter()
+            .flat_map(|layer| &layer.objects)
+            .filter(|object| object.object_type == spawn_type)
+            .count();
+
+        Ok(MapInfo {
+            name,
+            width: document.width,
+            height: document.height,
+            tile_width: document.tile_width,
+            tile_height: document.tile_height,
+            spawn_count,
+            content_hash: hasher.finish(),
+        })
+    }
+}
@@ -0,0 +1,338 @@
+//! Best effort conversion of LDtk projects, enabled with the `ldtk` feature.
+
+use std::io::Read;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::TiledError;
+use crate::tileset::{FLIPPED_HORIZONTALLY, FLIPPED_VERTICALLY};
+use crate::utils::json_from_reader;
+use crate::{
+    BlendMode, Layer, LayerType, Map, Object, ObjectGroup, ObjectShape, Properties, Property,
+    TileLayer, Tileset,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    default_grid_size: u32,
+    defs: Definitions,
+    levels: Vec<Level>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Definitions {
+    #[serde(default)]
+    tilesets: Vec<TilesetDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetDefinition {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    tile_grid_size: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32,
+    #[serde(rename = "__cWid")]
+    columns: u32,
+    #[serde(rename = "__cHei")]
+    rows: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Level {
+    identifier: String,
+    px_wid: u32,
+    px_hei: u32,
+    #[serde(default)]
+    layer_instances: Option<Vec<LayerInstance>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    layer_type: String,
+    #[serde(rename = "__cWid")]
+    width: u32,
+    #[serde(rename = "__cHei")]
+    height: u32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__opacity", default = "default_opacity")]
+    opacity: f32,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_uid: Option<i64>,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    int_grid_csv: Vec<u32>,
+    #[serde(default)]
+    grid_tiles: Vec<TileInstance>,
+    #[serde(default)]
+    auto_layer_tiles: Vec<TileInstance>,
+    #[serde(default)]
+    entity_instances: Vec<EntityInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TileInstance {
+    px: [i64; 2],
+    /// Bit 0 flips horizontally, bit 1 vertically.
+    #[serde(default)]
+    f: u32,
+    t: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__pivot", default)]
+    pivot: [f32; 2],
+    px: [f32; 2],
+    width: f32,
+    height: f32,
+    #[serde(default)]
+    field_instances: Vec<FieldInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    field_type: String,
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+/// Convert a level of an LDtk project to a map.
+///
+/// - Tilesets are converted in definition order, GIDs start from 1.
+/// - Tile and auto-layer tiles become tile layers. When several tiles are
+///   stacked in one cell only the last one is kept.
+/// - IntGrid values become hidden tile layers with an `intgrid` property;
+///   the values are not GIDs.
+/// - Entities become rectangle objects with their fields as properties.
+///   Array fields and fields without a value are skipped.
+pub fn parse_ldtk<R: Read>(reader: R, level: &str) -> Result<Map, TiledError> {
+    let project: Project = json_from_reader(reader)?;
+
+    let level = project
+        .levels
+        .iter()
+        .find(|candidate| candidate.identifier == level)
+        .ok_or_else(|| TiledError::Other(format!("LDtk level {:?} not found", level)))?;
+
+    let grid = project.default_grid_size.max(1);
+    let mut map = Map::new(level.px_wid / grid, level.px_hei / grid, grid, grid);
+
+    let mut first_gid = 1;
+    let mut tileset_gids = Vec::new();
+    for definition in &project.defs.tilesets {
+        let too_many = || {
+            TiledError::Other(format!(
+                "LDtk tileset {:?} has too many tiles",
+                definition.identifier
+            ))
+        };
+        let tile_count = definition
+            .columns
+            .checked_mul(definition.rows)
+            .ok_or_else(too_many)?;
+        tileset_gids.push((definition.uid, first_gid, tile_count));
+
+        map.tilesets.push(Tileset {
+            first_gid,
+            name: definition.identifier.clone(),
+            tile_width: definition.tile_grid_size,
+            tile_height: definition.tile_grid_size,
+            spacing: definition.spacing,
+            margin: definition.padding,
+            image: definition.rel_path.clone().unwrap_or_default(),
+            tile_count: Some(tile_count),
+            tiles: None,
+            properties: None,
+            terrains: None,
+            wang_sets: None,
+            source: None,
+        });
+
+        first_gid = first_gid.checked_add(tile_count).ok_or_else(too_many)?;
+    }
+
+    let mut next_object_id = 1;
+
+    // LDtk lists the topmost layer first.
+    for instance in level.layer_instances.iter().flatten().rev() {
+        let tileset = tileset_gids
+            .iter()
+            .find(|(uid, _, _)| Some(*uid) == instance.tileset_uid)
+            .map(|(_, first_gid, tile_count)| (*first_gid, *tile_count));
+
+        let mut push = |name: &str, visible: bool, data: LayerType, properties| {
+            map.layers.push(Layer {
+                id: None,
+                name: name.to_string(),
+                opacity: instance.opacity,
+                visible,
+                parallax_x: 1.0,
+                parallax_y: 1.0,
+                blend_mode: BlendMode::Normal,
+                tint_color: None,
+                data,
+                properties,
+            })
+        };
+
+        if instance.layer_type == "IntGrid" && !instance.int_grid_csv.is_empty() {
+            let layer = TileLayer::new(
+                instance.width,
+                instance.height,
+                instance.int_grid_csv.clone(),
+            )?;
+            let mut properties = Properties::new();
+            properties.insert("intgrid".to_string(), Property::Bool(true));
+
+            push(
+                &format!("{} (intgrid)", instance.identifier),
+                false,
+                LayerType::TileLayer(layer),
+                Some(properties),
+            );
+        }
+
+        let tiles = match instance.layer_type.as_str() {
+            "Tiles" => &instance.grid_tiles,
+            _ => &instance.auto_layer_tiles,
+        };
+        if let (false, Some((first_gid, tile_count))) = (tiles.is_empty(), tileset) {
+            let layer = tile_layer(instance, tiles, first_gid, tile_count);
+            push(
+                &instance.identifier,
+                instance.visible,
+                LayerType::TileLayer(layer),
+                None,
+            );
+        }
+
+        if instance.layer_type == "Entities" {
+            let objects = instance
+                .entity_instances
+                .iter()
+                .map(|entity| {
+                    let object = entity_object(next_object_id, entity);
+                    next_object_id += 1;
+                    object
+                })
+                .collect();
+
+            push(
+                &instance.identifier,
+                instance.visible,
+                LayerType::ObjectGroup(ObjectGroup {
+                    objects,
+                    color: None,
+                    x: 0.0,
+                    y: 0.0,
+                    offset_x: 0.0,
+                    offset_y: 0.0,
+                }),
+                None,
+            );
+        }
+    }
+
+    Ok(map)
+}
+
+/// Tile layer of tiles from a tileset of `tile_count` tiles. Tiles outside
+/// the layer or the tileset are skipped.
+fn tile_layer(
+    instance: &LayerInstance,
+    tiles: &[TileInstance],
+    first_gid: u32,
+    tile_count: u32,
+) -> TileLayer {
+    let grid = i64::from(instance.grid_size.max(1));
+    let mut layer = TileLayer::from_fn(instance.width, instance.height, |_, _| 0);
+
+    for tile in tiles {
+        let (x, y) = (tile.px[0] / grid, tile.px[1] / grid);
+        if x < 0 || y < 0 || x >= i64::from(instance.width) || y >= i64::from(instance.height) {
+            continue;
+        }
+        if tile.t >= tile_count {
+            continue;
+        }
+
+        let mut gid = first_gid + tile.t;
+        if tile.f & 1 != 0 {
+            gid |= FLIPPED_HORIZONTALLY;
+        }
+        if tile.f & 2 != 0 {
+            gid |= FLIPPED_VERTICALLY;
+        }
+
+        layer.set_tile(x as u32, y as u32, gid);
+    }
+
+    layer
+}
+
+fn entity_object(id: u32, entity: &EntityInstance) -> Object {
+    let properties: Properties = entity
+        .field_instances
+        .iter()
+        .filter_map(|field| Some((field.identifier.clone(), field_property(field)?)))
+        .collect();
+
+    Object {
+        id,
+        name: entity.identifier.as_str().into(),
+        r#type: entity.identifier.as_str().into(),
+        x: entity.px[0] - entity.pivot[0] * entity.width,
+        y: entity.px[1] - entity.pivot[1] * entity.height,
+        rotation: 0.0,
+        visible: true,
+        gid: None,
+        template: None,
+        shape: ObjectShape::Rect {
+            width: entity.width,
+            height: entity.height,
+        },
+        properties: Some(properties).filter(|properties| !properties.is_empty()),
+    }
+}
+
+fn field_property(field: &FieldInstance) -> Option<Property> {
+    let value = &field.value;
+
+    Some(match field.field_type.as_str() {
+        "Int" => Property::Int(value.as_i64()? as i32),
+        "Float" => Property::Float(value.as_f64()? as f32),
+        "Bool" => Property::Bool(value.as_bool()?),
+        "Color" => Property::Color(value.as_str()?.parse().ok()?),
+        "FilePath" => Property::File(value.as_str()?.to_string()),
+        // Strings, multilines and enum values.
+        _ => Property::String(value.as_str()?.to_string()),
+    })
+}
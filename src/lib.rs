@@ -1,22 +1,162 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
+use std::ops::Index;
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
 use serde::{de::Error, Deserialize, Deserializer};
 use serde_json::Value;
 
+pub mod analysis;
+mod bitgrid;
+mod bundle;
+mod camera;
+mod chunked;
+#[cfg(feature = "rapier2d")]
+pub mod collision;
+#[cfg(feature = "compat")]
+pub mod compat;
+mod context;
+mod contour;
+pub mod conventions;
+mod cull;
+mod decode;
+mod document;
 mod error;
+mod external;
+pub mod fov;
+#[cfg(feature = "geojson")]
+mod geojson;
+mod gid_table;
+mod gpu;
+mod hash;
+mod header;
+mod heatmap;
+mod hit;
+#[cfg(feature = "http")]
+mod http;
+mod images;
+mod index;
+mod info;
+#[cfg(feature = "intern")]
+mod intern;
+#[cfg(feature = "ldtk")]
+mod ldtk;
+mod loader;
+mod manifest;
+mod measure;
+#[cfg(feature = "mesh")]
+mod mesh;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod options;
+mod patch;
+pub mod paths;
+mod patrol;
+mod project;
 mod properties;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+mod property_formats;
+mod query;
+mod raycast;
+mod resize;
+mod rooms;
+mod state;
+mod strict;
+mod template;
+mod thumbnail;
 mod tileset;
+mod trace;
+#[cfg(feature = "geometry")]
+mod triangulate;
+mod units;
+mod upgrade;
 mod utils;
+mod version;
+mod visit;
+mod wang;
+mod warning;
+mod writer;
 
+use crate::hash::{content_hash, tile_data_hash};
+use crate::options::{
+    current_options, isolated, parse_in_progress, report_error, reported_count, take_warnings,
+    with_options, with_options_collecting, wrap_reported,
+};
 use crate::properties::deserialize_properties;
-use crate::utils::{decode_tiledata, deserialize_version, Compression, Encoding};
+use crate::strict::check_unknown_fields;
+use crate::tileset::GID_FLAGS;
+use crate::utils::{
+    decode_base64, decode_tile_bytes, decode_tiledata, decompressed_limit, deserialize_height,
+    deserialize_rotation, deserialize_version, deserialize_width, deserialize_with_path,
+    deserialize_x, deserialize_y, encode_tile_bytes, json_from_reader, json_from_slice,
+    normalize_enum_values, parse_version, transcode_to_utf8, TypeChecked,
+};
 
+pub use crate::bitgrid::{BitGrid, Region};
+pub use crate::bundle::bundle;
+pub use crate::camera::Camera;
+pub use crate::chunked::{ObjectChunks, ObjectCursor};
+pub use crate::context::LayerContext;
+pub use crate::cull::ViewRect;
+pub use crate::decode::{DecodeJob, DecodeProgress};
+pub use crate::document::MapDocument;
 pub use crate::error::TiledError;
-pub use crate::properties::{Properties, Property};
-pub use crate::tileset::Tileset;
-pub use crate::utils::Color;
+pub use crate::external::parse_file_with_externals;
+pub use crate::gid_table::ResolvedGid;
+pub use crate::gpu::GpuPack;
+pub use crate::hash::ContentHasher;
+pub use crate::header::{parse_header, LayerHeader, MapHeader, TilesetHeader};
+pub use crate::heatmap::HeatmapFormat;
+#[cfg(feature = "http")]
+pub use crate::http::HttpResolver;
+pub use crate::images::{ImageHandles, ImageSource};
+pub use crate::index::{ObjectId, ObjectIndex, ObjectKey};
+pub use crate::info::MapInfo;
+#[cfg(feature = "intern")]
+pub use crate::intern::{Interner, SharedStr};
+#[cfg(feature = "ldtk")]
+pub use crate::ldtk::parse_ldtk;
+pub use crate::loader::{Loader, LoaderConfig, ObjectRef};
+pub use crate::manifest::ManifestFormat;
+#[cfg(feature = "mesh")]
+pub use crate::mesh::{Mesh, MeshOptions, Vertex};
+#[cfg(feature = "mmap")]
+pub use crate::mmap::{parse_file_mmap, parse_file_mmap_with_options};
+pub use crate::options::{GidTransform, ParseOptions};
+pub use crate::patch::{LayerPatch, TileChange};
+pub use crate::patrol::{PathMode, PatrolPath};
+pub use crate::project::{
+    parse_project, parse_project_file, ClassType, EnumType, Project, PropertyType,
+};
+pub use crate::properties::{
+    ExpandContext, MergeStrategy, Properties, PropertiesDiff, PropertiesExt, Property,
+};
+#[cfg(feature = "toml")]
+pub use crate::property_formats::{properties_from_toml, properties_to_toml};
+#[cfg(feature = "yaml")]
+pub use crate::property_formats::{properties_from_yaml, properties_to_yaml};
+pub use crate::query::{Query, QueryOp, QueryTarget, Selected};
+pub use crate::raycast::RaycastHit;
+pub use crate::resize::{Anchor, OutsideObjects};
+pub use crate::rooms::Room;
+pub use crate::state::MapState;
+pub use crate::template::ObjectTemplate;
+pub use crate::thumbnail::ThumbnailCache;
+pub use crate::tileset::{
+    gid_transform, Animation, Frame, Tile, Tileset, FLIPPED_DIAGONALLY, FLIPPED_HORIZONTALLY,
+    FLIPPED_VERTICALLY,
+};
+pub use crate::units::{PixelPos, TilePos};
+pub use crate::upgrade::upgrade_json;
+pub use crate::utils::{Color, Compression, Encoding};
+pub use crate::version::TiledVersion;
+pub use crate::visit::MapVisitor;
+pub use crate::wang::{Terrain, WangColor, WangSet, WangTile};
+pub use crate::warning::ParseWarning;
+pub use crate::writer::{write_json, LineEnding, WriteOptions};
 
 /// Tile orientation.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize)]
@@ -28,9 +168,75 @@ pub enum Orientation {
     Hexagonal,
 }
 
+/// Which axis is staggered on staggered and hexagonal maps.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaggerAxis {
+    X,
+    Y,
+}
+
+/// Whether odd or even rows (or columns) are shifted on staggered and
+/// hexagonal maps.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaggerIndex {
+    Odd,
+    Even,
+}
+
+/// How a layer is blended with layers below it.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Add,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    /// Blend mode not known by this crate.
+    Unknown(String),
+}
+
+impl<'de> Deserialize<'de> for BlendMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mode: String = Deserialize::deserialize(deserializer)?;
+
+        Ok(match mode.as_str() {
+            "normal" => BlendMode::Normal,
+            "add" => BlendMode::Add,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            "overlay" => BlendMode::Overlay,
+            "darken" => BlendMode::Darken,
+            "lighten" => BlendMode::Lighten,
+            "color-dodge" => BlendMode::ColorDodge,
+            "color-burn" => BlendMode::ColorBurn,
+            "hard-light" => BlendMode::HardLight,
+            "soft-light" => BlendMode::SoftLight,
+            "difference" => BlendMode::Difference,
+            "exclusion" => BlendMode::Exclusion,
+            _ => BlendMode::Unknown(mode),
+        })
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub struct Point {
+    #[serde(deserialize_with = "deserialize_x")]
     pub x: f32,
+    #[serde(deserialize_with = "deserialize_y")]
     pub y: f32,
 }
 
@@ -44,6 +250,11 @@ pub struct Text {
 
     #[serde(rename = "pixelsize")]
     pixel_size: Option<u32>,
+
+    /// Other attributes, eg. color, bold and alignment, kept for writing
+    /// the object back.
+    #[serde(flatten)]
+    other: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
@@ -54,8 +265,6 @@ enum ObjectShapeData {
     },
     Ellipse {
         ellipse: bool,
-        width: f32,
-        height: f32,
     },
     Polyline {
         #[serde(rename = "polyline")]
@@ -67,47 +276,87 @@ enum ObjectShapeData {
     },
     Text {
         text: Text,
-        width: f32,
-        height: f32,
     },
+    /// Objects without a shape flag are rectangles if they have a size.
     Rect {
-        width: f32,
-        height: f32,
+        width: Value,
+        height: Value,
     },
 }
 
+/// Size of an object. Tiled writes it for all shapes, but ellipses and texts
+/// from other exporters may only have the shape flag.
+#[derive(Debug, Deserialize)]
+struct ObjectSize {
+    #[serde(default, deserialize_with = "deserialize_width")]
+    width: f32,
+    #[serde(default, deserialize_with = "deserialize_height")]
+    height: f32,
+}
+
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum ObjectShape {
     Point,
-    Rect { width: f32, height: f32 },
-    Ellipse { width: f32, height: f32 },
-    Polyline { points: Vec<Point> },
-    Polygon { points: Vec<Point> },
-    Text { text: Text, width: f32, height: f32 },
-    Unknown,
+    Rect {
+        width: f32,
+        height: f32,
+    },
+    Ellipse {
+        width: f32,
+        height: f32,
+    },
+    Polyline {
+        points: Vec<Point>,
+    },
+    Polygon {
+        points: Vec<Point>,
+    },
+    Text {
+        text: Text,
+        width: f32,
+        height: f32,
+    },
+    /// Shape that didn't match any known shape, with the unmatched fields of
+    /// the object.
+    Unknown(Value),
+    /// Placeholder for an object that failed to parse, see `parse_with_errors`.
+    Invalid {
+        index: usize,
+    },
 }
 
 impl ObjectShape {
-    fn from(data: ObjectShapeData) -> Self {
+    fn from(data: ObjectShapeData, size: ObjectSize) -> Self {
+        let ObjectSize { width, height } = size;
+
         match data {
             ObjectShapeData::Point { .. } => ObjectShape::Point,
-            ObjectShapeData::Rect { width, height } => ObjectShape::Rect { width, height },
-            ObjectShapeData::Ellipse { width, height, .. } => {
-                ObjectShape::Ellipse { width, height }
-            }
+            ObjectShapeData::Rect { .. } => ObjectShape::Rect { width, height },
+            ObjectShapeData::Ellipse { .. } => ObjectShape::Ellipse { width, height },
             ObjectShapeData::Polyline { points } => ObjectShape::Polyline { points },
             ObjectShapeData::Polygon { points } => ObjectShape::Polygon { points },
-            ObjectShapeData::Text {
-                width,
-                height,
-                text,
-            } => ObjectShape::Text {
+            ObjectShapeData::Text { text } => ObjectShape::Text {
                 width,
                 height,
                 text,
             },
         }
     }
+
+    /// Horizontal and vertical radius of an ellipse.
+    pub fn radii(&self) -> Option<(f32, f32)> {
+        match self {
+            ObjectShape::Ellipse { width, height } => Some((width / 2.0, height / 2.0)),
+            _ => None,
+        }
+    }
+
+    /// Whether the shape is an ellipse with equal width and height.
+    pub fn is_circle(&self) -> bool {
+        self.radii()
+            .is_some_and(|(rx, ry)| (rx - ry).abs() <= f32::EPSILON * rx.abs().max(ry.abs()))
+    }
 }
 
 impl<'de> Deserialize<'de> for ObjectShape {
@@ -115,26 +364,49 @@ impl<'de> Deserialize<'de> for ObjectShape {
     where
         D: Deserializer<'de>,
     {
-        if let Ok(shape_data) = Deserialize::deserialize(deserializer) {
-            return Ok(ObjectShape::from(shape_data));
+        let value = Value::deserialize(deserializer)?;
+        if let (Ok(data), Ok(size)) = (
+            ObjectShapeData::deserialize(&value),
+            ObjectSize::deserialize(&value),
+        ) {
+            return Ok(ObjectShape::from(data, size));
         }
 
-        Ok(ObjectShape::Unknown)
+        Ok(ObjectShape::Unknown(value))
     }
 }
 
+/// String type of object names and types. `SharedStr` with the `intern`
+/// feature, so that equal strings can share storage.
+#[cfg(feature = "intern")]
+pub type ObjectStr = SharedStr;
+/// String type of object names and types. `SharedStr` with the `intern`
+/// feature, so that equal strings can share storage.
+#[cfg(not(feature = "intern"))]
+pub type ObjectStr = String;
+
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub struct Object {
     /// Unique ID of the object. Each object that is placed on a map gets a unique id.
     pub id: u32,
     /// The name of the object. An arbitrary string.
-    pub name: String,
-    /// The type of the object. An arbitrary string.
-    pub r#type: String,
+    pub name: ObjectStr,
+    /// The type of the object. An arbitrary string. Called class since Tiled 1.9.
+    #[serde(alias = "class")]
+    pub r#type: ObjectStr,
+    #[serde(deserialize_with = "deserialize_x")]
     pub x: f32,
+    #[serde(deserialize_with = "deserialize_y")]
     pub y: f32,
+    #[serde(deserialize_with = "deserialize_rotation")]
     pub rotation: f32,
     pub visible: bool,
+    /// Global tile ID of tile objects.
+    pub gid: Option<u32>,
+    /// Path of the object template relative to the map. Fields of the
+    /// template are filled in by `parse_file_with_externals`.
+    #[serde(default)]
+    pub template: Option<String>,
 
     #[serde(flatten)]
     pub shape: ObjectShape,
@@ -146,8 +418,19 @@ pub struct Object {
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub struct ObjectGroup {
+    #[serde(deserialize_with = "deserialize_objects")]
     pub objects: Vec<Object>,
     pub color: Option<Color>,
+    /// Always 0 in maps written by Tiled.
+    #[serde(default)]
+    pub x: f32,
+    /// Always 0 in maps written by Tiled.
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default, rename = "offsetx")]
+    pub offset_x: f32,
+    #[serde(default, rename = "offsety")]
+    pub offset_y: f32,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
@@ -169,43 +452,575 @@ struct TileLayerData {
     width: u32,
     /// Row count. Same as map height for fixed-size maps.
     height: u32,
-    /// Type of data depends on encoding.
-    data: Value,
+    /// Type of data depends on encoding. Missing for infinite maps.
+    data: Option<Value>,
+    /// Tile data of infinite maps.
+    chunks: Option<Vec<ChunkData>>,
+    /// X coordinate where layer content starts (for infinite maps).
+    #[serde(default, rename = "startx")]
+    start_x: i32,
+    /// Y coordinate where layer content starts (for infinite maps).
+    #[serde(default, rename = "starty")]
+    start_y: i32,
+    #[serde(default, rename = "offsetx")]
+    offset_x: f32,
+    #[serde(default, rename = "offsety")]
+    offset_y: f32,
     compression: Option<Compression>,
     encoding: Option<Encoding>,
 }
 
+/// Internal type for chunks of infinite maps.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+struct ChunkData {
+    /// Type of data depends on encoding of the layer.
+    data: Value,
+    width: u32,
+    height: u32,
+    /// X coordinate in tiles. Can be negative.
+    x: i32,
+    /// Y coordinate in tiles. Can be negative.
+    y: i32,
+}
+
+/// Raw tile data of a base64 encoded layer, see `ParseOptions::keep_encoded`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EncodedTiles {
+    pub encoding: Encoding,
+    pub compression: Option<Compression>,
+    /// Base64 decoded, possibly compressed, bytes.
+    pub bytes: Vec<u8>,
+    /// Maximum decompressed size, see `ParseOptions::max_decompressed_bytes`.
+    pub max_decompressed_bytes: usize,
+    /// Applied to the tiles when decoding, see `ParseOptions::gid_transform`.
+    pub gid_transform: Option<GidTransform>,
+}
+
+impl EncodedTiles {
+    /// Decompress and decode the tiles.
+    pub fn decode(&self) -> Result<Vec<u32>, TiledError> {
+        let mut tiles = Vec::with_capacity(self.bytes.len() / 4);
+        decode_tile_bytes(
+            self.bytes.clone(),
+            self.compression,
+            self.max_decompressed_bytes,
+            &mut tiles,
+        )?;
+
+        if let Some(transform) = &self.gid_transform {
+            tiles
+                .iter_mut()
+                .for_each(|gid| *gid = transform.decode(*gid));
+        }
+
+        Ok(tiles)
+    }
+
+    /// Tile data as written to the `data` field of the layer.
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.bytes)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct TileLayer {
     /// Column count. Same as map width for fixed-size maps.
     width: u32,
     /// Row count. Same as map height for fixed-size maps.
     height: u32,
-    /// Tiles arranged in a 1d array.
-    tiles: Vec<u32>,
+    /// X coordinate of the first column. Only infinite maps have non-zero start.
+    start_x: i32,
+    /// Y coordinate of the first row. Only infinite maps have non-zero start.
+    start_y: i32,
+    /// Offset of the layer in pixels.
+    offset: (f32, f32),
+    /// Tiles arranged in a 1d array. Shared between layers with identical
+    /// data, see `Map::dedup_identical_layers`. Empty while encoded.
+    tiles: Arc<Vec<u32>>,
+    /// Raw tile data if the layer was not decoded while parsing.
+    encoded: Option<EncodedTiles>,
+    /// Cached result of `stats`, reset when the tiles change.
+    stats: StatsCache,
+}
+
+/// Statistics of the tiles of a layer, see `TileLayer::stats`.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct TileStats {
+    /// Highest GID without flip flags, 0 for empty layers.
+    pub max_gid: u32,
+    /// Number of different GIDs, counting flipped tiles and empty cells
+    /// separately.
+    pub distinct_gids: usize,
+    /// Shannon entropy of the GIDs in bits per tile. 0 for layers filled
+    /// with one tile, `log2(distinct_gids)` when all GIDs are equally common.
+    pub entropy: f32,
+}
+
+impl TileStats {
+    fn new(tiles: &[u32]) -> Self {
+        // Sorted, so that the entropy doesn't depend on the order of the sum.
+        let mut counts = std::collections::BTreeMap::new();
+        let mut max_gid = 0;
+
+        for gid in tiles {
+            *counts.entry(*gid).or_insert(0usize) += 1;
+            max_gid = max_gid.max(gid & !GID_FLAGS);
+        }
+
+        let total = tiles.len() as f64;
+        let entropy = counts
+            .values()
+            .map(|count| {
+                let p = *count as f64 / total;
+                -p * p.log2()
+            })
+            .sum::<f64>();
+
+        TileStats {
+            max_gid,
+            distinct_gids: counts.len(),
+            entropy: entropy as f32,
+        }
+    }
+}
+
+/// Lazily computed statistics of a tile layer. Ignored when comparing
+/// layers, they're derived from the tiles.
+#[derive(Debug, Default, Clone)]
+struct StatsCache(OnceLock<TileStats>);
+
+impl PartialEq for StatsCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
 impl TileLayer {
+    /// Construct TileLayer from raw parts. Tiles are arranged in rows and
+    /// their count must match the dimensions.
+    pub fn new(width: u32, height: u32, tiles: Vec<u32>) -> Result<Self, TiledError> {
+        if tiles.len() != width as usize * height as usize {
+            return Err(TiledError::Other(format!(
+                "Expected {} tiles, got {}",
+                width as usize * height as usize,
+                tiles.len()
+            )));
+        }
+
+        Ok(TileLayer {
+            width,
+            height,
+            start_x: 0,
+            start_y: 0,
+            offset: (0.0, 0.0),
+            tiles: Arc::new(tiles),
+            encoded: None,
+            stats: StatsCache::default(),
+        })
+    }
+
+    /// Construct TileLayer by calling `f(x, y)` for each tile, eg. to emit
+    /// the output of a procedural generator.
+    pub fn from_fn<F>(width: u32, height: u32, f: F) -> Self
+    where
+        F: Fn(u32, u32) -> u32,
+    {
+        let tiles = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+
+        TileLayer {
+            width,
+            height,
+            start_x: 0,
+            start_y: 0,
+            offset: (0.0, 0.0),
+            tiles: Arc::new(tiles),
+            encoded: None,
+            stats: StatsCache::default(),
+        }
+    }
+
     /// Construct TileLayer from TileLayerData.
     fn from(layer_data: TileLayerData) -> Result<Self, TiledError> {
-        Ok(TileLayer {
-            width: layer_data.width,
-            height: layer_data.height,
-            tiles: decode_tiledata(
-                layer_data.data,
+        let keep_encoded = current_options(|options| options.keep_encoded);
+
+        if let (true, Some(data), Some(Encoding::Base64)) =
+            (keep_encoded, &layer_data.data, layer_data.encoding)
+        {
+            return Ok(TileLayer {
+                width: layer_data.width,
+                height: layer_data.height,
+                start_x: layer_data.start_x,
+                start_y: layer_data.start_y,
+                offset: (layer_data.offset_x, layer_data.offset_y),
+                tiles: Arc::new(Vec::new()),
+                encoded: Some(EncodedTiles {
+                    encoding: Encoding::Base64,
+                    compression: layer_data.compression,
+                    bytes: decode_base64(data)?,
+                    max_decompressed_bytes: decompressed_limit(
+                        layer_data.width as usize * layer_data.height as usize,
+                    ),
+                    gid_transform: current_options(|options| options.gid_transform.clone()),
+                }),
+                stats: StatsCache::default(),
+            });
+        }
+
+        let mut tiles = match (layer_data.data, layer_data.chunks) {
+            (Some(data), _) => decode_tiledata(
+                data,
+                layer_data.width,
+                layer_data.height,
+                layer_data.encoding,
+                layer_data.compression,
+            )?,
+            (None, Some(chunks)) => TileLayer::merge_chunks(
+                chunks,
                 layer_data.width,
                 layer_data.height,
+                layer_data.start_x,
+                layer_data.start_y,
                 layer_data.encoding,
                 layer_data.compression,
             )?,
+            (None, None) => {
+                return Err(TiledError::Other(
+                    "Tile layer has no data or chunks".to_string(),
+                ))
+            }
+        };
+
+        if let Some(transform) = current_options(|options| options.gid_transform.clone()) {
+            tiles
+                .iter_mut()
+                .for_each(|gid| *gid = transform.decode(*gid));
+        }
+
+        Ok(TileLayer {
+            width: layer_data.width,
+            height: layer_data.height,
+            start_x: layer_data.start_x,
+            start_y: layer_data.start_y,
+            offset: (layer_data.offset_x, layer_data.offset_y),
+            tiles: Arc::new(tiles),
+            encoded: None,
+            stats: StatsCache::default(),
+        })
+    }
+
+    /// Offset of the layer in pixels.
+    pub fn offset(&self) -> (f32, f32) {
+        self.offset
+    }
+
+    /// Set the offset of the layer in pixels.
+    pub fn set_offset(&mut self, x: f32, y: f32) {
+        self.offset = (x, y);
+    }
+
+    /// Whether tile data is still encoded, see `ParseOptions::keep_encoded`.
+    pub fn is_encoded(&self) -> bool {
+        self.encoded.is_some()
+    }
+
+    /// Raw tile data if the layer hasn't been decoded.
+    pub fn encoded(&self) -> Option<&EncodedTiles> {
+        self.encoded.as_ref()
+    }
+
+    /// Encode the tiles as base64 with given compression, converting GIDs
+    /// with `transform` first. Encoded layers are returned as they are.
+    pub fn encode(
+        &self,
+        compression: Option<Compression>,
+        transform: Option<&GidTransform>,
+    ) -> Result<EncodedTiles, TiledError> {
+        if let Some(encoded) = &self.encoded {
+            return Ok(encoded.clone());
+        }
+
+        let bytes = match transform {
+            Some(transform) => {
+                let tiles: Vec<u32> = self
+                    .tiles
+                    .iter()
+                    .map(|gid| transform.encode(*gid))
+                    .collect();
+                encode_tile_bytes(&tiles, compression)?
+            }
+            None => encode_tile_bytes(&self.tiles, compression)?,
+        };
+
+        Ok(EncodedTiles {
+            encoding: Encoding::Base64,
+            compression,
+            bytes,
+            max_decompressed_bytes: self.tiles.len() * 4,
+            gid_transform: transform.cloned(),
         })
     }
 
+    /// Decode tile data kept by `ParseOptions::keep_encoded`. Does nothing if
+    /// the layer is already decoded.
+    pub fn decode(&mut self) -> Result<(), TiledError> {
+        if let Some(encoded) = &self.encoded {
+            let size = self.width as usize * self.height as usize;
+            let tiles = trace::phase("decode", size, || encoded.decode())?;
+
+            if tiles.len() != self.width as usize * self.height as usize {
+                return Err(TiledError::Other(format!(
+                    "Expected {} tiles, got {}",
+                    self.width as usize * self.height as usize,
+                    tiles.len()
+                )));
+            }
+
+            self.tiles = Arc::new(tiles);
+            self.encoded = None;
+            self.stats.0.take();
+        }
+
+        Ok(())
+    }
+
+    /// Decode chunks of an infinite map into one dense 1d array that starts
+    /// from `start_x` and `start_y`.
+    fn merge_chunks(
+        chunks: Vec<ChunkData>,
+        width: u32,
+        height: u32,
+        start_x: i32,
+        start_y: i32,
+        encoding: Option<Encoding>,
+        compression: Option<Compression>,
+    ) -> Result<Vec<u32>, TiledError> {
+        // The layer can be much larger than the chunks in it, so its size is
+        // checked before allocating instead of aborting on hostile sizes.
+        let too_large =
+            || TiledError::Other(format!("Layer of {}x{} tiles is too large", width, height));
+        let size =
+            usize::try_from(u64::from(width) * u64::from(height)).map_err(|_| too_large())?;
+        let bytes = size.checked_mul(4).ok_or_else(too_large)?;
+        if let Some(limit) = current_options(|options| options.max_decompressed_bytes) {
+            if bytes > limit {
+                return Err(TiledError::DecompressionLimitExceeded { limit });
+            }
+        }
+
+        let mut tiles = Vec::new();
+        tiles.try_reserve_exact(size).map_err(|_| too_large())?;
+        tiles.resize(size, 0);
+
+        for chunk in chunks {
+            let chunk_tiles =
+                decode_tiledata(chunk.data, chunk.width, chunk.height, encoding, compression)?;
+
+            // Chunk position relative to the layer origin.
+            let offset_x = i64::from(chunk.x) - i64::from(start_x);
+            let offset_y = i64::from(chunk.y) - i64::from(start_y);
+
+            if offset_x < 0
+                || offset_y < 0
+                || offset_x + i64::from(chunk.width) > i64::from(width)
+                || offset_y + i64::from(chunk.height) > i64::from(height)
+                || chunk_tiles.len() != chunk.width as usize * chunk.height as usize
+            {
+                return Err(TiledError::Other(format!(
+                    "Chunk at ({}, {}) doesn't fit in the layer",
+                    chunk.x, chunk.y
+                )));
+            }
+
+            for (row, row_tiles) in chunk_tiles.chunks(chunk.width as usize).enumerate() {
+                let start = (offset_y as usize + row) * width as usize + offset_x as usize;
+                tiles[start..start + row_tiles.len()].copy_from_slice(row_tiles);
+            }
+        }
+
+        Ok(tiles)
+    }
+
+    /// Hash of layer dimensions, position and tile data. The hash is stable
+    /// across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        content_hash(self)
+    }
+
+    /// Column count.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Row count.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// X coordinate of the first column. Negative for infinite maps that
+    /// extend left of the origin.
+    pub fn start_x(&self) -> i32 {
+        self.start_x
+    }
+
+    /// Y coordinate of the first row. Negative for infinite maps that extend
+    /// above the origin.
+    pub fn start_y(&self) -> i32 {
+        self.start_y
+    }
+
+    /// Tiles arranged in a 1d array. Empty if the layer is still encoded.
+    pub fn tiles(&self) -> &[u32] {
+        &self.tiles
+    }
+
+    /// Take ownership of the tiles. Copies the tiles if the storage is shared
+    /// with another layer.
+    pub fn into_tiles(self) -> Vec<u32> {
+        Arc::try_unwrap(self.tiles).unwrap_or_else(|tiles| (*tiles).clone())
+    }
+
     /// Get tile with x and y coordinates.
-    /// This is equivalent to `layer.tiles[x + y * layer.width]`
+    /// This is equivalent to `layer.tiles()[(x + y * layer.width()) as usize]`
+    /// except that encoded layers have only empty tiles (0), see `decode`.
     pub fn get_tile(&self, x: u32, y: u32) -> u32 {
+        if self.is_encoded() {
+            return 0;
+        }
+
         self.tiles[(x + y * self.width) as usize]
     }
+
+    /// Get tile with map coordinates, which can be negative for infinite
+    /// maps. Returns `None` for coordinates outside the layer and for layers
+    /// that are still encoded.
+    pub fn get_tile_signed(&self, x: i32, y: i32) -> Option<u32> {
+        if self.is_encoded() {
+            return None;
+        }
+
+        let x = i64::from(x) - i64::from(self.start_x);
+        let y = i64::from(y) - i64::from(self.start_y);
+
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return None;
+        }
+
+        Some(self.get_tile(x as u32, y as u32))
+    }
+
+    /// Tiles with flip flags stripped.
+    pub fn gids_masked(&self) -> impl Iterator<Item = u32> + '_ {
+        self.tiles.iter().map(|gid| gid & !GID_FLAGS)
+    }
+
+    /// GIDs without flip flags converted to a smaller integer type, eg.
+    /// `Vec<u16>` for uploading the layer as a texture. Fails if a GID
+    /// doesn't fit in `T`.
+    pub fn to_index_buffer<T: TryFrom<u32>>(&self) -> Result<Vec<T>, TiledError> {
+        self.gids_masked()
+            .map(|gid| {
+                T::try_from(gid).map_err(|_| {
+                    TiledError::Other(format!(
+                        "GID {} doesn't fit in {}",
+                        gid,
+                        std::any::type_name::<T>()
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Highest GID of the layer without flip flags, 0 for empty layers.
+    /// Cached, see `stats`.
+    pub fn max_gid(&self) -> u32 {
+        self.stats().max_gid
+    }
+
+    /// Number of different GIDs in the layer, including flipped variants and
+    /// empty cells. A rough measure of the atlas space the layer needs.
+    pub fn distinct_gid_count(&self) -> usize {
+        self.stats().distinct_gids
+    }
+
+    /// Tile statistics, computed in one pass on the first call and cached
+    /// until the tiles change. Encoded layers have no tiles.
+    ///
+    /// High entropy means the tiles vary a lot and the layer will compress
+    /// poorly.
+    pub fn stats(&self) -> TileStats {
+        *self.stats.0.get_or_init(|| TileStats::new(&self.tiles))
+    }
+
+    /// Iterate spans of identical consecutive tiles on each row as
+    /// `(start_x, y, length, gid)`. Runs never cross row boundaries and
+    /// empty cells (gid 0) are included.
+    pub fn runs(&self) -> Runs<'_> {
+        Runs {
+            tiles: &self.tiles,
+            width: self.width as usize,
+            index: 0,
+        }
+    }
+}
+
+/// Tile at `(x, y)`, same as `TileLayer::get_tile`.
+///
+/// # Panics
+///
+/// If the coordinates are outside the layer or the layer is still encoded.
+impl Index<(u32, u32)> for TileLayer {
+    type Output = u32;
+
+    fn index(&self, (x, y): (u32, u32)) -> &u32 {
+        assert!(
+            x < self.width && y < self.height,
+            "tile ({}, {}) out of bounds of {}x{} layer",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        &self.tiles[(x + y * self.width) as usize]
+    }
+}
+
+/// Iterator returned by `TileLayer::runs`.
+#[derive(Debug, Clone)]
+pub struct Runs<'a> {
+    tiles: &'a [u32],
+    width: usize,
+    index: usize,
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (u32, u32, u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.width == 0 || self.index >= self.tiles.len() {
+            return None;
+        }
+
+        let start = self.index;
+        let gid = self.tiles[start];
+        let row_end = std::cmp::min((start / self.width + 1) * self.width, self.tiles.len());
+
+        let length = self.tiles[start..row_end]
+            .iter()
+            .take_while(|tile| **tile == gid)
+            .count();
+
+        self.index += length;
+
+        Some((
+            (start % self.width) as u32,
+            (start / self.width) as u32,
+            length as u32,
+            gid,
+        ))
+    }
 }
 
 impl<'de> Deserialize<'de> for TileLayer {
@@ -215,26 +1030,55 @@ impl<'de> Deserialize<'de> for TileLayer {
     {
         // Deserialize to intermediary struct TileLayerData to allow
         // decompressing and decoding tile data.
-        TileLayer::from(Deserialize::deserialize(deserializer)?).map_err(Error::custom)
+        let data: TileLayerData = Deserialize::deserialize(deserializer)?;
+        let size = data.width as usize * data.height as usize;
+        trace::phase("decode", size, || TileLayer::from(data)).map_err(|err| {
+            let message = err.to_string();
+            // Keep the typed error so hostile input can be told apart.
+            if let TiledError::DecompressionLimitExceeded { .. } = err {
+                report_error(err);
+            }
+            Error::custom(message)
+        })
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 #[serde(rename_all = "lowercase", tag = "type")]
+#[non_exhaustive]
 pub enum LayerType {
     TileLayer(TileLayer),
     ImageLayer(ImageLayer),
     ObjectGroup(ObjectGroup),
+    /// Placeholder for a layer that failed to parse, see `parse_with_errors`.
+    #[serde(skip_deserializing)]
+    Invalid {
+        index: usize,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub struct Layer {
+    /// Unique ID of the layer. Missing before Tiled 1.2.
+    pub id: Option<u32>,
     /// The name of the layer.
     pub name: String,
     /// The opacity of the layer as a value from 0 to 1. Defaults to 1.
     pub opacity: f32,
     /// Whether the layer is shown or hidden.
     pub visible: bool,
+    /// Horizontal parallax factor. Defaults to 1.
+    #[serde(default = "default_parallax", rename = "parallaxx")]
+    pub parallax_x: f32,
+    /// Vertical parallax factor. Defaults to 1.
+    #[serde(default = "default_parallax", rename = "parallaxy")]
+    pub parallax_y: f32,
+    /// Blend mode of the layer. Defaults to normal.
+    #[serde(default, rename = "blendmode")]
+    pub blend_mode: BlendMode,
+    /// Color multiplied with the layer, since Tiled 1.4.
+    #[serde(rename = "tintcolor")]
+    pub tint_color: Option<Color>,
 
     /// Layer data depends on layer type.
     #[serde(flatten)]
@@ -245,51 +1089,733 @@ pub struct Layer {
     pub properties: Option<Properties>,
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize)]
-pub struct Map {
-    /// File format version
-    #[serde(deserialize_with = "deserialize_version")]
-    pub version: String,
-    pub orientation: Orientation,
-    /// Number of tile columns
-    pub width: u32,
-    /// Number of tile rows
-    pub height: u32,
-    /// Map grid width
-    #[serde(rename = "tilewidth")]
-    pub tile_width: u32,
-    /// Map grid height
-    #[serde(rename = "tileheight")]
-    pub tile_height: u32,
-    pub tilesets: Vec<Tileset>,
-    pub layers: Vec<Layer>,
-    #[serde(rename = "backgroundcolor")]
-    pub background_colour: Option<Color>,
-    /// Custom properties
-    #[serde(default, deserialize_with = "deserialize_properties")]
-    pub properties: Option<Properties>,
+fn default_parallax() -> f32 {
+    1.0
 }
 
-/// Read buffer hopefully containing a Tiled map and try to parse it.
-pub fn parse<R: Read>(reader: R) -> Result<Map, TiledError> {
-    serde_json::from_reader(reader).map_err(TiledError::ParsingError)
+fn default_compression_level() -> i32 {
+    -1
 }
 
-/// Read file hopefully containing a Tiled map and try to parse it.
-pub fn parse_file(path: &Path) -> Result<Map, TiledError> {
-    let file = File::open(path).map_err(|err| TiledError::Other(format!("{:?}", err)))?;
-
-    parse(file)
+impl Layer {
+    /// Placeholder for a layer that failed to parse.
+    fn invalid(index: usize, value: &Value) -> Self {
+        Layer {
+            id: value["id"].as_u64().and_then(|id| u32::try_from(id).ok()),
+            name: value["name"].as_str().unwrap_or_default().to_string(),
+            opacity: 1.0,
+            visible: false,
+            parallax_x: 1.0,
+            parallax_y: 1.0,
+            blend_mode: BlendMode::Normal,
+            tint_color: None,
+            data: LayerType::Invalid { index },
+            properties: None,
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
+impl Object {
+    /// Placeholder for an object that failed to parse.
+    fn invalid(index: usize, value: &Value) -> Self {
+        Object {
+            id: value["id"]
+                .as_u64()
+                .and_then(|id| u32::try_from(id).ok())
+                .unwrap_or_default(),
+            name: value["name"].as_str().unwrap_or_default().into(),
+            r#type: Default::default(),
+            x: 0.0,
+            y: 0.0,
+            rotation: 0.0,
+            visible: false,
+            gid: None,
+            template: None,
+            shape: ObjectShape::Invalid { index },
+            properties: None,
+        }
+    }
 
-    #[test]
-    fn test_objects() {
-        let map = parse_file(&Path::new("assets/map.json")).unwrap();
+    /// Color the object is drawn with in Tiled: a `color` custom property of
+    /// the object, the color of its layer or gray.
+    pub fn display_color(&self, group: &ObjectGroup) -> Color {
+        match self.properties.as_ref().and_then(|p| p.get("color")) {
+            Some(Property::Color(color)) => *color,
+            _ => group.color.unwrap_or(DEFAULT_OBJECT_COLOR),
+        }
+    }
+}
+
+/// Color of objects in layers without a color, same as in Tiled.
+const DEFAULT_OBJECT_COLOR: Color = Color::new(0xa0, 0xa0, 0xa4, 0xff);
+
+fn deserialize_layers<'de, D>(deserializer: D) -> Result<Vec<Layer>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_collecting(deserializer, Layer::invalid, |index, source| {
+        TiledError::Layer {
+            index,
+            source: Box::new(source),
+        }
+    })
+}
+
+fn deserialize_objects<'de, D>(deserializer: D) -> Result<Vec<Object>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_collecting(deserializer, Object::invalid, |index, source| {
+        TiledError::Object {
+            index,
+            source: Box::new(source),
+        }
+    })
+}
+
+/// Deserialize a list element by element when collecting errors. Elements that
+/// fail are replaced with placeholders and their errors are reported.
+fn deserialize_collecting<'de, D, T, P, W>(
+    deserializer: D,
+    placeholder: P,
+    wrap: W,
+) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+    P: Fn(usize, &Value) -> T,
+    W: Fn(usize, TiledError) -> TiledError,
+{
+    if !current_options(|options| options.collect_errors) {
+        return Vec::deserialize(deserializer);
+    }
+
+    let values = Vec::<Value>::deserialize(deserializer)?;
+    let items = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let reported = reported_count();
+            let item = match T::deserialize(&value) {
+                Ok(item) => item,
+                Err(err) => {
+                    // Prefer the typed error if one was reported.
+                    if reported_count() == reported {
+                        report_error(TiledError::ParsingError(err));
+                    }
+                    placeholder(index, &value)
+                }
+            };
+            wrap_reported(reported, |err| wrap(index, err));
+            item
+        })
+        .collect();
+
+    Ok(items)
+}
+
+/// How many times tiles of a tileset are used, see `Map::tileset_usage`.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct TilesetUsage {
+    /// Number of tile layer cells.
+    pub cells: usize,
+    /// Number of tile objects.
+    pub objects: usize,
+}
+
+impl TilesetUsage {
+    /// Whether the tileset isn't used at all.
+    pub fn is_unused(&self) -> bool {
+        self.cells == 0 && self.objects == 0
+    }
+}
+
+/// Layer in the order it should be rendered, see `Map::render_layers`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderLayer<'a> {
+    pub layer: &'a Layer,
+    pub blend_mode: &'a BlendMode,
+    /// Effective opacity of the layer.
+    pub opacity: f32,
+    /// Effective visibility of the layer.
+    pub visible: bool,
+}
+
+impl Layer {
+    /// Offset of the layer in pixels.
+    pub fn offset(&self) -> (f32, f32) {
+        match &self.data {
+            LayerType::TileLayer(layer) => layer.offset(),
+            LayerType::ImageLayer(layer) => (layer.offset_x, layer.offset_y),
+            LayerType::ObjectGroup(group) => (group.offset_x, group.offset_y),
+            LayerType::Invalid { .. } => (0.0, 0.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(remote = "Self")]
+pub struct Map {
+    /// File format version
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version: String,
+    pub orientation: Orientation,
+    /// Number of tile columns
+    pub width: u32,
+    /// Number of tile rows
+    pub height: u32,
+    /// Map grid width
+    #[serde(rename = "tilewidth")]
+    pub tile_width: u32,
+    /// Map grid height
+    #[serde(rename = "tileheight")]
+    pub tile_height: u32,
+    /// Staggered axis (staggered and hexagonal maps only)
+    #[serde(rename = "staggeraxis")]
+    pub stagger_axis: Option<StaggerAxis>,
+    /// Shifted rows or columns (staggered and hexagonal maps only)
+    #[serde(rename = "staggerindex")]
+    pub stagger_index: Option<StaggerIndex>,
+    /// Length of the side of a hex tile in pixels (hexagonal maps only)
+    #[serde(rename = "hexsidelength")]
+    pub hex_side_length: Option<u32>,
+    pub tilesets: Vec<Tileset>,
+    #[serde(deserialize_with = "deserialize_layers")]
+    pub layers: Vec<Layer>,
+    #[serde(rename = "backgroundcolor")]
+    pub background_colour: Option<Color>,
+    /// X coordinate of the parallax origin in pixels. Defaults to 0.
+    #[serde(default, rename = "parallaxoriginx")]
+    pub parallax_origin_x: f32,
+    /// Y coordinate of the parallax origin in pixels. Defaults to 0.
+    #[serde(default, rename = "parallaxoriginy")]
+    pub parallax_origin_y: f32,
+    /// Compression level used for compressed tile layers, -1 means the
+    /// default level of the algorithm.
+    #[serde(default = "default_compression_level", rename = "compressionlevel")]
+    pub compression_level: i32,
+    /// Class of the map, since Tiled 1.9.
+    #[serde(default)]
+    pub class: Option<String>,
+    /// Custom properties
+    #[serde(default, deserialize_with = "deserialize_properties")]
+    pub properties: Option<Properties>,
+    /// Object indexes, see `ParseOptions::build_object_index`.
+    #[serde(skip)]
+    pub object_index: Option<ObjectIndex>,
+    /// See `warnings`.
+    #[serde(skip)]
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'de> Deserialize<'de> for Map {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let deserializer = TypeChecked(deserializer);
+        if parse_in_progress() {
+            return Map::deserialize(deserializer);
+        }
+
+        // Deserialized with serde directly instead of `parse`. Keep errors and
+        // warnings of the map from leaking into later parses.
+        let (map, errors, warnings) = isolated(|| Map::deserialize(deserializer));
+        let mut map = map?;
+        if let Some(err) = errors.into_iter().next() {
+            return Err(Error::custom(err));
+        }
+
+        map.warnings = warnings;
+        map.check_warnings();
+        Ok(map)
+    }
+}
+
+impl Map {
+    /// Empty orthogonal map without tilesets or layers.
+    pub fn new(width: u32, height: u32, tile_width: u32, tile_height: u32) -> Self {
+        Map {
+            version: "1.2".to_string(),
+            orientation: Orientation::Orthogonal,
+            width,
+            height,
+            tile_width,
+            tile_height,
+            stagger_axis: None,
+            stagger_index: None,
+            hex_side_length: None,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            background_colour: None,
+            parallax_origin_x: 0.0,
+            parallax_origin_y: 0.0,
+            compression_level: default_compression_level(),
+            class: None,
+            properties: None,
+            object_index: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Add a visible tile layer of the map size with tiles from `f(x, y)`.
+    pub fn with_generated_layer<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(u32, u32) -> u32,
+    {
+        self.layers.push(Layer {
+            id: None,
+            name: name.to_string(),
+            opacity: 1.0,
+            visible: true,
+            parallax_x: 1.0,
+            parallax_y: 1.0,
+            blend_mode: BlendMode::Normal,
+            tint_color: None,
+            data: LayerType::TileLayer(TileLayer::from_fn(self.width, self.height, f)),
+            properties: None,
+        });
+        self
+    }
+
+    /// Check that fields required by the orientation are present. Called
+    /// after parsing in strict mode, see `ParseOptions::strict`.
+    pub fn validate(&self) -> Result<(), TiledError> {
+        let missing = |field: &str| {
+            Err(TiledError::Other(format!(
+                "{:?} map is missing {}",
+                self.orientation, field
+            )))
+        };
+
+        if let Orientation::Staggered | Orientation::Hexagonal = self.orientation {
+            if self.stagger_axis.is_none() {
+                return missing("staggeraxis");
+            }
+            if self.stagger_index.is_none() {
+                return missing("staggerindex");
+            }
+        }
+
+        if self.orientation == Orientation::Hexagonal && self.hex_side_length.is_none() {
+            return missing("hexsidelength");
+        }
+
+        Ok(())
+    }
+
+    /// Build indexes used by `find_objects_fast`. Must be called again after
+    /// objects are modified.
+    pub fn build_object_index(&mut self) {
+        self.object_index = Some(ObjectIndex::build(&self.layers));
+    }
+
+    /// Object with given ID. Uses the object index if it has been built and
+    /// falls back to a linear search if the index is out of date.
+    pub fn object_by_id(&self, id: ObjectId) -> Option<&Object> {
+        let indexed = self
+            .object_index
+            .as_ref()
+            .and_then(|index| index.location(id))
+            .and_then(|(layer, object)| match &self.layers.get(layer)?.data {
+                LayerType::ObjectGroup(group) => group.objects.get(object),
+                _ => None,
+            })
+            .filter(|object| object.id == id);
+
+        indexed.or_else(|| self.objects().find(|object| object.id == id))
+    }
+
+    /// Objects matching the key. Uses the object index if it has been built
+    /// and falls back to a linear search otherwise.
+    pub fn find_objects_fast(&self, key: ObjectKey) -> Vec<&Object> {
+        match &self.object_index {
+            Some(index) => index
+                .ids(key)
+                .iter()
+                .filter_map(|id| self.object_by_id(*id))
+                .collect(),
+            None => self
+                .objects()
+                .filter(|object| match key {
+                    ObjectKey::Name(name) => object.name == name,
+                    ObjectKey::Type(r#type) => object.r#type == r#type,
+                    ObjectKey::Property(name) => object
+                        .properties
+                        .as_ref()
+                        .is_some_and(|properties| properties.contains_key(name)),
+                })
+                .collect(),
+        }
+    }
+
+    /// Iterate layers in the order they should be rendered, bottom first,
+    /// with blend mode and effective opacity and visibility.
+    pub fn render_layers(&self) -> impl Iterator<Item = RenderLayer<'_>> {
+        self.layers.iter().map(|layer| RenderLayer {
+            layer,
+            blend_mode: &layer.blend_mode,
+            opacity: layer.opacity,
+            visible: layer.visible,
+        })
+    }
+
+    /// Count how many layer cells and tile objects reference each tileset.
+    /// The result has the same order as `tilesets`. Encoded layers are not
+    /// counted.
+    pub fn tileset_usage(&self) -> Vec<TilesetUsage> {
+        let mut usage = vec![TilesetUsage::default(); self.tilesets.len()];
+
+        // Tileset indices sorted by first GID for binary search.
+        let mut order: Vec<usize> = (0..self.tilesets.len()).collect();
+        order.sort_by_key(|i| self.tilesets[*i].first_gid);
+
+        let find = |gid: u32| -> Option<usize> {
+            let gid = gid & !GID_FLAGS;
+            if gid == 0 {
+                return None;
+            }
+
+            let position = order.partition_point(|i| self.tilesets[*i].first_gid <= gid);
+            let index = order[position.checked_sub(1)?];
+            Some(index).filter(|i| self.tilesets[*i].contains_gid(gid))
+        };
+
+        for layer in &self.layers {
+            match &layer.data {
+                LayerType::TileLayer(tile_layer) => {
+                    // Consecutive tiles are often identical.
+                    for (_, _, length, gid) in tile_layer.runs() {
+                        if let Some(index) = find(gid) {
+                            usage[index].cells += length as usize;
+                        }
+                    }
+                }
+                LayerType::ObjectGroup(group) => {
+                    for gid in group.objects.iter().filter_map(|object| object.gid) {
+                        if let Some(index) = find(gid) {
+                            usage[index].objects += 1;
+                        }
+                    }
+                }
+                LayerType::ImageLayer(_) | LayerType::Invalid { .. } => {}
+            }
+        }
+
+        usage
+    }
+
+    /// First layer with given name.
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Tileset containing the global tile ID. Flip flags are ignored.
+    pub fn tileset_for_gid(&self, gid: u32) -> Option<&Tileset> {
+        self.tilesets
+            .iter()
+            .filter(|tileset| tileset.contains_gid(gid))
+            .max_by_key(|tileset| tileset.first_gid)
+    }
+
+    /// Animated tiles of all tilesets, ordered by GID, so animations can be
+    /// registered up front.
+    pub fn animations(&self) -> Vec<Animation> {
+        let mut animations: Vec<Animation> = self
+            .tilesets
+            .iter()
+            .flat_map(|tileset| {
+                tileset.tiles.iter().flatten().filter_map(move |tile| {
+                    let frames = tile.animation.as_ref()?;
+                    Some(Animation {
+                        gid: tileset.first_gid + tile.id,
+                        frames: frames
+                            .iter()
+                            .map(|frame| (tileset.first_gid + frame.tile_id, frame.duration))
+                            .collect(),
+                    })
+                })
+            })
+            .collect();
+
+        animations.sort_by_key(|animation| animation.gid);
+        animations
+    }
+
+    /// Resolve property of the tile at map coordinates of a tile layer.
+    /// Properties of the tile are looked up first, then properties of its
+    /// tileset, the layer and the map. `None` for layers that are still
+    /// encoded, see `TileLayer::decode`.
+    pub fn cell_property(&self, layer: usize, x: i32, y: i32, name: &str) -> Option<&Property> {
+        let layer = self.layers.get(layer)?;
+        let gid = match &layer.data {
+            LayerType::TileLayer(tile_layer) => tile_layer.get_tile_signed(x, y)?,
+            _ => return None,
+        };
+
+        self.tileset_for_gid(gid)
+            .and_then(|tileset| tileset.property(gid, name))
+            .or_else(|| layer.properties.as_ref()?.get(name))
+            .or_else(|| self.properties.as_ref()?.get(name))
+    }
+
+    /// Iterate objects of all object layers.
+    pub fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.layers
+            .iter()
+            .filter_map(|layer| match &layer.data {
+                LayerType::ObjectGroup(group) => Some(&group.objects),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Select layers or objects with a query string, eg.
+    /// `objects[type=enemy][hp>10]`. See `Query` for the syntax.
+    pub fn query(&self, query: &str) -> Result<Vec<Selected<'_>>, TiledError> {
+        Ok(query.parse::<Query>()?.select(self))
+    }
+
+    /// Set file format version of the map and rewrite legacy fields like
+    /// `upgrade_json` does. Object and tile `type` is already read as class,
+    /// so only tileset terrains are converted to wang sets. Downgrading is not
+    /// supported.
+    pub fn upgrade_to(&mut self, version: &str) -> Result<(), TiledError> {
+        let target = parse_version(version)
+            .ok_or_else(|| TiledError::Other(format!("Invalid version {:?}", version)))?;
+
+        if parse_version(&self.version).is_some_and(|current| target < current) {
+            return Err(TiledError::Other(format!(
+                "Can't downgrade map from {} to {}",
+                self.version, version
+            )));
+        }
+
+        if target >= upgrade::WANG_SETS {
+            self.tilesets.iter_mut().for_each(Tileset::upgrade_terrains);
+        }
+
+        self.version = version.trim().to_string();
+
+        // The version is now written as a string.
+        self.warnings.clear();
+        self.check_warnings();
+        Ok(())
+    }
+
+    /// Hash of the map contents. The hash is stable across runs and platforms
+    /// and doesn't depend on the order of custom properties.
+    pub fn content_hash(&self) -> u64 {
+        content_hash(self)
+    }
+
+    /// Share tile storage between tile layers that have identical data.
+    /// Returns the number of layers that now reuse storage of another layer.
+    pub fn dedup_identical_layers(&mut self) -> usize {
+        let mut seen: HashMap<u64, Vec<Arc<Vec<u32>>>> = HashMap::new();
+        let mut count = 0;
+
+        for layer in &mut self.layers {
+            if let LayerType::TileLayer(tile_layer) = &mut layer.data {
+                if tile_layer.is_encoded() {
+                    continue;
+                }
+
+                // Layers at different positions can share tiles too.
+                let candidates = seen.entry(tile_data_hash(tile_layer)).or_default();
+
+                // Compare the data too, hash collisions are possible.
+                if let Some(shared) = candidates.iter().find(|tiles| **tiles == tile_layer.tiles) {
+                    if !Arc::ptr_eq(shared, &tile_layer.tiles) {
+                        tile_layer.tiles = Arc::clone(shared);
+                        count += 1;
+                    }
+                } else {
+                    candidates.push(Arc::clone(&tile_layer.tiles));
+                }
+            }
+        }
+
+        count
+    }
+}
+
+/// First layer with given name.
+///
+/// # Panics
+///
+/// If the map has no layer with the name. Use `Map::layer` to check.
+impl Index<&str> for Map {
+    type Output = Layer;
+
+    fn index(&self, name: &str) -> &Layer {
+        self.layer(name)
+            .unwrap_or_else(|| panic!("map has no layer {:?}", name))
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = &'a Layer;
+    type IntoIter = std::slice::Iter<'a, Layer>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.layers.iter()
+    }
+}
+
+/// Read buffer hopefully containing a Tiled map and try to parse it.
+pub fn parse<R: Read>(reader: R) -> Result<Map, TiledError> {
+    parse_with_options(reader, &ParseOptions::default())
+}
+
+/// Read file hopefully containing a Tiled map and try to parse it.
+pub fn parse_file(path: &Path) -> Result<Map, TiledError> {
+    parse_file_with_options(path, &ParseOptions::default())
+}
+
+/// Read buffer hopefully containing a Tiled map and try to parse it with
+/// given options.
+pub fn parse_with_options<R: Read>(reader: R, options: &ParseOptions) -> Result<Map, TiledError> {
+    with_options(options, || {
+        parse_reader_with_options(reader, options).map(add_warnings)
+    })
+    .and_then(|map| finish_map(map, options))
+}
+
+fn parse_reader_with_options<R: Read>(
+    mut reader: R,
+    options: &ParseOptions,
+) -> Result<Map, TiledError> {
+    if let Some(hint) = options.preallocate_hint {
+        let mut buffer = Vec::with_capacity(hint);
+        reader
+            .read_to_end(&mut buffer)
+            .map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+
+        return parse_slice(&buffer, options);
+    }
+
+    trace::phase("deserialize", 0, || {
+        if !options.lenient && !options.strict {
+            return json_from_reader(reader);
+        }
+
+        map_from_value(json_from_reader(reader)?, options)
+    })
+}
+
+/// Parse Tiled map from memory with given options.
+pub fn parse_slice_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Map, TiledError> {
+    with_options(options, || parse_slice(bytes, options).map(add_warnings))
+        .and_then(|map| finish_map(map, options))
+}
+
+/// Parse Tiled map from memory. UTF-16 input, which some export plugins
+/// write on Windows, is converted to UTF-8 first.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Map, TiledError> {
+    parse_slice_with_options(&transcode_to_utf8(bytes)?, &ParseOptions::default())
+}
+
+/// Add warnings of the parse in progress to the map.
+pub(crate) fn add_warnings(mut map: Map) -> Map {
+    map.warnings = take_warnings();
+    map.check_warnings();
+    map
+}
+
+/// Post-processing of a parsed map.
+fn finish_map(mut map: Map, options: &ParseOptions) -> Result<Map, TiledError> {
+    if options.strict && !options.lenient {
+        map.validate()?;
+    }
+
+    if let Some(project) = &options.project {
+        map.apply_class_defaults(project);
+    }
+
+    if options.build_object_index {
+        map.build_object_index();
+    }
+
+    Ok(map)
+}
+
+fn parse_slice(bytes: &[u8], options: &ParseOptions) -> Result<Map, TiledError> {
+    trace::phase("deserialize", bytes.len(), || {
+        if !options.lenient && !options.strict {
+            return json_from_slice(bytes);
+        }
+
+        map_from_value(json_from_slice(bytes)?, options)
+    })
+}
+
+/// Parse a map read as JSON value, in lenient or strict mode.
+pub(crate) fn map_from_value(mut value: Value, options: &ParseOptions) -> Result<Map, TiledError> {
+    if options.lenient {
+        normalize_enum_values(&mut value);
+    } else if options.strict {
+        check_unknown_fields(&value)?;
+    }
+
+    deserialize_with_path(value)
+}
+
+/// Parse everything that can be parsed instead of failing on the first error.
+/// Layers and objects that fail to parse are replaced with placeholders
+/// (`LayerType::Invalid` and `ObjectShape::Invalid`) and all errors are
+/// returned with the map. Errors outside of layers still fail the parse.
+pub fn parse_with_errors<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<(Map, Vec<TiledError>), TiledError> {
+    let mut options = options.clone();
+    options.collect_errors = true;
+
+    let (mut map, mut errors) = with_options_collecting(&options, || {
+        parse_reader_with_options(reader, &options).map(add_warnings)
+    })?;
+
+    if options.strict && !options.lenient {
+        if let Err(err) = map.validate() {
+            errors.push(err);
+        }
+    }
+
+    if let Some(project) = &options.project {
+        map.apply_class_defaults(project);
+    }
+
+    if options.build_object_index {
+        map.build_object_index();
+    }
+
+    Ok((map, errors))
+}
+
+/// Read file hopefully containing a Tiled map and try to parse it with given
+/// options.
+pub fn parse_file_with_options(path: &Path, options: &ParseOptions) -> Result<Map, TiledError> {
+    let file = File::open(path).map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+
+    if options.preallocate_hint.is_none() {
+        if let Ok(metadata) = file.metadata() {
+            let options = options.clone().preallocate_hint(metadata.len() as usize);
+            return parse_with_options(file, &options);
+        }
+    }
+
+    parse_with_options(file, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_objects() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
 
         // Map file should contain non-empty object layer.
         map.layers
@@ -304,7 +1830,7 @@ mod tests {
     #[allow(clippy::approx_constant)]
     #[test]
     fn test_properties() {
-        let map = parse_file(&Path::new("assets/map.json")).unwrap();
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
 
         // Map file should contain properties.
         let properties = map.properties.unwrap();
@@ -313,10 +1839,33 @@ mod tests {
         assert_eq!(properties.get("answer").unwrap(), &Property::Int(42));
     }
 
+    #[test]
+    fn test_property_conversions() {
+        let int = Property::from(42);
+        assert_eq!(i32::try_from(&int).unwrap(), 42);
+        assert_eq!(f32::try_from(&int).unwrap(), 42.0);
+        assert!(bool::try_from(&int).is_err());
+        assert_eq!(int.as_str(), None);
+
+        let string = Property::File("a.png".to_string());
+        assert_eq!(<&str>::try_from(&string).unwrap(), "a.png");
+        assert_eq!(string.to_string(), "a.png");
+
+        for s in &["true", "-3", "1.5", "#ff00ff00", "hello"] {
+            assert_eq!(s.parse::<Property>().unwrap().to_string(), *s);
+        }
+        assert_eq!("1.5".parse::<Property>().unwrap(), Property::Float(1.5));
+        assert_eq!(
+            Property::parse_typed("string", "10").unwrap(),
+            Property::String("10".to_string())
+        );
+        assert!(Property::parse_typed("int", "ten").is_err());
+    }
+
     #[test]
     fn test_encodings() {
-        let a = parse_file(&Path::new("assets/map.json")).unwrap();
-        let b = parse_file(&Path::new("assets/map_csv.json")).unwrap();
+        let a = parse_file(Path::new("assets/map.json")).unwrap();
+        let b = parse_file(Path::new("assets/map_csv.json")).unwrap();
 
         // Map file should contain properties.
         assert_eq!(a.layers.len(), b.layers.len());
@@ -329,4 +1878,3250 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_content_hash() {
+        let a = parse_file(Path::new("assets/map.json")).unwrap();
+        let b = parse_file(Path::new("assets/map.json")).unwrap();
+        let csv = parse_file(Path::new("assets/map_csv.json")).unwrap();
+
+        // Properties are stored in hash maps with random iteration order.
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        for i in 0..a.layers.len() {
+            if let (LayerType::TileLayer(a), LayerType::TileLayer(b)) =
+                (&a.layers[i].data, &csv.layers[i].data)
+            {
+                assert_eq!(a.content_hash(), b.content_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_dedup_identical_layers() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let copy = map.layers[0].clone();
+        map.layers.push(copy);
+
+        // Clone already shares the storage.
+        assert_eq!(map.dedup_identical_layers(), 0);
+
+        if let LayerType::TileLayer(layer) = &mut map.layers[3].data {
+            layer.tiles = Arc::new(layer.tiles.to_vec());
+        }
+
+        assert_eq!(map.dedup_identical_layers(), 1);
+
+        let mut moved = map.layers[0].clone();
+        if let LayerType::TileLayer(layer) = &mut moved.data {
+            layer.tiles = Arc::new(layer.tiles.to_vec());
+            layer.set_offset(8.0, 4.0);
+        }
+        map.layers.push(moved);
+        assert_eq!(map.dedup_identical_layers(), 1);
+    }
+
+    #[test]
+    fn test_expand_properties() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        let mut properties = Properties::new();
+        properties.insert(
+            "greeting".to_string(),
+            Property::String("${answer} on ${map.width}x${map.height} ${missing".to_string()),
+        );
+        properties.insert(
+            "nested".to_string(),
+            Property::String("<${greeting}> ${unknown}".to_string()),
+        );
+
+        let context = ExpandContext::new().with_map(&map);
+        let expanded = properties.expand(&context);
+
+        assert_eq!(
+            expanded.get("nested").unwrap(),
+            &Property::String("<42 on 10x10 ${missing> ${unknown}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_runs() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        for layer in &map.layers {
+            if let LayerType::TileLayer(layer) = &layer.data {
+                let mut count = 0;
+
+                for (x, y, length, gid) in layer.runs() {
+                    assert!(x + length <= layer.width);
+                    for i in x..x + length {
+                        assert_eq!(layer.get_tile(i, y), gid);
+                    }
+                    count += length;
+                }
+
+                assert_eq!(count as usize, layer.tiles.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_lenient_enums() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replace("\"orthogonal\"", "\"Orthogonal\"")
+            .replace("\"base64\"", "\"Base64 \"")
+            .replace("\"zlib\"", "\"ZLIB\"");
+
+        assert!(parse(json.as_bytes()).is_err());
+
+        let options = ParseOptions::new().lenient(true);
+        let map = parse_with_options(json.as_bytes(), &options).unwrap();
+        assert_eq!(map, parse_file(Path::new("assets/map.json")).unwrap());
+    }
+
+    #[test]
+    fn test_tile_layer_new() {
+        let layer = TileLayer::new(2, 2, vec![1, 2, 3, 4]).unwrap();
+
+        assert_eq!(layer.width(), 2);
+        assert_eq!(layer.height(), 2);
+        assert_eq!(layer.get_tile(0, 1), 3);
+        assert_eq!(layer.clone().into_tiles(), layer.tiles());
+
+        assert!(TileLayer::new(2, 2, vec![1, 2, 3]).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_parse_file_mmap() {
+        let path = Path::new("assets/map.json");
+        assert_eq!(parse_file_mmap(path).unwrap(), parse_file(path).unwrap());
+
+        let options = ParseOptions::new().lenient(true);
+        let path = Path::new("assets/map_infinite.json");
+        assert_eq!(
+            parse_file_mmap_with_options(path, &options).unwrap(),
+            parse_file_with_options(path, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_infinite_map() {
+        let map = parse_file(Path::new("assets/map_infinite.json")).unwrap();
+
+        let layer = match &map.layers[0].data {
+            LayerType::TileLayer(layer) => layer,
+            _ => panic!("Expected tile layer"),
+        };
+
+        assert_eq!((layer.start_x(), layer.start_y()), (-16, -16));
+        assert_eq!(layer.get_tile_signed(-16, -16), Some(3));
+        assert_eq!(layer.get_tile_signed(-1, -1), Some(3));
+        assert_eq!(layer.get_tile_signed(-2, -1), Some(0));
+        assert_eq!(layer.get_tile_signed(0, 0), Some(7));
+        assert_eq!(layer.get_tile_signed(15, 15), Some(1));
+        // Area not covered by chunks is empty.
+        assert_eq!(layer.get_tile_signed(0, -1), Some(0));
+        assert_eq!(layer.get_tile_signed(16, 0), None);
+        assert_eq!(layer.get_tile_signed(-17, 0), None);
+
+        // Compressed chunks.
+        let layer = match &map.layers[1].data {
+            LayerType::TileLayer(layer) => layer,
+            _ => panic!("Expected tile layer"),
+        };
+
+        assert_eq!(layer.get_tile_signed(16, -16), Some(2));
+        assert_eq!(layer.get_tile_signed(31, -1), Some(2));
+        assert_eq!(layer.get_tile_signed(0, 0), None);
+    }
+
+    #[cfg(feature = "compat")]
+    #[test]
+    fn test_compat_views() {
+        use crate::compat::{self, LayerType};
+
+        let parsed = parse_file(Path::new("assets/map.json")).unwrap();
+        let map = compat::Map::new(&parsed);
+        assert_eq!(map.layers().len(), 3);
+
+        let layer = map.get_layer(1).unwrap();
+        assert_eq!(layer.name(), "Tile Layer 2");
+        match layer.layer_type() {
+            LayerType::Tiles(tiles) => {
+                let tile = tiles.get_tile(7, 1).unwrap();
+                assert_eq!((tile.tileset_index(), tile.id()), (0, 6));
+                assert!(!tile.flip_h);
+                assert!(tiles.get_tile(0, 0).is_none());
+                assert!(tiles.get_tile(-1, 0).is_none());
+            }
+            _ => panic!("expected tile layer"),
+        }
+
+        match map.get_layer(2).unwrap().layer_type() {
+            LayerType::Objects(objects) => {
+                assert_eq!(objects.get_object(0).unwrap().id(), 1);
+            }
+            _ => panic!("expected object layer"),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_json() {
+        let file = File::open("assets/map.json").unwrap();
+        let mut value: Value = serde_json::from_reader(file).unwrap();
+
+        upgrade_json(&mut value, "1.9").unwrap();
+        assert_eq!(value["version"], Value::String("1.9".to_string()));
+        assert!(value["layers"][2]["objects"][0].get("type").is_none());
+        assert!(value["layers"][2]["objects"][0].get("class").is_some());
+
+        // Upgraded file should still parse to the same map.
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        map.upgrade_to("1.9").unwrap();
+        assert_eq!(serde_json::from_value::<Map>(value.clone()).unwrap(), map);
+
+        assert!(upgrade_json(&mut value, "1.2").is_err());
+    }
+
+    #[test]
+    fn test_upgrade_terrains() {
+        let mut value = serde_json::json!({
+            "version": 1.2,
+            "tilesets": [{
+                "terrains": [{ "name": "grass", "tile": 0 }, { "name": "water", "tile": 1 }],
+                "tiles": [{ "id": 5, "terrain": [0, 1, -1, 0] }]
+            }]
+        });
+
+        upgrade_json(&mut value, "1.5").unwrap();
+
+        let tileset = &value["tilesets"][0];
+        assert!(tileset.get("terrains").is_none());
+        assert!(tileset["tiles"][0].get("terrain").is_none());
+        assert_eq!(tileset["wangsets"][0]["colors"][1]["name"], "water");
+        assert_eq!(tileset["wangsets"][0]["colors"][0]["color"], "#ff0000");
+        assert_eq!(tileset["wangsets"][0]["colors"][1]["color"], "#00ff00");
+        assert_eq!(
+            tileset["wangsets"][0]["wangtiles"][0]["wangid"],
+            serde_json::json!([0, 2, 0, 1, 0, 0, 0, 1])
+        );
+        assert_eq!(value["version"], serde_json::json!(1.5));
+    }
+
+    #[test]
+    fn test_upgrade_terrains_typed() {
+        let mut value: Value =
+            serde_json::from_slice(&std::fs::read("assets/map.json").unwrap()).unwrap();
+        value["tilesets"][0]["terrains"] =
+            serde_json::json!([{ "name": "grass", "tile": 0 }, { "name": "water", "tile": 1 }]);
+        value["tilesets"][0]["tiles"] = serde_json::json!([{ "id": 5, "terrain": [0, 1, -1, 0] }]);
+
+        let mut map = parse(&serde_json::to_vec(&value).unwrap()[..]).unwrap();
+        map.upgrade_to("1.6").unwrap();
+        upgrade_json(&mut value, "1.6").unwrap();
+
+        let tileset = &map.tilesets[0];
+        assert!(tileset.terrains.is_none());
+        assert!(tileset
+            .tiles
+            .iter()
+            .flatten()
+            .all(|tile| tile.terrain.is_none()));
+        assert_eq!(serde_json::from_value::<Map>(value).unwrap(), map);
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(
+            paths::resolve(Path::new("maps/level1"), "..\\tiles\\.\\grass.png"),
+            Path::new("maps/tiles/grass.png")
+        );
+        assert_eq!(
+            paths::resolve(Path::new(""), "../../shared/a.png"),
+            Path::new("../../shared/a.png")
+        );
+
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        map.tilesets[0].image = "..\\art\\tilesheet.png".to_string();
+        let mut properties = Properties::new();
+        properties.insert(
+            "music".to_string(),
+            Property::File("sfx/theme.ogg".to_string()),
+        );
+        properties.insert("name".to_string(), Property::String("a\\b".to_string()));
+        map.properties = Some(properties);
+
+        map.rebase_paths(Path::new("game/maps"), Path::new("game/packed/level1"));
+
+        assert_eq!(map.tilesets[0].image, "../../art/tilesheet.png");
+        let properties = map.properties.as_ref().unwrap();
+        assert_eq!(
+            properties["music"],
+            Property::File("../../maps/sfx/theme.ogg".to_string())
+        );
+        assert_eq!(properties["name"], Property::String("a\\b".to_string()));
+    }
+
+    #[test]
+    fn test_load_images() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let mut second = map.tilesets[0].clone();
+        second.image = "./tilesheet.png".to_string();
+        map.tilesets.push(second);
+
+        let mut loaded = Vec::new();
+        let handles = map
+            .load_images(Path::new("assets"), |image| {
+                loaded.push(image.path.clone());
+                Ok(image.source.len())
+            })
+            .unwrap();
+
+        assert_eq!(loaded, vec![Path::new("assets/tilesheet.png")]);
+        assert_eq!(handles.len(), 1);
+        assert_eq!(handles.get("./tilesheet.png"), Some(&13));
+        assert_eq!(handles.get("missing.png"), None);
+
+        let failed = map.load_images(Path::new("assets"), |_| -> Result<(), _> {
+            Err(TiledError::Other("no decoder".to_string()))
+        });
+        assert!(failed.is_err());
+    }
+
+    #[test]
+    fn test_tiles_in_view() {
+        let mut map = Map::new(4, 4, 32, 16);
+        let layer = TileLayer::from_fn(4, 4, |x, y| 1 + x + y * 4);
+        let tiles = |map: &Map, view: ViewRect| -> Vec<u32> {
+            layer
+                .tiles_in_view(view, map)
+                .map(|(_, _, gid)| gid)
+                .collect()
+        };
+
+        assert_eq!(
+            tiles(&map, ViewRect::new(40.0, 20.0, 30.0, 20.0)),
+            vec![6, 7, 10, 11]
+        );
+        assert_eq!(
+            tiles(&map, ViewRect::new(-100.0, -100.0, 1000.0, 1000.0)).len(),
+            16
+        );
+        assert!(tiles(&map, ViewRect::new(200.0, 0.0, 10.0, 10.0)).is_empty());
+
+        // Isometric maps start from the top corner at x = height * 16.
+        map.orientation = Orientation::Isometric;
+        assert_eq!(tiles(&map, ViewRect::new(63.0, 1.0, 2.0, 2.0)), vec![1]);
+        assert_eq!(
+            tiles(&map, ViewRect::new(63.0, 12.0, 2.0, 6.0)),
+            vec![1, 2, 5, 6]
+        );
+
+        // Odd rows of staggered maps are shifted half a tile right.
+        map.orientation = Orientation::Staggered;
+        map.stagger_axis = Some(StaggerAxis::Y);
+        map.stagger_index = Some(StaggerIndex::Odd);
+        assert_eq!(tiles(&map, ViewRect::new(1.0, 9.0, 2.0, 2.0)), vec![1]);
+        assert_eq!(tiles(&map, ViewRect::new(17.0, 9.0, 2.0, 2.0)), vec![1, 5]);
+    }
+
+    #[test]
+    fn test_thumbnail_cache() {
+        let dir = std::env::temp_dir().join(format!("tiled-json-thumbs-{}", std::process::id()));
+        let cache = ThumbnailCache::new(&dir);
+        let mut map = parse_file(Path::new("assets/map_csv.json")).unwrap();
+
+        let mut renders = 0;
+        let mut render = |map: &Map, size: u32| {
+            renders += 1;
+            Ok(format!("{} {}", map.layers.len(), size).into_bytes())
+        };
+
+        assert_eq!(cache.get(&map, 64), None);
+        assert_eq!(cache.get_or_render(&map, 64, &mut render).unwrap(), b"3 64");
+        assert_eq!(cache.get_or_render(&map, 64, &mut render).unwrap(), b"3 64");
+        assert_eq!(cache.get(&map, 64), Some(b"3 64".to_vec()));
+        assert_eq!(cache.get_or_render(&map, 32, &mut render).unwrap(), b"3 32");
+
+        map.layers.pop();
+        assert_eq!(cache.get_or_render(&map, 64, &mut render).unwrap(), b"2 64");
+        assert_eq!(renders, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_object_group_attributes() {
+        let mut json: Value =
+            serde_json::from_slice(&std::fs::read("assets/map_csv.json").unwrap()).unwrap();
+        json["layers"][2]["offsetx"] = serde_json::json!(8);
+        json["layers"][2]["opacity"] = serde_json::json!(0.5);
+        json["layers"][0]["offsety"] = serde_json::json!(-4);
+        let map: Map = serde_json::from_value(json).unwrap();
+
+        assert_eq!(map.layers[2].id, Some(3));
+        match &map.layers[2].data {
+            LayerType::ObjectGroup(group) => {
+                assert_eq!((group.offset_x, group.offset_y), (8.0, 0.0));
+            }
+            _ => panic!("expected object layer"),
+        }
+        assert_eq!(map.layers[2].offset(), (8.0, 0.0));
+        assert_eq!(map.layers[2].opacity, 0.5);
+        assert_eq!(map.layers[0].offset(), (0.0, -4.0));
+
+        let tile: Tile = serde_json::from_value(serde_json::json!({
+            "id": 3,
+            "objectgroup": {
+                "draworder": "index",
+                "id": 2,
+                "name": "",
+                "objects": [{
+                    "height": 8, "id": 1, "name": "", "rotation": 0, "type": "",
+                    "visible": true, "width": 16, "x": 0, "y": 8
+                }],
+                "opacity": 0.75,
+                "type": "objectgroup",
+                "visible": true,
+                "x": 0,
+                "y": 0
+            }
+        }))
+        .unwrap();
+        let layer = tile.object_group.unwrap();
+        assert_eq!((layer.id, layer.opacity), (Some(2), 0.75));
+        match &layer.data {
+            LayerType::ObjectGroup(group) => {
+                assert_eq!(group.objects[0].y, 8.0);
+            }
+            _ => panic!("expected object layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let json = std::fs::read_to_string("assets/map_csv.json").unwrap();
+        let expected = parse_bytes(json.as_bytes()).unwrap();
+
+        let utf16 = |big_endian: bool, bom: bool| -> Vec<u8> {
+            let bom = if bom { Some('\u{feff}') } else { None };
+            bom.into_iter()
+                .chain(json.chars())
+                .collect::<String>()
+                .encode_utf16()
+                .flat_map(|unit| {
+                    if big_endian {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    }
+                })
+                .collect()
+        };
+
+        for &(big_endian, bom) in
+            [(false, true), (true, true), (false, false), (true, false)].iter()
+        {
+            assert_eq!(parse_bytes(&utf16(big_endian, bom)).unwrap(), expected);
+        }
+
+        let mut odd = utf16(false, true);
+        odd.pop();
+        assert!(matches!(
+            parse_bytes(&odd),
+            Err(TiledError::UnsupportedEncoding(_))
+        ));
+        assert!(matches!(
+            parse_bytes(b"\xff\xfe\0\0{\0\0\0"),
+            Err(TiledError::UnsupportedEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_bom() {
+        let json = std::fs::read("assets/map.json").unwrap();
+        for (bom, encoding) in [
+            (&b"\0\0\xfe\xff"[..], "UTF-32BE"),
+            (b"\xff\xfe\0\0", "UTF-32LE"),
+            (b"\xff\xfe", "UTF-16LE"),
+            (b"+/v8", "UTF-7"),
+            (b"\x84\x31\x95\x33", "GB18030"),
+        ]
+        .iter()
+        {
+            let bytes = [*bom, &json[..]].concat();
+            let options = ParseOptions::new();
+            for result in [
+                parse_slice_with_options(&bytes, &options),
+                parse(&bytes[..]),
+            ]
+            .iter()
+            {
+                match result {
+                    Err(TiledError::UnsupportedEncoding(name)) => assert_eq!(name, encoding),
+                    result => panic!("Expected unsupported encoding, got {:?}", result),
+                }
+            }
+        }
+
+        let bytes = [&b"\xef\xbb\xbf"[..], &json[..]].concat();
+        assert!(parse(&bytes[..]).is_ok());
+    }
+
+    #[test]
+    fn test_walk() {
+        #[derive(Default)]
+        struct Stats {
+            layers: usize,
+            tiles: usize,
+            objects: Vec<ObjectId>,
+        }
+
+        impl MapVisitor for Stats {
+            fn visit_layer(&mut self, layer: &Layer) -> bool {
+                self.layers += 1;
+                layer.name != "Tile Layer 1"
+            }
+
+            fn visit_tile(&mut self, _x: u32, _y: u32, _gid: u32) {
+                self.tiles += 1;
+            }
+
+            fn visit_object(&mut self, _layer: &Layer, object: &Object) {
+                self.objects.push(object.id);
+            }
+        }
+
+        let map = parse_file(Path::new("assets/map_csv.json")).unwrap();
+        let mut stats = Stats::default();
+        map.walk(&mut stats);
+
+        assert_eq!(stats.layers, 3);
+        assert_eq!(stats.tiles, 26);
+        assert_eq!(stats.objects, vec![1, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_color_forms() {
+        let color = |s: &str| s.parse::<Color>().unwrap();
+
+        assert_eq!(color("#F80"), color("#ff8800"));
+        assert_eq!(color("#8F80"), color("#88ff8800"));
+        assert_eq!(color("#AbCdEf"), color("#abcdef"));
+        assert!("#ff888".parse::<Color>().is_err());
+        assert!("#ff88zz".parse::<Color>().is_err());
+        assert!("#ffé8".parse::<Color>().is_err());
+
+        for s in ["#80ff0000", "#ff0000"].iter() {
+            let with_alpha = s.len() == 9;
+            assert_eq!(color(s).to_tiled_string(with_alpha), *s);
+        }
+        assert_eq!(color("#0f0").to_tiled_string(true), "#ff00ff00");
+    }
+
+    #[test]
+    fn test_bom_and_trailing_data() {
+        let json = std::fs::read("assets/map_csv.json").unwrap();
+        let expected = parse_slice_with_options(&json, &ParseOptions::new()).unwrap();
+
+        let mut padded = b"\xef\xbb\xbf".to_vec();
+        padded.extend_from_slice(&json);
+        padded.extend_from_slice(b"\r\n\n  ");
+        assert_eq!(parse(&padded[..]).unwrap(), expected);
+        assert_eq!(
+            parse_slice_with_options(&padded, &ParseOptions::new()).unwrap(),
+            expected
+        );
+
+        padded.extend_from_slice(b"garbage");
+        assert!(matches!(
+            parse(&padded[..]),
+            Err(TiledError::TrailingData { .. })
+        ));
+        assert!(matches!(
+            parse_slice_with_options(&padded, &ParseOptions::new().lenient(true)),
+            Err(TiledError::TrailingData { .. })
+        ));
+    }
+
+    #[cfg(feature = "intern")]
+    #[test]
+    fn test_interner() {
+        let interner = Interner::new();
+        let options = ParseOptions::new().interner(&interner);
+        let map = parse_file_with_options(Path::new("assets/map_csv.json"), &options).unwrap();
+        let other = parse_file_with_options(Path::new("assets/map_csv.json"), &options).unwrap();
+
+        let object = |map: &Map, index: usize| match &map.layers[2].data {
+            LayerType::ObjectGroup(group) => group.objects[index].clone(),
+            _ => panic!("expected object layer"),
+        };
+        assert_eq!(object(&map, 0).name, "text");
+        assert!(object(&map, 0).name.ptr_eq(&object(&other, 0).name));
+        assert!(object(&map, 0).r#type.ptr_eq(&object(&map, 1).r#type));
+        assert!(interner.intern("text").ptr_eq(&object(&other, 0).name));
+
+        let unshared = parse_file(Path::new("assets/map_csv.json")).unwrap();
+        assert!(!object(&map, 0).name.ptr_eq(&object(&unshared, 0).name));
+    }
+
+    #[test]
+    fn test_rooms() {
+        let mut map = parse_file(Path::new("assets/map_csv.json")).unwrap();
+        map.layers.remove(0);
+
+        let rooms = map.rooms(4, 4);
+        assert_eq!(rooms.len(), 9);
+        assert_eq!((rooms[2].x, rooms[2].width), (8, 2));
+        assert_eq!((rooms[8].column, rooms[8].row, rooms[8].height), (2, 2, 2));
+
+        // Tile Layer 2 is empty in the first rows and the bottom-right corner.
+        assert!(rooms[0].has_tiles);
+        assert!(!rooms[8].has_tiles);
+
+        // Rect at (112, 16) and point at (16, 144) in 16 px tiles.
+        assert!(rooms[1].objects.contains(&7));
+        assert!(rooms[6].objects.contains(&6));
+        let total: usize = rooms.iter().map(|room| room.objects.len()).sum();
+        assert_eq!(total, 6);
+    }
+
+    #[test]
+    fn test_properties_diff_merge() {
+        let properties = |entries: &[(&str, Property)]| -> Properties {
+            entries
+                .iter()
+                .map(|(name, property)| (name.to_string(), property.clone()))
+                .collect()
+        };
+        let base = properties(&[
+            ("hp", Property::Int(10)),
+            ("speed", Property::Float(1.5)),
+            ("boss", Property::Bool(false)),
+        ]);
+        let hard = properties(&[
+            ("hp", Property::Int(20)),
+            ("speed", Property::Float(1.5)),
+            ("armor", Property::Int(3)),
+        ]);
+
+        let diff = base.diff(&hard);
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(diff.changed["hp"], Property::Int(20));
+        assert_eq!(diff.removed, vec!["boss".to_string()]);
+        assert!(base.diff(&base).is_empty());
+
+        let mut merged = base.clone();
+        merged.merge_from(&diff.changed, MergeStrategy::Overwrite);
+        assert_eq!(merged["hp"], Property::Int(20));
+        assert_eq!(merged["armor"], Property::Int(3));
+        assert_eq!(merged["boss"], Property::Bool(false));
+
+        let mut kept = base.clone();
+        kept.merge_from(&hard, MergeStrategy::KeepExisting);
+        assert_eq!(kept["hp"], Property::Int(10));
+        assert_eq!(kept["armor"], Property::Int(3));
+
+        let mut typed = base.clone();
+        let wrong_type = properties(&[("hp", Property::String("many".to_string()))]);
+        typed.merge_from(&wrong_type, MergeStrategy::SameType);
+        assert_eq!(typed["hp"], Property::Int(10));
+    }
+
+    #[test]
+    fn test_object_template() {
+        let map = parse_file(Path::new("assets/map_csv.json")).unwrap();
+        let mut object = match &map.layers[2].data {
+            LayerType::ObjectGroup(group) => group.objects[3].clone(),
+            _ => panic!("expected object layer"),
+        };
+
+        let json = object.to_template().to_json().unwrap();
+        assert_eq!(json["type"], "template");
+        assert_eq!(json["object"]["ellipse"], true);
+        assert_eq!(json["object"]["width"], 48.0);
+        assert!(json["object"].get("x").is_none());
+
+        object.gid = Some(370 | FLIPPED_HORIZONTALLY);
+        assert!(object.to_template().to_json().is_err());
+        let mut bytes = Vec::new();
+        object
+            .to_template()
+            .tileset(365, "tiles.json")
+            .write(&mut bytes)
+            .unwrap();
+        let json: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["object"]["gid"], 6 | FLIPPED_HORIZONTALLY);
+        assert_eq!(json["tileset"]["source"], "tiles.json");
+    }
+
+    #[test]
+    fn test_text_template() {
+        let object: Object = serde_json::from_value(serde_json::json!({
+            "id": 1, "name": "label", "type": "", "rotation": 0, "visible": true,
+            "x": 0, "y": 0, "width": 64, "height": 19,
+            "text": {
+                "text": "Hello", "wrap": true, "color": "#ff0000", "bold": true,
+                "halign": "center", "valign": "bottom"
+            }
+        }))
+        .unwrap();
+
+        let json = object.to_template().to_json().unwrap();
+        assert_eq!(
+            json["object"]["text"],
+            serde_json::json!({
+                "text": "Hello", "wrap": true, "color": "#ff0000", "bold": true,
+                "halign": "center", "valign": "bottom"
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_file_with_externals() {
+        let path = Path::new("assets/external/map.json");
+        let map = parse_file_with_externals(path, &ParseOptions::new()).unwrap();
+
+        assert_eq!(map.tilesets[1].name, "External");
+        assert_eq!(map.tilesets[1].first_gid, 365);
+
+        let objects = match &map.layers[1].data {
+            LayerType::ObjectGroup(group) => &group.objects,
+            _ => panic!("expected object layer"),
+        };
+        assert_eq!((objects[0].id, objects[0].gid), (1, Some(369)));
+        assert_eq!((objects[0].name.as_str(), objects[0].x), ("crate", 16.0));
+        assert_eq!(objects[1].name, "big crate");
+
+        let properties = objects[1].properties.as_ref().unwrap();
+        assert_eq!(properties["hp"], Property::Int(50));
+        assert_eq!(properties["breakable"], Property::Bool(true));
+    }
+
+    #[test]
+    fn test_template_overrides() {
+        let path = Path::new("assets/external/map.json");
+        let options = ParseOptions::new().strict(true);
+        let map = parse_file_with_externals(path, &options).unwrap();
+        let objects = match &map.layers[1].data {
+            LayerType::ObjectGroup(group) => &group.objects,
+            _ => panic!("expected object layer"),
+        };
+
+        // Fields the instance doesn't write come from the template.
+        let plain = &objects[0];
+        assert_eq!(plain.template.as_deref(), Some("templates/crate.tj"));
+        assert_eq!((plain.r#type.as_str(), plain.rotation), ("prop", 0.0));
+        assert_eq!(
+            plain.shape,
+            ObjectShape::Rect {
+                width: 16.0,
+                height: 16.0
+            }
+        );
+
+        // Fields written by the instance win, including a flipped GID that
+        // already refers to the map's tilesets.
+        let flipped = &objects[2];
+        assert_eq!(flipped.gid, Some(369 | FLIPPED_HORIZONTALLY));
+        assert_eq!(flipped.rotation, 90.0);
+        assert_eq!(flipped.name, "crate");
+
+        // Template values that differ from defaults are kept.
+        let sign = &objects[3];
+        assert!(!sign.visible);
+        assert_eq!(sign.r#type, "sign");
+        match &sign.shape {
+            ObjectShape::Text { text, width, .. } => {
+                assert_eq!(
+                    (text.text.as_str(), text.wrap, *width),
+                    ("Welcome", true, 64.0)
+                )
+            }
+            shape => panic!("expected text, got {:?}", shape),
+        }
+
+        // Nested values such as text are replaced as a whole and properties
+        // merge per name, the instance winning even with another type.
+        let exit = &objects[4];
+        assert!(exit.visible);
+        match &exit.shape {
+            ObjectShape::Text { text, .. } => {
+                assert_eq!((text.text.as_str(), text.wrap), ("Exit", false))
+            }
+            shape => panic!("expected text, got {:?}", shape),
+        }
+        let properties = exit.properties.as_ref().unwrap();
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["message"], Property::Int(7));
+        assert_eq!(properties["readable"], Property::Bool(true));
+    }
+
+    #[test]
+    fn test_max_gid() {
+        let mut layer = TileLayer::new(2, 2, vec![0, 3 | FLIPPED_HORIZONTALLY, 7, 1]).unwrap();
+        assert_eq!(layer.gids_masked().collect::<Vec<_>>(), vec![0, 3, 7, 1]);
+        assert_eq!(layer.max_gid(), 7);
+
+        layer.set_tile(0, 1, 2);
+        assert_eq!(layer.max_gid(), 3);
+        assert_eq!(
+            layer,
+            TileLayer::new(2, 2, vec![0, 3 | FLIPPED_HORIZONTALLY, 2, 1]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unknown_shape() {
+        let object: Object = serde_json::from_str(
+            r#"{ "id": 1, "name": "", "type": "", "x": 0, "y": 0, "rotation": 0,
+                 "visible": true, "gid": null, "capsule": { "radius": 4 } }"#,
+        )
+        .unwrap();
+
+        match object.shape {
+            ObjectShape::Unknown(value) => assert_eq!(value["capsule"]["radius"], 4),
+            shape => panic!("unexpected shape {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn test_ellipse_shape() {
+        let shape = |fields: &str| {
+            let json = format!(
+                r#"{{ "id": 1, "name": "", "type": "", "x": 0, "y": 0, "rotation": 0,
+                      "visible": true {} }}"#,
+                fields
+            );
+            serde_json::from_str::<Object>(&json).unwrap().shape
+        };
+
+        let ellipse = shape(r#", "ellipse": true, "width": 32, "height": 16"#);
+        assert_eq!(ellipse.radii(), Some((16.0, 8.0)));
+        assert!(!ellipse.is_circle());
+        assert!(shape(r#", "ellipse": true, "width": 8, "height": 8"#).is_circle());
+
+        // Only the flag, eg. from exporters that leave out zero sizes.
+        let flag_only = shape(r#", "ellipse": true"#);
+        assert_eq!(
+            flag_only,
+            ObjectShape::Ellipse {
+                width: 0.0,
+                height: 0.0
+            }
+        );
+        assert_eq!(shape(r#", "width": 4, "height": 2"#).radii(), None);
+    }
+
+    #[test]
+    fn test_tileset_upgrade_terrains() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let tileset = &mut map.tilesets[0];
+        tileset.terrains = Some(vec![
+            serde_json::from_str(r#"{ "name": "grass", "tile": 0 }"#).unwrap(),
+            serde_json::from_str(r#"{ "name": "water", "tile": 1 }"#).unwrap(),
+        ]);
+        tileset.tiles = Some(vec![serde_json::from_str(
+            r#"{ "id": 5, "terrain": [0, 1, -1, 0] }"#,
+        )
+        .unwrap()]);
+
+        tileset.upgrade_terrains();
+
+        assert!(tileset.terrains.is_none());
+        let wang_set = &tileset.wang_sets.as_ref().unwrap()[0];
+        assert_eq!(wang_set.colors[1].name, "water");
+        assert_ne!(wang_set.colors[0].color, wang_set.colors[1].color);
+        assert_eq!(wang_set.wang_tiles[0].wang_id, [0, 2, 0, 1, 0, 0, 0, 1]);
+
+        let legacy: WangTile =
+            serde_json::from_str(r#"{ "tileid": 1, "wangid": 33554464 }"#).unwrap();
+        assert_eq!(legacy.wang_id, [0, 2, 0, 0, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_query() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        if let LayerType::ObjectGroup(group) = &mut map.layers[2].data {
+            for (i, object) in group.objects.iter_mut().enumerate() {
+                object.r#type = "enemy".into();
+                let mut properties = Properties::new();
+                properties.insert("hp".to_string(), Property::Int(i as i32 * 5));
+                object.properties = Some(properties);
+            }
+        }
+
+        let selected = map.query("objects[type=enemy][hp>10]").unwrap();
+        let built = Query::objects().with_type("enemy").prop_gt("hp", 10);
+        assert_eq!(selected, built.select(&map));
+        assert_eq!(selected.len(), 3);
+
+        assert_eq!(map.query("objects[name='rect'][hp]").unwrap().len(), 1);
+        assert_eq!(map.query("layers[type=tilelayer]").unwrap().len(), 2);
+        assert_eq!(map.query("layers [name = Tile Layer 2]").unwrap().len(), 1);
+        assert!(map.query("tiles[x]").is_err());
+        assert!(map.query("objects[hp>10").is_err());
+    }
+
+    #[test]
+    fn test_keep_encoded() {
+        let decoded = parse_file(Path::new("assets/map.json")).unwrap();
+        let options = ParseOptions::new().keep_encoded(true);
+        let mut map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+
+        for (layer, decoded) in map.layers.iter_mut().zip(&decoded.layers) {
+            if let (LayerType::TileLayer(layer), LayerType::TileLayer(decoded)) =
+                (&mut layer.data, &decoded.data)
+            {
+                assert!(layer.tiles().is_empty());
+                assert_eq!(
+                    layer.encoded().unwrap().compression,
+                    Some(Compression::Zlib)
+                );
+                assert_eq!(layer.get_tile(0, 0), 0);
+                assert_eq!(layer.get_tile_signed(0, 0), None);
+
+                layer.decode().unwrap();
+                assert_eq!(layer.get_tile_signed(0, 0), Some(layer.get_tile(0, 0)));
+                assert!(!layer.is_encoded());
+                assert_eq!(layer, decoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_class_defaults() {
+        let project = parse_project(
+            r##"{
+                "propertyTypes": [
+                    { "id": 1, "name": "Direction", "type": "enum",
+                      "values": ["left", "right"], "valuesAsFlags": false },
+                    { "id": 2, "name": "ellipse", "type": "class", "useAs": ["object"],
+                      "members": [
+                          { "name": "hp", "type": "int", "value": 10 },
+                          { "name": "facing", "type": "string",
+                            "propertyType": "Direction", "value": "left" },
+                          { "name": "loot", "type": "class",
+                            "propertyType": "Loot", "value": {} }
+                      ] }
+                ]
+            }"##
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(project.enum_type("Direction").unwrap().values.len(), 2);
+        assert_eq!(project.class("ellipse").unwrap().members.len(), 2);
+
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replace("\r\n", "\n")
+            .replacen(
+                "\"type\":\"\",\n                 \"visible\":true,\n                 \"width\":48",
+                "\"type\":\"ellipse\", \"visible\":true, \"width\":48,
+                 \"properties\":[{\"name\":\"hp\", \"type\":\"int\", \"value\":3}]",
+                1,
+            );
+        let options = ParseOptions::new().project(&project);
+        let map = parse_with_options(json.as_bytes(), &options).unwrap();
+
+        let object = map
+            .objects()
+            .find(|object| object.name == "ellipse")
+            .unwrap();
+        let properties = object.properties.as_ref().unwrap();
+        assert_eq!(properties["hp"], Property::Int(3));
+        assert_eq!(properties["facing"], Property::String("left".to_string()));
+        assert!(map
+            .objects()
+            .filter(|object| object.name != "ellipse")
+            .all(|object| object.properties.is_none()));
+    }
+
+    #[test]
+    fn test_tile_uv() {
+        let mut tileset = parse_file(Path::new("assets/map.json")).unwrap().tilesets[0].clone();
+        tileset.margin = 1;
+        tileset.spacing = 2;
+
+        // 4 columns: 1 + 4 * 16 + 3 * 2 + 1 = 72 pixels.
+        assert_eq!(tileset.columns(72), 4);
+        assert_eq!(tileset.tile_rect(5, (72, 36)), [19, 19, 16, 16]);
+        assert_eq!(
+            tileset.tile_uv(5, (72, 36)),
+            [19.0 / 72.0, 19.0 / 36.0, 35.0 / 72.0, 35.0 / 36.0]
+        );
+        assert_eq!(
+            tileset.tile_uv_inset(0, (72, 36), 0.5),
+            [1.5 / 72.0, 1.5 / 36.0, 16.5 / 72.0, 16.5 / 36.0]
+        );
+
+        // Offsets saturate, the far edge must not overflow.
+        tileset.margin = u32::MAX - 8;
+        assert!(tileset.tile_uv(0, (72, 36)).iter().all(|uv| uv.is_finite()));
+    }
+
+    #[test]
+    fn test_gid_table_limits() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        let image_size = [(16 * 28, 16 * 13)];
+
+        let mut huge = map.clone();
+        huge.tilesets[0].tile_count = Some(u32::MAX);
+        assert!(huge.build_gid_table(&image_size).is_err());
+
+        let mut overflow = map.clone();
+        overflow.tilesets[0].first_gid = u32::MAX - 10;
+        assert!(overflow.build_gid_table(&image_size).is_err());
+
+        let mut spacing = map;
+        spacing.tilesets[0].tile_count = None;
+        spacing.tilesets[0].spacing = u32::MAX;
+        assert!(spacing.build_gid_table(&image_size).is_err());
+        spacing.tilesets[0].margin = u32::MAX;
+        spacing.tilesets[0].tile_count = Some(2);
+        assert_eq!(spacing.build_gid_table(&image_size).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_gid_table() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let mut second = map.tilesets[0].clone();
+        second.first_gid = 365;
+        second.tile_count = None;
+        second.tiles = Some(vec![Tile {
+            id: 2,
+            properties: None,
+            animation: Some(vec![Frame {
+                tile_id: 3,
+                duration: 100,
+            }]),
+            terrain: None,
+            object_group: None,
+        }]);
+        map.tilesets.push(second);
+
+        let table = map
+            .build_gid_table(&[(16 * 28, 16 * 13), (32, 32)])
+            .unwrap();
+        assert_eq!(table.len(), 365 + 4);
+        assert_eq!(table[0], ResolvedGid::default());
+
+        let resolved = table[30];
+        assert_eq!(resolved.tileset, Some(0));
+        assert_eq!(resolved.local_id, 29);
+        assert_eq!(resolved.rect, [16, 16, 16, 16]);
+        assert_eq!(resolved.uv, map.tilesets[0].tile_uv(29, (16 * 28, 16 * 13)));
+
+        assert_eq!(table[367].tileset, Some(1));
+        assert_eq!(table[367].animation, Some(0));
+        assert_eq!(table[368].rect, [16, 16, 16, 16]);
+    }
+
+    #[test]
+    fn test_animations() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        assert!(map.animations().is_empty());
+
+        let frame = |tile_id, duration| Frame { tile_id, duration };
+        let tile = |id, animation| Tile {
+            id,
+            properties: None,
+            animation,
+            terrain: None,
+            object_group: None,
+        };
+        map.tilesets[0].tiles = Some(vec![
+            tile(7, Some(vec![frame(7, 100), frame(8, 50)])),
+            tile(2, None),
+            tile(3, Some(vec![frame(4, 10)])),
+        ]);
+
+        let animations = map.animations();
+        assert_eq!(animations.len(), 2);
+        assert_eq!(animations[0].gid, 4);
+        assert_eq!(animations[1].frames, vec![(8, 100), (9, 50)]);
+        assert_eq!(animations[1].duration_ms(), 150);
+    }
+
+    #[test]
+    fn test_animation_time() {
+        use std::time::Duration;
+
+        let walk = Animation {
+            gid: 8,
+            frames: vec![(8, 100), (9, 50)],
+        };
+        assert_eq!(walk.total_duration(), Duration::from_millis(150));
+        assert_eq!(walk.frame_at(Duration::from_millis(99)), Some(8));
+        assert_eq!(walk.frame_at(Duration::from_millis(100)), Some(9));
+        assert_eq!(walk.frame_at(Duration::from_secs(3)), Some(8));
+
+        let frame = Frame {
+            tile_id: 1,
+            duration: 100,
+        };
+        assert_eq!(frame.duration(), Duration::from_millis(100));
+        let tile = Tile {
+            id: 0,
+            properties: None,
+            animation: Some(vec![frame.clone(), frame]),
+            terrain: None,
+            object_group: None,
+        };
+        assert_eq!(
+            tile.frame_at_time(Duration::from_millis(250)),
+            tile.frame_at(50)
+        );
+
+        // Durations saturate instead of overflowing.
+        let long = Animation {
+            gid: 1,
+            frames: vec![(1, u32::MAX), (2, 10)],
+        };
+        assert_eq!(long.duration_ms(), u32::MAX);
+        assert_eq!(long.frame_at(Duration::from_millis(10)), Some(1));
+    }
+
+    #[test]
+    fn test_error_path() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let parse_modified = |from: &str, to: &str| {
+            parse_slice_with_options(json.replacen(from, to, 1).as_bytes(), &ParseOptions::new())
+                .unwrap_err()
+        };
+
+        match parse_modified("\"tilewidth\":16", "\"tilewidth\":\"16\"") {
+            TiledError::Path { path, source } => {
+                assert_eq!(path, "tilesets[0].tilewidth");
+                assert!(matches!(*source, TiledError::ParsingError(_)));
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        // Objects are read as a whole, so the path ends at the layer.
+        match parse_modified("\"rotation\":0", "\"rotation\":\"x\"") {
+            TiledError::Path { path, source } => {
+                assert_eq!(path, "layers[2]");
+                assert!(matches!(*source, TiledError::InvalidNumber { .. }));
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        let err = parse_modified("\"name\":\"Tile Layer 2\",", "\"name\":\"Tile Layer 2\"");
+        assert!(err.to_string().starts_with("layers[1]: expected `,`"));
+
+        // Readers and lenient parsing report the path too.
+        let json = json.replacen("\"tilewidth\":16", "\"tilewidth\":\"16\"", 1);
+        let lenient = ParseOptions::new().lenient(true);
+        for err in [
+            parse(json.as_bytes()).unwrap_err(),
+            parse_with_options(json.as_bytes(), &lenient).unwrap_err(),
+        ]
+        .iter()
+        {
+            assert!(err.to_string().starts_with("tilesets[0].tilewidth: "));
+        }
+    }
+
+    #[test]
+    fn test_display_color() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        let mut group = match &map.layers[2].data {
+            LayerType::ObjectGroup(group) => group.clone(),
+            _ => panic!("not an object layer"),
+        };
+
+        let mut object = group.objects[0].clone();
+        assert_eq!(
+            object.display_color(&group).rgba(),
+            [0xa0, 0xa0, 0xa4, 0xff]
+        );
+
+        group.color = Some("#80ff0000".parse().unwrap());
+        assert_eq!(object.display_color(&group), Color::new(0xff, 0, 0, 0x80));
+
+        let mut properties = Properties::new();
+        properties.insert(
+            "color".to_string(),
+            Property::Color("#00ff00".parse().unwrap()),
+        );
+        object.properties = Some(properties);
+        assert_eq!(object.display_color(&group), Color::new(0, 0xff, 0, 0xff));
+    }
+
+    #[test]
+    fn test_typed_positions() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let layer = match &map.layers[1].data {
+            LayerType::TileLayer(layer) => layer.clone(),
+            _ => panic!("not a tile layer"),
+        };
+
+        assert_eq!(layer.tile_at(TilePos(7, 1)), Some(layer.get_tile(7, 1)));
+        assert_eq!(layer[TilePos(7, 1)], layer.get_tile(7, 1));
+        assert_eq!(layer.tile_at(TilePos(10, 0)), None);
+
+        assert_eq!(map.tile_to_pixel(TilePos(2, 3)), PixelPos(32.0, 48.0));
+        assert_eq!(map.pixel_to_tile(PixelPos(47.9, 48.0)), Some(TilePos(2, 3)));
+        assert_eq!(map.pixel_to_tile(PixelPos(-1.0, 0.0)), None);
+
+        let hit = map
+            .raycast(&layer, PixelPos(8.0, 24.0), PixelPos(150.0, 24.0), |gid| {
+                gid != 0
+            })
+            .unwrap();
+        assert_eq!((hit.x, hit.y), (7, 1));
+
+        map.orientation = Orientation::Isometric;
+        let pos = TilePos(2, 3);
+        let PixelPos(x, y) = map.tile_to_pixel(pos);
+        // Center of the diamond.
+        let center = PixelPos(x + 8.0, y + 8.0);
+        assert_eq!(map.pixel_to_tile(center), Some(pos));
+    }
+
+    #[test]
+    fn test_strict() {
+        let options = ParseOptions::new().strict(true);
+        let map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        assert_eq!(map, parse_file(Path::new("assets/map.json")).unwrap());
+        parse_file_with_options(Path::new("assets/map_infinite.json"), &options).unwrap();
+
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replacen("\"rotation\":0", "\"rotaton\":0, \"rotation\":0", 1);
+        assert!(parse(json.as_bytes()).is_ok());
+        assert!(parse_with_options(json.as_bytes(), &options.clone().lenient(true)).is_ok());
+
+        match parse_with_options(json.as_bytes(), &options) {
+            Err(TiledError::Path { path, source }) => {
+                assert_eq!(path, "layers[2].objects[0].rotaton");
+                assert!(matches!(*source, TiledError::UnknownField(field) if field == "rotaton"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_export_manifest() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        let mut group = match &map.layers[2].data {
+            LayerType::ObjectGroup(group) => group.clone(),
+            _ => panic!("not an object layer"),
+        };
+        group.objects.truncate(2);
+        let mut properties = Properties::new();
+        properties.insert("hp".to_string(), Property::Int(10));
+        properties.insert(
+            "says".to_string(),
+            Property::String("hi, \"you\"".to_string()),
+        );
+        group.objects[1].properties = Some(properties);
+
+        let mut csv = Vec::new();
+        group
+            .export_manifest(&mut csv, ManifestFormat::Csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "id,name,type,x,y,hp,says\n1,text,,16,0,,\n3,polygon,,16,48,10,\"hi, \"\"you\"\"\"\n"
+        );
+
+        let mut ndjson = Vec::new();
+        group
+            .export_manifest(&mut ndjson, ManifestFormat::NdJson)
+            .unwrap();
+        let lines: Vec<Value> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["name"], "polygon");
+        assert_eq!(lines[1]["properties"]["hp"], 10);
+        assert_eq!(lines[0]["properties"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_mask() {
+        let layer = TileLayer::from_fn(4, 3, |x, y| if x < 2 { 1 } else { y });
+        let ones = layer.mask(|gid| gid == 1);
+        assert_eq!(ones.count(), 8);
+        assert!(ones.get(0, 2) && ones.get(3, 1) && !ones.get(3, 2));
+        assert!(!ones.get(4, 0));
+
+        let right = layer.mask(|gid| gid != 1);
+        assert_eq!(ones.union(&right).count(), 12);
+        assert!(ones.intersect(&right).is_empty());
+        assert_eq!(ones.difference(&right), ones);
+        assert_eq!(
+            right.iter().collect::<Vec<_>>(),
+            vec![(2, 0), (3, 0), (2, 2), (3, 2)]
+        );
+
+        let mut small = BitGrid::new(2, 2);
+        small.set(1, 1, true);
+        assert_eq!(
+            ones.intersect(&small).iter().collect::<Vec<_>>(),
+            vec![(1, 1)]
+        );
+        small.set(1, 1, false);
+        assert!(small.is_empty());
+    }
+
+    #[test]
+    fn test_bitgrid_morphology() {
+        // Two lakes: a 2x2 square and a single cell.
+        let layer = TileLayer::from_fn(6, 5, |x, y| match (x, y) {
+            (1..=2, 1..=2) | (5, 4) => 1,
+            _ => 0,
+        });
+        let water = layer.mask(|gid| gid == 1);
+
+        let lakes = water.connected_components();
+        assert_eq!(lakes.len(), 2);
+        assert_eq!(lakes[0].bounds, [1, 1, 2, 2]);
+        assert_eq!(lakes[0].len(), 4);
+        assert_eq!(lakes[1].bounds, [5, 4, 1, 1]);
+
+        let grown = water.dilate();
+        assert_eq!(grown.count(), 12 + 3);
+        assert!(grown.get(0, 1) && grown.get(3, 2) && !grown.get(0, 0));
+        assert_eq!(grown.erode().count(), 4);
+        assert!(water.erode().is_empty());
+        assert_eq!(water.outline(), water);
+        assert_eq!(grown.outline().count(), grown.count() - 4);
+    }
+
+    #[test]
+    fn test_mask_polygons() {
+        // An L shape with a hole, and two cells touching at a corner.
+        let layer = TileLayer::from_fn(8, 4, |x, y| match (x, y) {
+            (0..=2, 0..=2) => (x, y) != (1, 1),
+            (3, 2) | (5, 0) | (6, 1) => true,
+            _ => false,
+        } as u32);
+        let polygons = layer.mask(|gid| gid != 0).to_polygons(16.0, 8.0);
+        let points = |polygon: &Vec<Point>| {
+            polygon
+                .iter()
+                .map(|point| (point.x, point.y))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(polygons.len(), 4);
+        assert_eq!(
+            points(&polygons[0]),
+            vec![
+                (0.0, 0.0),
+                (48.0, 0.0),
+                (48.0, 16.0),
+                (64.0, 16.0),
+                (64.0, 24.0),
+                (0.0, 24.0)
+            ]
+        );
+        // The hole goes the other way around.
+        assert_eq!(
+            points(&polygons[1]),
+            vec![(32.0, 8.0), (16.0, 8.0), (16.0, 16.0), (32.0, 16.0)]
+        );
+        assert_eq!(points(&polygons[2])[0], (80.0, 0.0));
+        assert_eq!(points(&polygons[3])[0], (96.0, 8.0));
+
+        let stairs = TileLayer::from_fn(4, 4, |x, y| (x <= y) as u32);
+        let mask = stairs.mask(|gid| gid != 0);
+        assert_eq!(mask.to_polygons(1.0, 1.0)[0].len(), 10);
+        assert_eq!(mask.to_polygons_simplified(1.0, 1.0, 1.0)[0].len(), 3);
+    }
+
+    #[test]
+    fn test_loader_profiles() {
+        let dir = std::env::temp_dir().join(format!("tiled-json-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let (overworld, dungeon) = (dir.join("overworld.json"), dir.join("dungeon.json"));
+        let with_class =
+            |class: &str| json.replacen('{', &format!("{{ \"class\":\"{}\",", class), 1);
+        std::fs::write(&overworld, with_class("overworld")).unwrap();
+        std::fs::write(&dungeon, with_class("dungeon")).unwrap();
+
+        let config =
+            LoaderConfig::new().profile("overworld", ParseOptions::new().keep_encoded(true));
+        let loader = Loader::new(config);
+        let is_encoded = |map: &Map| match &map.layers[0].data {
+            LayerType::TileLayer(layer) => layer.is_encoded(),
+            _ => unreachable!(),
+        };
+
+        let map = loader.load(&overworld).unwrap();
+        assert_eq!(map.class.as_deref(), Some("overworld"));
+        assert!(is_encoded(&map));
+        assert!(!is_encoded(&loader.load(&dungeon).unwrap()));
+        assert!(std::sync::Arc::ptr_eq(
+            &map,
+            &loader.load(&overworld).unwrap()
+        ));
+
+        assert!(loader.evict(&overworld).is_some());
+        assert!(!std::sync::Arc::ptr_eq(
+            &map,
+            &loader.load(&overworld).unwrap()
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_map_document() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let mut document = MapDocument::parse(json.clone()).unwrap();
+        assert_eq!(document.to_json_string().unwrap(), json);
+
+        let map = document.map_mut();
+        if let LayerType::TileLayer(layer) = &mut map.layers[0].data {
+            layer.set_tile(0, 0, 42);
+        }
+        if let LayerType::ObjectGroup(group) = &mut map.layers[2].data {
+            group.objects[1].x = 99.0;
+        }
+
+        let written = document.to_json_string().unwrap();
+        let changed: Vec<_> = json
+            .lines()
+            .zip(written.lines())
+            .filter(|(a, b)| a != b)
+            .collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(json.lines().count(), written.lines().count());
+
+        let reparsed = parse(written.as_bytes()).unwrap();
+        assert_eq!(&reparsed.layers, &document.map().layers);
+
+        if let LayerType::ObjectGroup(group) = &mut document.map_mut().layers[2].data {
+            group.objects.pop();
+        }
+        assert!(document.to_json_string().is_err());
+    }
+
+    #[test]
+    fn test_map_document_text() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replacen(
+                "\"wrap\":true",
+                "\"wrap\":true,\n                     \"color\":\"#ff0000\",\"bold\":true",
+                1,
+            );
+        let mut document = MapDocument::parse(json.clone()).unwrap();
+
+        if let LayerType::ObjectGroup(group) = &mut document.map_mut().layers[2].data {
+            if let ObjectShape::Text { text, .. } = &mut group.objects[0].shape {
+                text.text = "Bye".to_string();
+            }
+        }
+
+        let written = document.to_json_string().unwrap();
+        assert_eq!(written, json.replace("Hello World", "Bye"));
+        assert_eq!(
+            &parse(written.as_bytes()).unwrap().layers,
+            &document.map().layers
+        );
+    }
+
+    #[test]
+    fn test_write_large_integers() {
+        let value =
+            serde_json::json!({ "a": u64::MAX, "b": i64::MIN, "c": 9_007_199_254_740_993u64 });
+        let mut written = Vec::new();
+        write_json(&value, &mut written, &WriteOptions::new().canonical(true)).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "{ \"a\":18446744073709551615,\n \"b\":-9223372036854775808,\n \"c\":9007199254740993\n}"
+        );
+    }
+
+    #[test]
+    fn test_canonical_write() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+
+        let options = WriteOptions::new()
+            .canonical(true)
+            .line_ending(LineEnding::CrLf);
+        let mut written = Vec::new();
+        write_json(&value, &mut written, &options).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), json);
+
+        let value = serde_json::json!({ "x": 1.5f32, "y": 16.0, "z": 0.1f32, "a": [] });
+        let mut written = Vec::new();
+        write_json(&value, &mut written, &WriteOptions::new().canonical(true)).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "{ \"a\":[],\n \"x\":1.5,\n \"y\":16,\n \"z\":0.1\n}"
+        );
+    }
+
+    #[test]
+    fn test_map_info() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let json = json
+            .replacen("\"type\":\"\"", "\"type\":\"spawn\"", 2)
+            .replace("\"name\":\"foo\"", "\"name\":\"name\"");
+
+        let info = MapInfo::from_reader(json.as_bytes(), "spawn").unwrap();
+        assert_eq!(info.name.as_deref(), Some("Hello, world!"));
+        assert_eq!((info.width, info.height), (10, 10));
+        assert_eq!((info.tile_width, info.tile_height), (16, 16));
+        assert_eq!(info.spawn_count, 2);
+
+        let other = MapInfo::from_reader(json.replace("spawn", "enemy").as_bytes(), "spawn");
+        let other = other.unwrap();
+        assert_eq!(other.spawn_count, 0);
+        assert_ne!(other.content_hash, info.content_hash);
+    }
+
+    #[test]
+    fn test_layer_context() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let json = json.replacen(
+            "\"opacity\":1,",
+            "\"opacity\":0.5, \"tintcolor\":\"#ff8000\",",
+            1,
+        );
+        let map = parse(json.as_bytes()).unwrap();
+
+        let contexts: Vec<LayerContext> = map.layers_with_context().map(|(_, c)| c).collect();
+        assert_eq!(contexts.len(), 3);
+        assert_eq!(contexts[0].opacity, 0.5);
+        assert_eq!(contexts[0].tint, Some(Color::new(0xff, 0x80, 0x00, 0xff)));
+        assert_eq!(contexts[1], LayerContext::default());
+
+        // Nested layers accumulate the values of their parents.
+        let mut child = map.layers[0].clone();
+        child.visible = false;
+        child.parallax_x = 0.5;
+        let nested = contexts[0].child(&child);
+        assert_eq!(nested.opacity, 0.25);
+        assert_eq!(nested.parallax, (0.5, 1.0));
+        assert_eq!(nested.tint, Some(Color::new(0xff, 0x40, 0x00, 0xff)));
+        assert!(!nested.visible);
+    }
+
+    #[test]
+    fn test_list_properties() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let json = json.replace("\"value\":42", "\"value\":[1, 2.5, \"x\", [true]]");
+
+        // Without lenient mode maps with list properties don't load.
+        assert!(parse(json.as_bytes()).is_err());
+
+        let options = ParseOptions::new().lenient(true);
+        let map = parse_with_options(json.as_bytes(), &options).unwrap();
+        let properties = map.properties.unwrap();
+        let answer = &properties["answer"];
+        assert_eq!(
+            answer,
+            &Property::List(vec![
+                Property::Int(1),
+                Property::Float(2.5),
+                Property::String("x".to_string()),
+                Property::List(vec![Property::Bool(true)]),
+            ])
+        );
+        assert_eq!(answer.type_name(), "list");
+        assert_eq!(answer.to_string(), "[1, 2.5, x, [true]]");
+        assert_eq!(properties["foo"], Property::from("Hello, world!"));
+
+        let options = ParseOptions::new().strict(true);
+        let err = parse_with_options(json.as_bytes(), &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "properties[0]: Property \"answer\" has a non-standard list value"
+        );
+    }
+
+    #[test]
+    fn test_index_buffer() {
+        let layer = TileLayer::from_fn(3, 1, |x, _| [0, 300 | FLIPPED_HORIZONTALLY, 7][x as usize]);
+        assert_eq!(layer.to_index_buffer::<u16>().unwrap(), vec![0, 300, 7]);
+        assert_eq!(layer.to_index_buffer::<u32>().unwrap(), vec![0, 300, 7]);
+
+        let err = layer.to_index_buffer::<u8>().unwrap_err();
+        assert_eq!(err.to_string(), "GID 300 doesn't fit in u8");
+    }
+
+    #[test]
+    fn test_gpu_pack() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        map.tilesets[0].tiles = Some(vec![Tile {
+            id: 6,
+            properties: None,
+            animation: Some(vec![
+                Frame {
+                    tile_id: 6,
+                    duration: 100,
+                },
+                Frame {
+                    tile_id: 8,
+                    duration: 50,
+                },
+            ]),
+            terrain: None,
+            object_group: None,
+        }]);
+
+        let pack = map.gpu_pack(0, &[(112, 832)]).unwrap();
+        assert_eq!((pack.width, pack.height), (10, 10));
+        match &map.layers[0].data {
+            LayerType::TileLayer(layer) => assert_eq!(pack.cells, layer.tiles()),
+            _ => unreachable!(),
+        }
+        assert_eq!(pack.uvs.len(), 365);
+        assert_eq!(pack.uvs[7], map.tilesets[0].tile_uv(6, (112, 832)));
+        assert_eq!(pack.animations[7], [0, 2, 150]);
+        assert_eq!(pack.animations[8], [0; 3]);
+        assert_eq!(pack.frames, vec![[7, 100], [9, 150]]);
+
+        assert!(map.gpu_pack(2, &[]).is_none());
+    }
+
+    #[test]
+    fn test_object_ref() {
+        let dir = std::env::temp_dir().join(format!("tiled-json-ref-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("map.json");
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        std::fs::write(&path, &json).unwrap();
+
+        let loader = Loader::default();
+        let marker = ObjectRef::new(&path, 3);
+        let serialized = serde_json::to_string(&marker).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ObjectRef>(&serialized).unwrap(),
+            marker
+        );
+        let before = loader.resolve(&marker).unwrap().unwrap();
+        assert_eq!(before.id, 3);
+
+        // Removing objects before the marker doesn't change its ID.
+        let mut edited: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let objects = edited["layers"][2]["objects"].as_array_mut().unwrap();
+        objects.retain(|object| object["id"] == 3);
+        std::fs::write(&path, edited.to_string()).unwrap();
+
+        loader.evict(&path);
+        assert_eq!(loader.resolve(&marker).unwrap(), Some(before));
+        assert_eq!(loader.resolve(&ObjectRef::new(&path, 1)).unwrap(), None);
+        assert!(loader
+            .resolve(&ObjectRef::new(dir.join("missing.json"), 1))
+            .is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conventions() {
+        use crate::conventions::{Conventions, Exit};
+
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("assets/map.json").unwrap()).unwrap();
+        let object = |id: u32, r#type: &str, properties: serde_json::Value| {
+            serde_json::json!({
+                "id": id, "name": "", "type": r#type, "x": 16, "y": 16, "rotation": 0,
+                "visible": true, "width": 0, "height": 0, "point": true,
+                "properties": properties,
+            })
+        };
+        let property = |name: &str, value: &str| serde_json::json!({ "name": name, "type": "string", "value": value });
+        let objects = json["layers"][2]["objects"].as_array_mut().unwrap();
+        objects.push(object(100, "spawn", serde_json::json!([])));
+        objects.push(object(101, "checkpoint", serde_json::json!([])));
+        objects.push(object(
+            102,
+            "door",
+            serde_json::json!([
+                property("target_map", "cave.json"),
+                property("target_spawn", "entrance")
+            ]),
+        ));
+        json["properties"]
+            .as_array_mut()
+            .unwrap()
+            .push(property("music", "overworld.ogg"));
+        let map = parse_bytes(json.to_string().as_bytes()).unwrap();
+
+        let ids =
+            |objects: Vec<&Object>| objects.iter().map(|object| object.id).collect::<Vec<_>>();
+        assert_eq!(ids(map.spawn_points()), [100]);
+        assert_eq!(
+            map.exits(),
+            [Exit {
+                id: 102,
+                target: "cave.json".to_string(),
+                target_spawn: Some("entrance".to_string()),
+            }]
+        );
+        assert_eq!(map.music(), Some("overworld.ogg"));
+
+        let custom = Conventions::new()
+            .spawn_type("checkpoint")
+            .target_spawn_property("spawn")
+            .music_property("bgm");
+        assert_eq!(ids(custom.spawn_points(&map)), [101]);
+        assert_eq!(custom.exits(&map)[0].target_spawn, None);
+        assert_eq!(custom.music(&map), None);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_http_externals() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = std::env::temp_dir().join(format!("tiled-json-http-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("assets/external/map.json").unwrap())
+                .unwrap();
+        for object in json["layers"][1]["objects"].as_array_mut().unwrap() {
+            let template = object["template"].as_str().unwrap();
+            object["template"] = format!("https://example.com/shared/{}", template).into();
+        }
+        for tileset in json["tilesets"].as_array_mut().unwrap() {
+            if let Some(source) = tileset.get("source").and_then(serde_json::Value::as_str) {
+                tileset["source"] = format!("https://example.com/shared/{}", source).into();
+            }
+        }
+        let path = dir.join("map.json");
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let fetched = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = fetched.clone();
+        let resolver = HttpResolver::new(move |url| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let file = url.trim_start_matches("https://example.com/shared/");
+            std::fs::read(Path::new("assets/external").join(file))
+                .map_err(|_| TiledError::Other(format!("404 {}", url)))
+        })
+        .cache_dir(&dir.join("cache"));
+        let options = ParseOptions::new().http(resolver);
+
+        let local = parse_file_with_externals(Path::new("assets/external/map.json"), &options);
+        let summary = |map: &Map| {
+            map.objects()
+                .map(|object| {
+                    (
+                        object.gid,
+                        object.name.to_string(),
+                        object.properties.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let map = parse_file_with_externals(&path, &options).unwrap();
+        assert_eq!(summary(&map), summary(&local.unwrap()));
+        let count = fetched.load(Ordering::SeqCst);
+        assert!(count >= 2);
+
+        // Downloads are cached.
+        parse_file_with_externals(&path, &options).unwrap();
+        assert_eq!(fetched.load(Ordering::SeqCst), count);
+
+        let err = parse_file_with_externals(&path, &ParseOptions::new()).unwrap_err();
+        assert!(err.to_string().contains("without ParseOptions::http"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundle() {
+        let path = Path::new("assets/external/map.json");
+        let map = parse_file_with_externals(path, &ParseOptions::new()).unwrap();
+        assert_eq!(map.tilesets[1].source.as_deref(), Some("tiles.json"));
+        assert_eq!(
+            map.collect_dependencies(Path::new("assets/external")),
+            [
+                "assets/external/templates/crate.tj",
+                "assets/external/templates/sign.tj",
+                "assets/external/tiles.json",
+                "assets/tilesheet.png",
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        );
+
+        let dir = std::env::temp_dir().join(format!("tiled-json-bundle-{}", std::process::id()));
+        let copied = bundle(path, &dir).unwrap();
+        assert_eq!(copied.len(), 5);
+        assert!(dir.join("tilesheet.png").is_file());
+
+        let bundled =
+            parse_file_with_externals(&dir.join("external/map.json"), &ParseOptions::new());
+        assert_eq!(bundled.unwrap(), map);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "determinism")]
+    #[test]
+    fn test_determinism() {
+        // The same on every platform and Rust version. A change here breaks
+        // hashes that games have stored or exchanged.
+        let hashes = [
+            ("assets/map.json", 18100323671808048486),
+            // Same tiles as map.json in another encoding.
+            ("assets/map_csv.json", 18100323671808048486),
+            ("assets/map_infinite.json", 17525294256020989985),
+        ];
+        for (path, hash) in hashes.iter() {
+            let map = parse_file(Path::new(path)).unwrap();
+            assert_eq!(map.content_hash(), *hash, "{}", path);
+        }
+
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("assets/map.json").unwrap()).unwrap();
+        let mut json = Vec::new();
+        write_json(&value, &mut json, &WriteOptions::new()).unwrap();
+        let mut hasher = ContentHasher::default();
+        std::hash::Hasher::write(&mut hasher, &json);
+        assert_eq!(std::hash::Hasher::finish(&hasher), 6846880235484640431);
+
+        // Iteration orders don't depend on hashing.
+        let map = Map::new(4, 1, 16, 16).with_generated_layer("ground", |x, _| 8 - x * 2);
+        let rules = map.extract_adjacency("ground").unwrap();
+        let gids: Vec<_> = rules.frequencies().keys().copied().collect();
+        assert_eq!(gids, [2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn test_decode_job() {
+        let decoded = parse_file(Path::new("assets/map.json")).unwrap();
+        let options = ParseOptions::new().keep_encoded(true);
+        let map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+
+        let mut job = DecodeJob::new(map.clone());
+        assert_eq!(job.progress().fraction(), 0.0);
+        let progress = job.step_layers(1).unwrap();
+        assert_eq!((progress.decoded, progress.total), (1, 2));
+        assert!(!progress.is_done());
+        assert!(job.step(1_000_000).unwrap().is_done());
+        assert_eq!(job.into_map(), decoded);
+
+        // A budget of zero still makes progress.
+        let mut job = DecodeJob::new(map.clone());
+        assert_eq!(job.step(0).unwrap().decoded, 1);
+
+        let mut broken = map;
+        if let LayerType::TileLayer(layer) = &mut broken.layers[0].data {
+            layer.encoded.as_mut().unwrap().bytes.truncate(4);
+        }
+        let mut job = DecodeJob::new(broken);
+        match job.step(1_000_000) {
+            Err(TiledError::Layer { index: 0, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(job.step(1_000_000).unwrap().is_done());
+        assert_eq!(job.map().layers[1], decoded.layers[1]);
+    }
+
+    #[test]
+    fn test_objects_chunked() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        // A second object layer, chunks continue across layers.
+        map.layers.push(map.layers[2].clone());
+        let ids: Vec<_> = map.objects().map(|object| object.id).collect();
+        let chunk_ids = |chunk: Vec<&Object>| chunk.iter().map(|o| o.id).collect::<Vec<_>>();
+
+        let chunks: Vec<_> = map.objects_chunked(3).map(chunk_ids).collect();
+        assert_eq!(chunks.len(), ids.len().div_ceil(3));
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 3));
+        assert_eq!(chunks.concat(), ids);
+
+        // Resume from a cursor without borrowing the map in between.
+        let mut chunks = map.objects_chunked(4);
+        chunks.next();
+        let mut cursor = chunks.cursor();
+        assert!(!cursor.is_done(&map));
+        let mut rest = Vec::new();
+        loop {
+            let chunk = cursor.next_chunk(&map, 4);
+            if chunk.is_empty() {
+                break;
+            }
+            rest.extend(chunk_ids(chunk));
+        }
+        assert_eq!(rest, ids[4..]);
+        assert!(cursor.is_done(&map));
+    }
+
+    #[test]
+    fn test_gid_heatmap() {
+        let map = Map::new(3, 2, 16, 16)
+            .with_generated_layer("ground", |x, _| if x == 0 { 1 } else { 2 })
+            .with_generated_layer("decor", |x, y| match (x, y) {
+                (0, 0) => 1 | crate::tileset::FLIPPED_HORIZONTALLY,
+                (2, 1) => 7,
+                _ => 0,
+            });
+
+        let histogram: Vec<_> = map.gid_histogram().into_iter().collect();
+        assert_eq!(histogram, [(1, 3), (2, 4), (7, 1)]);
+        assert_eq!(map.gid_heatmap(1), [2, 0, 0, 1, 0, 0]);
+
+        let mut csv = Vec::new();
+        map.export_gid_heatmap(1, &mut csv, HeatmapFormat::Csv)
+            .unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "2,0,0\n1,0,0\n");
+
+        let mut png = Vec::new();
+        map.export_gid_heatmap(1, &mut png, HeatmapFormat::Png)
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02"));
+        // IEND chunk with its CRC.
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn test_shape_measures() {
+        let points = |points: &[(f32, f32)]| -> Vec<Point> {
+            points.iter().map(|&(x, y)| Point { x, y }).collect()
+        };
+        let object = |shape: ObjectShape, rotation: f32| Object {
+            id: 1,
+            name: Default::default(),
+            r#type: Default::default(),
+            x: 100.0,
+            y: 50.0,
+            rotation,
+            visible: true,
+            gid: None,
+            template: None,
+            shape,
+            properties: None,
+        };
+
+        let path = ObjectShape::Polyline {
+            points: points(&[(0.0, 0.0), (30.0, 0.0), (30.0, 40.0)]),
+        };
+        assert_eq!(path.length(), Some(70.0));
+        assert_eq!(path.area(), None);
+
+        // L-shaped polygon, counter-clockwise on screen.
+        let shape = ObjectShape::Polygon {
+            points: points(&[
+                (0.0, 0.0),
+                (0.0, 20.0),
+                (20.0, 20.0),
+                (20.0, 10.0),
+                (10.0, 10.0),
+                (10.0, 0.0),
+            ]),
+        };
+        assert_eq!(shape.length(), Some(80.0));
+        assert_eq!(shape.area(), Some(300.0));
+        let centroid = shape.centroid().unwrap();
+        // 20x20 square without its 10x10 top right quarter.
+        assert!((centroid.x - 25.0 / 3.0).abs() < 1e-4);
+        assert!((centroid.y - 35.0 / 3.0).abs() < 1e-4);
+
+        let rotated = object(shape, 90.0);
+        let world = rotated.world_points().unwrap();
+        assert!((world[1].x - 80.0).abs() < 1e-4 && (world[1].y - 50.0).abs() < 1e-4);
+        let centroid = rotated.centroid().unwrap();
+        assert!((centroid.x - (100.0 - 35.0 / 3.0)).abs() < 1e-4);
+        assert!((centroid.y - (50.0 + 25.0 / 3.0)).abs() < 1e-4);
+
+        let ellipse = ObjectShape::Ellipse {
+            width: 20.0,
+            height: 10.0,
+        };
+        assert_eq!(ellipse.area(), Some(std::f32::consts::PI * 50.0));
+
+        // Tile objects are aligned to their bottom left corner.
+        let mut tile = object(
+            ObjectShape::Rect {
+                width: 16.0,
+                height: 16.0,
+            },
+            0.0,
+        );
+        tile.gid = Some(1);
+        assert_eq!(tile.centroid(), Some(Point { x: 108.0, y: 42.0 }));
+    }
+
+    #[test]
+    fn test_patrol_path() {
+        let point = |x: f32, y: f32| Point { x, y };
+        let object = Object {
+            id: 1,
+            name: Default::default(),
+            r#type: Default::default(),
+            x: 10.0,
+            y: 10.0,
+            rotation: 0.0,
+            visible: true,
+            gid: None,
+            template: None,
+            shape: ObjectShape::Polyline {
+                points: vec![point(0.0, 0.0), point(30.0, 0.0), point(30.0, 40.0)],
+            },
+            properties: None,
+        };
+
+        let once = object.patrol_path(PathMode::Once).unwrap();
+        assert_eq!(once.length(), 70.0);
+        assert_eq!(once.sample(15.0), (point(25.0, 10.0), point(1.0, 0.0)));
+        assert_eq!(once.sample(50.0), (point(40.0, 30.0), point(0.0, 1.0)));
+        assert_eq!(once.sample(100.0), (point(40.0, 50.0), point(0.0, 1.0)));
+        assert_eq!(once.sample(-5.0).0, point(10.0, 10.0));
+
+        let looping = object.patrol_path(PathMode::Loop).unwrap();
+        assert_eq!(looping.sample(85.0), once.sample(15.0));
+
+        let ping_pong = object.patrol_path(PathMode::PingPong).unwrap();
+        assert_eq!(
+            ping_pong.sample(90.0),
+            (point(40.0, 30.0), point(0.0, -1.0))
+        );
+        assert_eq!(ping_pong.sample(140.0).0, point(10.0, 10.0));
+
+        // Polygons return to the first point.
+        let square = Object {
+            shape: ObjectShape::Polygon {
+                points: vec![
+                    point(0.0, 0.0),
+                    point(10.0, 0.0),
+                    point(10.0, 10.0),
+                    point(0.0, 10.0),
+                ],
+            },
+            ..object
+        };
+        let path = square.patrol_path(PathMode::Loop).unwrap();
+        assert_eq!(path.length(), 40.0);
+        assert_eq!(path.sample(35.0), (point(10.0, 15.0), point(0.0, -1.0)));
+    }
+
+    #[test]
+    fn test_serde_state() {
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+
+        // Warnings of maps deserialized with serde directly stay in the map.
+        let map: Map = serde_json::from_str(&json).unwrap();
+        assert_eq!(map.warnings(), &[ParseWarning::NumericVersion]);
+        assert!(take_warnings().is_empty());
+        assert_eq!(reported_count(), 0);
+
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        value["type"] = serde_json::json!("tileset");
+        assert!(serde_json::from_value::<Map>(value).is_err());
+        assert_eq!(reported_count(), 0);
+        assert_eq!(parse(json.as_bytes()).unwrap(), map);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let version: TiledVersion = "1.10.2".parse().unwrap();
+        assert!(version > "1.9".parse().unwrap());
+        assert_eq!(version.to_string(), "1.10.2");
+        assert!("1.x".parse::<TiledVersion>().is_err());
+
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        assert_eq!(map.warnings(), &[ParseWarning::NumericVersion]);
+        map.upgrade_to("1.9").unwrap();
+        assert!(map.warnings().is_empty());
+        assert_eq!(map.tiled_version().unwrap(), TiledVersion::new(1, 9, 0));
+
+        let mut value: Value =
+            serde_json::from_slice(&std::fs::read("assets/map.json").unwrap()).unwrap();
+        value["version"] = serde_json::json!(1.1);
+        value["layers"][2].as_object_mut().unwrap().remove("id");
+        value["tilesets"][0]["terrains"] = serde_json::json!([{ "name": "grass", "tile": 1 }]);
+
+        let map = parse(&serde_json::to_vec(&value).unwrap()[..]).unwrap();
+        assert_eq!(
+            map.warnings(),
+            &[
+                ParseWarning::NumericVersion,
+                ParseWarning::OldVersion(TiledVersion::new(1, 1, 0)),
+                ParseWarning::DeprecatedField {
+                    field: "tilesets[0].terrains".to_string(),
+                    replacement: "wangsets".to_string(),
+                },
+                ParseWarning::MissingLayerId { layer: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_layer_ids() {
+        let mut value: Value =
+            serde_json::from_slice(&std::fs::read("assets/map.json").unwrap()).unwrap();
+        value["version"] = serde_json::json!("1.9");
+        value["layers"][0].as_object_mut().unwrap().remove("id");
+        value["layers"][1] = serde_json::json!({
+            "image": "sky.png", "name": "sky", "offsetx": 0, "offsety": 0,
+            "opacity": 1, "type": "imagelayer", "visible": true
+        });
+
+        let map = parse(&serde_json::to_vec(&value).unwrap()[..]).unwrap();
+        assert_eq!(map.layers[2].id, Some(3));
+        assert_eq!(
+            map.warnings(),
+            &[
+                ParseWarning::MissingLayerId { layer: 0 },
+                ParseWarning::MissingLayerId { layer: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tile_stats() {
+        let layer = TileLayer::new(2, 2, vec![3, 3, 3, 3]).unwrap();
+        assert_eq!(layer.distinct_gid_count(), 1);
+        assert_eq!(layer.stats().entropy, 0.0);
+
+        let mut layer = TileLayer::new(2, 2, vec![0, 1, 2, 3 | 0x8000_0000]).unwrap();
+        assert_eq!(
+            layer.stats(),
+            TileStats {
+                max_gid: 3,
+                distinct_gids: 4,
+                entropy: 2.0,
+            }
+        );
+
+        layer.set_tile(0, 0, 1);
+        assert_eq!(layer.distinct_gid_count(), 3);
+        assert_eq!(layer.stats().entropy, 1.5);
+    }
+
+    #[test]
+    fn test_gid_transform_option() {
+        // Biome in bit 24, below the flip flags.
+        let transform = GidTransform::new(|gid| gid | 1 << 24, |gid| gid & !(1 << 24));
+        let options = ParseOptions::new().gid_transform(transform.clone());
+        let plain = parse_file(Path::new("assets/map.json")).unwrap();
+        let map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+
+        let (plain, layer) = match (&plain.layers[1].data, &map.layers[1].data) {
+            (LayerType::TileLayer(plain), LayerType::TileLayer(layer)) => (plain, layer),
+            _ => panic!("not a tile layer"),
+        };
+        assert_eq!(layer.get_tile(7, 1), plain.get_tile(7, 1) | 1 << 24);
+
+        let encoded = layer
+            .encode(Some(Compression::Zlib), Some(&transform))
+            .unwrap();
+        assert_eq!(encoded.gid_transform.as_ref(), Some(&transform));
+        assert_eq!(&encoded.decode().unwrap()[..], layer.tiles());
+        let plain_encoded = plain.encode(Some(Compression::Gzip), None).unwrap();
+        assert_eq!(&plain_encoded.decode().unwrap()[..], plain.tiles());
+
+        let options = options.keep_encoded(true);
+        let mut map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        if let LayerType::TileLayer(encoded) = &mut map.layers[1].data {
+            encoded.decode().unwrap();
+            assert_eq!(encoded, layer);
+        }
+    }
+
+    #[test]
+    fn test_max_decompressed_bytes() {
+        // The layer claims fewer tiles than its data inflates to.
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replace("\r\n", "\n")
+            .replacen(
+                "\"height\":10,\n         \"id\":1",
+                "\"height\":2,\n         \"id\":1",
+                1,
+            );
+        match parse(json.as_bytes()) {
+            Err(TiledError::Path { path, source }) => {
+                assert_eq!(path, "layers[0]");
+                assert!(matches!(
+                    *source,
+                    TiledError::DecompressionLimitExceeded { limit: 80 }
+                ));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let options = ParseOptions::new().max_decompressed_bytes(64);
+        let err = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap_err();
+        match err.root_cause() {
+            TiledError::DecompressionLimitExceeded { limit } => assert_eq!(*limit, 64),
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let options = options.keep_encoded(true);
+        let mut map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        if let LayerType::TileLayer(layer) = &mut map.layers[0].data {
+            assert!(matches!(
+                layer.decode(),
+                Err(TiledError::DecompressionLimitExceeded { limit: 64 })
+            ));
+        }
+
+        // Chunks don't limit the size of the layer they are in.
+        let json = std::fs::read_to_string("assets/map_infinite.json")
+            .unwrap()
+            .replace("\r\n", "\n")
+            .replacen("\"height\": 32,", "\"height\": 100000,", 1)
+            .replacen("\"width\": 32,", "\"width\": 100000,", 1);
+        let options = ParseOptions::new().max_decompressed_bytes(1 << 20);
+        let err = parse_with_options(json.as_bytes(), &options).unwrap_err();
+        match err.root_cause() {
+            TiledError::DecompressionLimitExceeded { limit } => assert_eq!(*limit, 1 << 20),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replacen("\"x\":16", "\"x\":\"12,5\"", 1)
+            .replacen("\"width\":83", "\"width\":\"NaN\"", 1);
+
+        match parse(json.as_bytes()).unwrap_err().root_cause() {
+            TiledError::InvalidNumber { field, value } => {
+                assert_eq!(field, "x");
+                assert_eq!(value, "\"12,5\"");
+            }
+            err => panic!("Expected invalid number, got {:?}", err),
+        }
+
+        let options = ParseOptions::new().lenient(true);
+        let map = parse_with_options(json.as_bytes(), &options).unwrap();
+
+        if let LayerType::ObjectGroup(group) = &map.layers[2].data {
+            assert_eq!(group.objects[0].x, 12.5);
+            assert_eq!(
+                group.objects[0].shape,
+                ObjectShape::Text {
+                    text: Text {
+                        text: "Hello World".to_string(),
+                        wrap: true,
+                        font_family: None,
+                        pixel_size: None,
+                        other: Default::default(),
+                    },
+                    width: 0.0,
+                    height: 19.0,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_tileset_properties() {
+        let tileset: Tileset = serde_json::from_value(serde_json::json!({
+            "firstgid": 10,
+            "name": "tiles",
+            "tilewidth": 16,
+            "tileheight": 16,
+            "spacing": 0,
+            "margin": 0,
+            "image": "tilesheet.png",
+            "tilecount": 4,
+            "properties": [
+                { "name": "solid", "type": "bool", "value": false },
+                { "name": "damage", "type": "int", "value": 0 }
+            ],
+            "tiles": [{
+                "id": 1,
+                "properties": [{ "name": "solid", "type": "bool", "value": true }]
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(tileset.property(11, "solid"), Some(&Property::Bool(true)));
+        assert_eq!(tileset.property(11, "damage"), Some(&Property::Int(0)));
+        assert_eq!(tileset.property(12, "solid"), Some(&Property::Bool(false)));
+        // Flipped tile.
+        assert_eq!(
+            tileset.property(0x8000_000b, "solid"),
+            Some(&Property::Bool(true))
+        );
+        // GIDs of other tilesets.
+        assert_eq!(tileset.property(9, "solid"), None);
+        assert_eq!(tileset.property(14, "solid"), None);
+    }
+
+    #[test]
+    fn test_tile_animation() {
+        let tile: Tile = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "animation": [
+                { "tileid": 1, "duration": 100 },
+                { "tileid": 2, "duration": 50 }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(tile.animation_duration_ms(), 150);
+        assert_eq!(tile.frame_at(0).unwrap().tile_id, 1);
+        assert_eq!(tile.frame_at(120).unwrap().tile_id, 2);
+        assert_eq!(tile.frame_at(310).unwrap().duration_ms(), 100);
+    }
+
+    #[test]
+    fn test_object_index() {
+        let options = ParseOptions::new().build_object_index(true);
+        let map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        let mut linear = map.clone();
+        linear.object_index = None;
+
+        assert!(map.object_index.is_some());
+
+        for key in &[
+            ObjectKey::Name("polygon"),
+            ObjectKey::Type(""),
+            ObjectKey::Property("hp"),
+            ObjectKey::Name("missing"),
+        ] {
+            assert_eq!(map.find_objects_fast(*key), linear.find_objects_fast(*key));
+        }
+
+        assert_eq!(map.find_objects_fast(ObjectKey::Type("")).len(), 6);
+        assert_eq!(map.object_by_id(7).unwrap().name, "rect");
+        assert_ne!(map, linear);
+    }
+
+    #[test]
+    fn test_object_index_stale() {
+        let options = ParseOptions::new().build_object_index(true);
+        let mut map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        match &mut map.layers[2].data {
+            LayerType::ObjectGroup(group) => {
+                group.objects.remove(0);
+            }
+            _ => panic!("expected object layer"),
+        }
+
+        let ids: Vec<_> = map.objects().map(|object| object.id).collect();
+        for id in ids {
+            assert_eq!(map.object_by_id(id).unwrap().id, id);
+        }
+    }
+
+    #[test]
+    fn test_camera_translation() {
+        let camera = Camera::new(100.0, 50.0, 2.0);
+
+        // Normal layers move with the camera.
+        assert_eq!(
+            camera.translation((1.0, 1.0), (0.0, 0.0), (0.0, 0.0)),
+            (-200.0, -100.0)
+        );
+        // Layers with zero parallax factor stay fixed on the screen.
+        assert_eq!(
+            camera.translation((0.0, 0.0), (10.0, 0.0), (0.0, 0.0)),
+            (20.0, 0.0)
+        );
+        // Half speed background.
+        assert_eq!(
+            camera.translation((0.5, 0.5), (0.0, 0.0), (0.0, 0.0)),
+            (-100.0, -50.0)
+        );
+
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        assert_eq!(
+            camera.layer_translation(&map, &map.layers[0]),
+            (-200.0, -100.0)
+        );
+    }
+
+    #[cfg(feature = "rapier2d")]
+    #[test]
+    fn test_rapier_colliders() {
+        use crate::collision::{to_rapier, Colliders};
+
+        let point = |x: f32, y: f32| Point { x, y };
+        let collides = |name: &str| -> Properties {
+            vec![(name.to_string(), Property::Bool(true))]
+                .into_iter()
+                .collect()
+        };
+        let object = |id: u32, shape: ObjectShape, properties: Option<Properties>| Object {
+            id,
+            name: Default::default(),
+            r#type: Default::default(),
+            x: 10.0,
+            y: 20.0,
+            rotation: 0.0,
+            visible: true,
+            gid: None,
+            template: None,
+            shape,
+            properties,
+        };
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let rect = ObjectShape::Rect {
+            width: 8.0,
+            height: 4.0,
+        };
+        let circle = ObjectShape::Ellipse {
+            width: 6.0,
+            height: 6.0,
+        };
+        let line = ObjectShape::Polyline {
+            points: vec![point(0.0, 0.0), point(5.0, 0.0)],
+        };
+        let flat = ObjectShape::Polygon {
+            points: vec![point(0.0, 0.0), point(2.0, 2.0), point(4.0, 4.0)],
+        };
+        match &mut map.layers[2].data {
+            LayerType::ObjectGroup(group) => {
+                group.objects = vec![
+                    object(1, rect.clone(), Some(collides("collides"))),
+                    object(2, circle, Some(collides("collides"))),
+                    object(3, line, Some(collides("solid"))),
+                    object(4, ObjectShape::Point, Some(collides("collides"))),
+                    object(5, rect, None),
+                    object(6, flat, Some(collides("collides"))),
+                ]
+            }
+            _ => panic!("expected object layer"),
+        }
+
+        let colliders = to_rapier(&map, 0.5);
+        assert_eq!(
+            colliders.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [1, 2]
+        );
+
+        let cuboid = &colliders[0].1;
+        assert_eq!(cuboid.shape.as_cuboid().unwrap().half_extents.x, 2.0);
+        assert_eq!(cuboid.position.translation.vector.x, 7.0);
+        assert_eq!(cuboid.position.translation.vector.y, 11.0);
+        assert_eq!(colliders[1].1.shape.as_ball().unwrap().radius, 1.5);
+
+        let solid = Colliders::new().property("solid").to_rapier(&map, 1.0);
+        assert_eq!(solid.len(), 1);
+        assert!(solid[0].1.shape.as_polyline().is_some());
+    }
+
+    #[cfg(any(feature = "toml", feature = "yaml"))]
+    fn override_properties() -> Properties {
+        vec![
+            ("speed", Property::Float(0.1)),
+            ("lives", Property::Int(3)),
+            ("boss", Property::Bool(true)),
+            ("tint", Property::Color("#80ff0000".parse().unwrap())),
+            ("music", Property::File("music/cave.ogg".to_string())),
+            ("title", Property::String("file: not a file".to_string())),
+            (
+                "waves",
+                Property::List(vec![Property::Float(2.0), Property::Int(4)]),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, property)| (name.to_string(), property))
+        .collect()
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_properties_toml() {
+        let properties = override_properties();
+        let toml = properties_to_toml(&properties).unwrap();
+        assert!(toml.contains("speed = 0.1\n"));
+        assert!(toml.contains("music = \"file:music/cave.ogg\"\n"));
+        assert!(toml.contains("title = \"string:file: not a file\"\n"));
+        assert_eq!(properties_from_toml(&toml).unwrap(), properties);
+
+        assert!(properties_from_toml("stats = { hp = 10 }").is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_properties_yaml() {
+        let properties = override_properties();
+        let yaml = properties_to_yaml(&properties).unwrap();
+        assert!(yaml.contains("color:#80ff0000"));
+        assert_eq!(properties_from_yaml(&yaml).unwrap(), properties);
+
+        assert!(properties_from_yaml("lives: 10000000000").is_err());
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn test_geojson() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        if let LayerType::ObjectGroup(group) = &map.layers[2].data {
+            let collection = group.to_geojson();
+            let features = collection["features"].as_array().unwrap();
+
+            assert_eq!(features.len(), group.objects.len());
+            assert_eq!(features[1]["properties"]["name"], "polygon");
+            assert_eq!(features[1]["geometry"]["type"], "Polygon");
+            assert_eq!(features[2]["geometry"]["type"], "LineString");
+            assert_eq!(features[4]["geometry"]["type"], "Point");
+        }
+    }
+
+    #[test]
+    fn test_blend_mode() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replacen(
+                "\"name\":\"Tile Layer 2\"",
+                "\"name\":\"Tile Layer 2\", \"blendmode\":\"color-dodge\"",
+                1,
+            )
+            .replacen(
+                "\"name\":\"Object Layer 1\"",
+                "\"name\":\"Object Layer 1\", \"blendmode\":\"glow\"",
+                1,
+            );
+        let map = parse(json.as_bytes()).unwrap();
+
+        let modes: Vec<_> = map
+            .render_layers()
+            .map(|layer| layer.blend_mode.clone())
+            .collect();
+        assert_eq!(
+            modes,
+            vec![
+                BlendMode::Normal,
+                BlendMode::ColorDodge,
+                BlendMode::Unknown("glow".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tint_color() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replacen(
+                "\"name\":\"Tile Layer 2\"",
+                "\"name\":\"Tile Layer 2\", \"tintcolor\":\"#80ff8000\"",
+                1,
+            );
+        let map = parse(json.as_bytes()).unwrap();
+
+        assert_eq!(map.layers[0].tint_color, None);
+        let tint = map.layers[1].tint_color.unwrap();
+        assert_eq!(tint.rgba(), [0xff, 0x80, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn test_hexagonal_map() {
+        let json = std::fs::read_to_string("assets/map.json")
+            .unwrap()
+            .replace("\"orthogonal\"", "\"hexagonal\"");
+
+        assert!(parse(json.as_bytes()).is_ok());
+
+        let strict = ParseOptions::new().strict(true);
+        let err = parse_with_options(json.as_bytes(), &strict).unwrap_err();
+        assert!(err.to_string().contains("missing staggeraxis"));
+
+        let lenient = ParseOptions::new().strict(true).lenient(true);
+        assert!(parse_with_options(json.as_bytes(), &lenient).is_ok());
+
+        let json = json.replacen(
+            "\"height\":10,",
+            "\"height\":10, \"staggeraxis\":\"y\", \"staggerindex\":\"odd\", \"hexsidelength\":8,",
+            1,
+        );
+        let map = parse_with_options(json.as_bytes(), &strict).unwrap();
+
+        assert_eq!(map.stagger_axis, Some(StaggerAxis::Y));
+        assert_eq!(map.stagger_index, Some(StaggerIndex::Odd));
+        assert_eq!(map.hex_side_length, Some(8));
+    }
+
+    #[test]
+    fn test_tileset_usage() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let cells = map
+            .layers
+            .iter()
+            .filter_map(|layer| match &layer.data {
+                LayerType::TileLayer(layer) => {
+                    Some(layer.tiles().iter().filter(|t| **t != 0).count())
+                }
+                _ => None,
+            })
+            .sum::<usize>();
+
+        if let LayerType::ObjectGroup(group) = &mut map.layers[2].data {
+            group.objects[0].gid = Some(0x8000_0002);
+        }
+
+        let usage = map.tileset_usage();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0], TilesetUsage { cells, objects: 1 });
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut layer = TileLayer::new(2, 2, vec![1, 2, 3, 4]).unwrap();
+        layer.resize(4, 3, Anchor::Center).unwrap();
+        assert_eq!(layer.tiles(), &[0, 1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 0][..]);
+        layer.resize(2, 2, Anchor::Center).unwrap();
+        assert_eq!(layer.tiles(), &[1, 2, 3, 4][..]);
+        layer.resize(1, 1, Anchor::BottomRight).unwrap();
+        assert_eq!(layer.tiles(), &[4][..]);
+
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let objects = map.objects().count();
+        let (x, y) = {
+            let object = map.objects().next().unwrap();
+            (object.x, object.y)
+        };
+
+        map.resize(
+            map.width + 2,
+            map.height,
+            Anchor::Right,
+            OutsideObjects::Keep,
+        )
+        .unwrap();
+        let object = map.objects().next().unwrap();
+        assert_eq!(object.x, x + 2.0 * map.tile_width as f32);
+        assert_eq!(map.objects().count(), objects);
+
+        map.resize(1, 1, Anchor::BottomRight, OutsideObjects::Remove)
+            .unwrap();
+        assert_eq!((map.width, map.height), (1, 1));
+        assert!(map.objects().count() < objects);
+
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        map.scale_objects(2.0);
+        let object = map.objects().next().unwrap();
+        assert_eq!((object.x, object.y), (x * 2.0, y * 2.0));
+    }
+
+    #[test]
+    fn test_resize_infinite() {
+        fn tile_layers(map: &Map) -> Vec<TileLayer> {
+            map.layers
+                .iter()
+                .filter_map(|layer| match &layer.data {
+                    LayerType::TileLayer(tiles) => Some(tiles.clone()),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        let mut map = parse_file(Path::new("assets/map_infinite.json")).unwrap();
+        let before = tile_layers(&map);
+
+        let (width, height) = (map.width + 4, map.height + 2);
+        map.resize(width, height, Anchor::BottomRight, OutsideObjects::Keep)
+            .unwrap();
+
+        let after = tile_layers(&map);
+        assert_eq!(after.len(), before.len());
+        for (before, after) in before.iter().zip(&after) {
+            assert_eq!(after.tiles(), before.tiles());
+            assert_eq!(
+                (after.start_x(), after.start_y()),
+                (before.start_x() + 4, before.start_y() + 2)
+            );
+        }
+        assert_eq!(
+            after[0].get_tile_signed(20, -14),
+            before[0].get_tile_signed(16, -16)
+        );
+    }
+
+    #[test]
+    fn test_parse_with_errors() {
+        let mut value: Value =
+            serde_json::from_str(&std::fs::read_to_string("assets/map.json").unwrap()).unwrap();
+        value["layers"][0]["data"] = Value::from("garbage");
+        value["layers"][2]["objects"][1]["x"] = Value::from("abc");
+        let json = serde_json::to_vec(&value).unwrap();
+
+        assert!(parse(&json[..]).is_err());
+
+        let (map, errors) = parse_with_errors(&json[..], &ParseOptions::new()).unwrap();
+        assert_eq!(map.layers.len(), value["layers"].as_array().unwrap().len());
+        assert_eq!(map.layers[0].data, LayerType::Invalid { index: 0 });
+        assert_eq!(map.layers[0].name, value["layers"][0]["name"]);
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], TiledError::Layer { index: 0, .. }));
+        match &errors[1] {
+            TiledError::Layer { index: 2, source } => match source.as_ref() {
+                TiledError::Object { index: 1, source } => {
+                    assert!(matches!(**source, TiledError::InvalidNumber { .. }))
+                }
+                err => panic!("unexpected error {:?}", err),
+            },
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        if let LayerType::ObjectGroup(group) = &map.layers[2].data {
+            assert_eq!(group.objects[1].shape, ObjectShape::Invalid { index: 1 });
+            assert_ne!(group.objects[0].shape, ObjectShape::Invalid { index: 0 });
+        } else {
+            panic!("object layer failed to parse");
+        }
+    }
+
+    #[test]
+    fn test_gid_transform() {
+        let apply = |m: [[f32; 3]; 2], (x, y): (f32, f32)| {
+            (
+                m[0][0] * x + m[0][1] * y + m[0][2],
+                m[1][0] * x + m[1][1] * y + m[1][2],
+            )
+        };
+
+        let identity = gid_transform(1, 16.0, 8.0);
+        assert_eq!(apply(identity, (16.0, 8.0)), (16.0, 8.0));
+
+        let flipped = gid_transform(1 | FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY, 16.0, 8.0);
+        assert_eq!(apply(flipped, (0.0, 0.0)), (16.0, 8.0));
+
+        // Diagonal + horizontal rotates 90 degrees clockwise.
+        let rotated = gid_transform(1 | FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY, 16.0, 8.0);
+        assert_eq!(apply(rotated, (0.0, 0.0)), (8.0, 0.0));
+        assert_eq!(apply(rotated, (16.0, 0.0)), (8.0, 16.0));
+        assert_eq!(apply(rotated, (0.0, 8.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_document_type() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        assert_eq!(map.compression_level, -1);
+
+        let json = std::fs::read_to_string("assets/map.json").unwrap();
+        let map_json = json.replacen('{', "{ \"compressionlevel\":6,", 1);
+        assert_eq!(parse(map_json.as_bytes()).unwrap().compression_level, 6);
+
+        let tileset_json = json.replace("\"type\":\"map\"", "\"type\":\"tileset\"");
+        for result in [
+            parse(tileset_json.as_bytes()).map(|_| ()),
+            parse_with_errors(tileset_json.as_bytes(), &ParseOptions::new()).map(|_| ()),
+            parse_header(tileset_json.as_bytes()).map(|_| ()),
+        ]
+        .iter()
+        {
+            match result.as_ref().map_err(TiledError::root_cause) {
+                Err(TiledError::WrongDocumentType { expected, found }) => {
+                    assert_eq!((expected.as_str(), found.as_str()), ("map", "tileset"))
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let file = File::open("assets/map.json").unwrap();
+        let header = parse_header(file).unwrap();
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        assert_eq!((header.width, header.height), (map.width, map.height));
+        assert_eq!(header.tilesets[0].name.as_deref(), Some("Tileset"));
+        assert_eq!(header.layers.len(), map.layers.len());
+        assert_eq!(header.layers[2].layer_type, "objectgroup");
+
+        let file = File::open("assets/map_infinite.json").unwrap();
+        assert!(parse_header(file).unwrap().infinite);
+    }
+
+    #[cfg(feature = "mesh")]
+    #[test]
+    fn test_build_mesh() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        let table = map.build_gid_table(&[(16 * 28, 16 * 13)]).unwrap();
+        let layer = TileLayer::new(3, 1, vec![2, 2, 2 | FLIPPED_HORIZONTALLY]).unwrap();
+
+        let mesh = layer.build_mesh(&table, &MeshOptions::new(16.0, 16.0));
+        assert_eq!(mesh.vertices.len(), 12);
+        assert_eq!(&mesh.indices[..6], &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.vertices[4].position, [16.0, 0.0]);
+        assert_eq!(mesh.vertices[6].position, [32.0, 16.0]);
+
+        let uv = table[2].uv;
+        assert_eq!(mesh.vertices[0].uv, [uv[0], uv[1]]);
+        // Flipped horizontally, the top-left corner shows the top-right of the tile.
+        assert_eq!(mesh.vertices[8].uv, [uv[2], uv[1]]);
+
+        let options = MeshOptions {
+            merge_runs: true,
+            ..MeshOptions::new(16.0, 16.0)
+        };
+        let mesh = layer.build_mesh(&table, &options);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.indices.len(), 12);
+        assert_eq!(mesh.vertices[1].position, [32.0, 0.0]);
+        assert_eq!(mesh.vertices[1].uv[0], uv[0] + 2.0 * (uv[2] - uv[0]));
+    }
+
+    #[cfg(feature = "geometry")]
+    #[test]
+    fn test_triangulate() {
+        let points = |points: &[(f32, f32)]| ObjectShape::Polygon {
+            points: points.iter().map(|&(x, y)| Point { x, y }).collect(),
+        };
+        let area = |shape: &ObjectShape| match shape {
+            ObjectShape::Polygon { points } => shape
+                .triangulate()
+                .unwrap()
+                .iter()
+                .map(|[a, b, c]| {
+                    let (a, b, c) = (&points[*a], &points[*b], &points[*c]);
+                    ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)).abs() / 2.0
+                })
+                .sum::<f32>(),
+            _ => unreachable!(),
+        };
+
+        // L-shape with a reflex corner, in both windings.
+        let l_shape = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ];
+        assert_eq!(points(&l_shape).triangulate().unwrap().len(), 4);
+        assert_eq!(area(&points(&l_shape)), 3.0);
+        let reversed: Vec<_> = l_shape.iter().rev().cloned().collect();
+        assert_eq!(area(&points(&reversed)), 3.0);
+
+        // Collinear point on an edge.
+        let square = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        assert_eq!(area(&points(&square)), 4.0);
+
+        assert_eq!(ObjectShape::Point.triangulate(), None);
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let object = |shape: ObjectShape, rotation: f32| Object {
+            id: 1,
+            name: Default::default(),
+            r#type: Default::default(),
+            x: 10.0,
+            y: 10.0,
+            rotation,
+            visible: true,
+            gid: None,
+            template: None,
+            shape,
+            properties: None,
+        };
+
+        let rect = object(
+            ObjectShape::Rect {
+                width: 20.0,
+                height: 10.0,
+            },
+            0.0,
+        );
+        assert!(rect.contains_point(29.0, 19.0));
+        assert!(!rect.contains_point(31.0, 15.0));
+
+        // Rotated 90 degrees clockwise around the top left corner.
+        let rotated = object(
+            ObjectShape::Rect {
+                width: 20.0,
+                height: 10.0,
+            },
+            90.0,
+        );
+        assert!(rotated.contains_point(5.0, 25.0));
+        assert!(!rotated.contains_point(25.0, 15.0));
+
+        let ellipse = object(
+            ObjectShape::Ellipse {
+                width: 20.0,
+                height: 10.0,
+            },
+            0.0,
+        );
+        assert!(ellipse.contains_point(20.0, 15.0));
+        assert!(!ellipse.contains_point(11.0, 11.0));
+
+        let points = vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            Point { x: 0.0, y: 10.0 },
+        ];
+        let polygon = object(
+            ObjectShape::Polygon {
+                points: points.clone(),
+            },
+            0.0,
+        );
+        assert!(polygon.contains_point(12.0, 12.0));
+        assert!(!polygon.contains_point(18.0, 18.0));
+
+        let polyline = object(ObjectShape::Polyline { points }, 0.0);
+        assert!(polyline.contains_point(15.0, 10.5));
+        assert!(!polyline.contains_point(12.0, 12.0));
+        assert!(polyline.hit_test(12.0, 12.0, 3.0));
+    }
+
+    #[test]
+    fn test_raycast() {
+        #[rustfmt::skip]
+        let layer = TileLayer::new(4, 3, vec![
+            0, 0, 0, 0,
+            0, 0, 2, 0,
+            0, 0, 0, 0,
+        ])
+        .unwrap();
+        let blocking = |gid| gid != 0;
+
+        let hit = layer.raycast((0.5, 1.5), (3.5, 1.5), blocking).unwrap();
+        assert_eq!((hit.x, hit.y, hit.gid), (2, 1, 2));
+        assert_eq!(hit.point, (2.0, 1.5));
+
+        // Diagonal ray from the other side.
+        let hit = layer.raycast((3.5, 0.5), (0.5, 3.5), blocking).unwrap();
+        assert_eq!((hit.x, hit.y), (2, 1));
+
+        assert_eq!(layer.raycast((0.5, 0.5), (3.5, 0.5), blocking), None);
+        assert_eq!(layer.raycast((0.5, 1.5), (1.5, 1.5), blocking), None);
+    }
+
+    #[test]
+    fn test_fov() {
+        #[rustfmt::skip]
+        let layer = TileLayer::new(7, 5, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ])
+        .unwrap();
+        let opaque = |gid| gid != 0;
+
+        let visibility = fov::compute(&layer, (2, 2), 10, opaque);
+        assert!(visibility.is_visible(2, 2));
+        assert!(visibility.is_visible(4, 2));
+        assert!(!visibility.is_visible(5, 2));
+        assert!(!visibility.is_visible(6, 2));
+        assert!(visibility.is_visible(6, 0));
+        assert!(!visibility.is_visible(7, 2));
+        assert_eq!(visibility.count(), 35 - 2);
+
+        // Shadowcasting is symmetric.
+        for y in 0..5 {
+            for x in 0..7 {
+                if layer.get_tile(x as u32, y as u32) == 0 {
+                    let back = fov::compute(&layer, (x, y), 10, opaque);
+                    assert_eq!(visibility.is_visible(x, y), back.is_visible(2, 2));
+                }
+            }
+        }
+
+        let visibility = fov::compute(&layer, (2, 2), 1, opaque);
+        assert_eq!(visibility.count(), 5);
+    }
+
+    #[test]
+    fn test_generated_layer() {
+        let map = Map::new(4, 3, 16, 16)
+            .with_generated_layer("ground", |_, _| 1)
+            .with_generated_layer("walls", |x, y| if x == 0 || y == 0 { 2 } else { 0 });
+
+        assert_eq!(map.layers.len(), 2);
+        match &map.layers[1].data {
+            LayerType::TileLayer(layer) => {
+                assert_eq!(layer.tiles(), &[2, 2, 2, 2, 2, 0, 0, 0, 2, 0, 0, 0][..]);
+            }
+            _ => panic!("expected tile layer"),
+        }
+        assert_eq!(map.tileset_usage(), Vec::new());
+        assert!(map.validate().is_ok());
+    }
+
+    #[test]
+    fn test_extract_adjacency() {
+        use crate::analysis::Direction;
+
+        let map = Map::new(3, 2, 16, 16).with_generated_layer("ground", |x, y| x + y * 3 + 1);
+        assert_eq!(map.extract_adjacency("missing"), None);
+
+        let rules = map.extract_adjacency("ground").unwrap();
+        assert_eq!(rules.frequency(1), 1);
+        assert_eq!(rules.count(1, Direction::Right, 2), 1);
+        assert_eq!(rules.count(2, Direction::Left, 1), 1);
+        assert_eq!(rules.count(1, Direction::Down, 4), 1);
+        assert!(!rules.allows(1, Direction::Right, 3));
+
+        let mut right: Vec<_> = rules.neighbors(5, Direction::Right).collect();
+        right.sort();
+        assert_eq!(right, vec![(6, 1)]);
+
+        let options = ParseOptions::new().keep_encoded(true);
+        let map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        let rules = map.extract_adjacency(&map.layers[0].name).unwrap();
+        assert!(rules.frequencies().is_empty());
+    }
+
+    #[test]
+    fn test_cell_property() {
+        let mut map = parse_file(Path::new("assets/map.json")).unwrap();
+        let property = |name: &str, value: Property| {
+            let mut properties = Properties::new();
+            properties.insert(name.to_string(), value);
+            Some(properties)
+        };
+
+        map.tilesets[0].properties = property("water", Property::Bool(false));
+        map.tilesets[0].tiles = Some(vec![Tile {
+            id: 0,
+            properties: property("water", Property::Bool(true)),
+            animation: None,
+            terrain: None,
+            object_group: None,
+        }]);
+        map.layers[0].properties = property("depth", Property::Int(1));
+        let tiles = match &mut map.layers[0].data {
+            LayerType::TileLayer(layer) => {
+                layer.tiles = Arc::new(vec![1; 100]);
+                Arc::make_mut(&mut layer.tiles)[1] = 2;
+                layer
+            }
+            _ => unreachable!(),
+        };
+        assert_eq!(tiles.get_tile(1, 0), 2);
+
+        assert_eq!(
+            map.cell_property(0, 0, 0, "water"),
+            Some(&Property::Bool(true))
+        );
+        assert_eq!(
+            map.cell_property(0, 1, 0, "water"),
+            Some(&Property::Bool(false))
+        );
+        assert_eq!(map.cell_property(0, 1, 0, "depth"), Some(&Property::Int(1)));
+        assert_eq!(
+            map.cell_property(0, 1, 0, "answer"),
+            Some(&Property::Int(42))
+        );
+        assert_eq!(map.cell_property(0, 10, 0, "answer"), None);
+        assert_eq!(map.cell_property(2, 0, 0, "answer"), None);
+
+        let options = ParseOptions::new().keep_encoded(true);
+        let mut map = parse_file_with_options(Path::new("assets/map.json"), &options).unwrap();
+        assert_eq!(map.cell_property(0, 0, 0, "answer"), None);
+        if let LayerType::TileLayer(layer) = &mut map.layers[0].data {
+            layer.decode().unwrap();
+        }
+        assert_eq!(
+            map.cell_property(0, 0, 0, "answer"),
+            Some(&Property::Int(42))
+        );
+    }
+
+    #[test]
+    fn test_layer_patch() {
+        let original = TileLayer::new(3, 2, vec![1, 1, 1, 2, 2, 2]).unwrap();
+        let mut server = original.clone();
+
+        let patch = server
+            .set_tiles(vec![(0, 0, 5), (1, 0, 1), (2, 1, 7)])
+            .unwrap();
+        assert_eq!(patch.changes.len(), 2);
+        let cells: Vec<(u32, u32, u32, u32)> = patch.changes.iter().map(|c| (*c).into()).collect();
+        assert_eq!(cells, vec![(0, 0, 1, 5), (2, 1, 2, 7)]);
+
+        let mut client = original.clone();
+        client.apply_patch(&patch).unwrap();
+        assert_eq!(client.tiles(), server.tiles());
+
+        // Patch doesn't apply twice.
+        assert!(client.apply_patch(&patch).is_err());
+        client.apply_patch(&patch.inverse()).unwrap();
+        assert_eq!(client.tiles(), original.tiles());
+    }
+
+    #[test]
+    #[should_panic(expected = "tile (3, 0) out of bounds of 3x2 layer")]
+    fn test_index_out_of_bounds() {
+        let layer = TileLayer::new(3, 2, vec![1, 1, 1, 2, 2, 2]).unwrap();
+        assert_eq!(layer[(2, 1)], 2);
+        let _ = layer[(3, 0)];
+    }
+
+    #[test]
+    fn test_layer_diff() {
+        let original = TileLayer::new(3, 2, vec![1, 1, 1, 2, 2, 2]).unwrap();
+        let mut changed = original.clone();
+        let patch = changed.set_tiles(vec![(0, 0, 5), (2, 1, 7)]).unwrap();
+
+        assert_eq!(original.diff(&changed).unwrap(), patch);
+        let mut restored = changed.clone();
+        restored
+            .apply_patch(&changed.diff(&original).unwrap())
+            .unwrap();
+        assert_eq!(restored.tiles(), original.tiles());
+        assert!(original.diff(&original).unwrap().is_empty());
+        assert!(original.diff(&TileLayer::from_fn(2, 3, |_, _| 0)).is_err());
+
+        // Layers with the same size at different positions aren't comparable.
+        let mut moved = original.clone();
+        moved.resize(3, 2, Anchor::TopLeft).unwrap();
+        moved.start_x = 4;
+        assert!(original.diff(&moved).is_err());
+
+        let map = parse_with_options(
+            File::open("assets/map.json").unwrap(),
+            &ParseOptions::new().keep_encoded(true),
+        )
+        .unwrap();
+        if let LayerType::TileLayer(encoded) = &map.layers[0].data {
+            assert!(encoded.diff(encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn test_layer_patch_errors() {
+        let original = TileLayer::new(3, 2, vec![1, 1, 1, 2, 2, 2]).unwrap();
+        let mut client = original.clone();
+
+        // Out of bounds tiles fail without changing anything.
+        assert!(client.set_tiles(vec![(0, 0, 9), (3, 0, 9)]).is_err());
+        assert!(client
+            .apply_patch(&LayerPatch {
+                changes: vec![TileChange {
+                    x: 0,
+                    y: 2,
+                    old_gid: 2,
+                    new_gid: 3
+                }],
+            })
+            .is_err());
+        assert_eq!(client.tiles(), original.tiles());
+
+        // Encoded layers are decoded first.
+        let map = parse_with_options(
+            File::open("assets/map.json").unwrap(),
+            &ParseOptions::new().keep_encoded(true),
+        )
+        .unwrap();
+        if let LayerType::TileLayer(encoded) = &map.layers[0].data {
+            let mut layer = encoded.clone();
+            let patch = layer.set_tiles(vec![(0, 0, 0)]).unwrap();
+            assert!(!layer.is_encoded());
+            let mut other = encoded.clone();
+            other.apply_patch(&patch).unwrap();
+            assert_eq!(other.tiles(), layer.tiles());
+        }
+    }
+
+    #[cfg(feature = "ldtk")]
+    #[test]
+    fn test_parse_ldtk() {
+        let project = serde_json::json!({
+            "defaultGridSize": 16,
+            "defs": { "tilesets": [{
+                "uid": 7, "identifier": "Cavern", "relPath": "cavern.png", "tileGridSize": 16,
+                "spacing": 0, "padding": 0, "__cWid": 4, "__cHei": 4,
+            }]},
+            "levels": [{
+                "identifier": "Level_0", "pxWid": 48, "pxHei": 32,
+                "layerInstances": [
+                    {
+                        "__identifier": "Entities", "__type": "Entities", "__cWid": 3,
+                        "__cHei": 2, "__gridSize": 16, "__opacity": 1, "visible": true,
+                        "entityInstances": [{
+                            "__identifier": "Player", "__pivot": [0.5, 1], "px": [24, 32],
+                            "width": 16, "height": 16,
+                            "fieldInstances": [
+                                { "__identifier": "hp", "__type": "Int", "__value": 10 },
+                                { "__identifier": "items", "__type": "Array<String>", "__value": [] },
+                            ],
+                        }],
+                    },
+                    {
+                        "__identifier": "Ground", "__type": "Tiles", "__cWid": 3, "__cHei": 2,
+                        "__gridSize": 16, "__opacity": 1, "__tilesetDefUid": 7, "visible": true,
+                        "gridTiles": [
+                            { "px": [0, 0], "src": [0, 0], "f": 0, "t": 0 },
+                            { "px": [32, 16], "src": [16, 0], "f": 1, "t": 1 },
+                            { "px": [16, 0], "src": [0, 0], "f": 0, "t": 16 },
+                        ],
+                    },
+                ],
+            }],
+        });
+        let json = serde_json::to_vec(&project).unwrap();
+
+        let map = parse_ldtk(&json[..], "Level_0").unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.tilesets[0].tile_count, Some(16));
+        assert_eq!(map.layers[0].name, "Ground");
+
+        match &map.layers[0].data {
+            LayerType::TileLayer(layer) => {
+                assert_eq!(
+                    layer.tiles(),
+                    &[1, 0, 0, 0, 0, 2 | FLIPPED_HORIZONTALLY][..]
+                )
+            }
+            _ => panic!("expected tile layer"),
+        }
+
+        let player = map.objects().next().unwrap();
+        assert_eq!((player.x, player.y), (16.0, 16.0));
+        assert_eq!(player.properties.as_ref().unwrap().len(), 1);
+
+        assert!(parse_ldtk(&json[..], "Level_1").is_err());
+
+        let mut project = project;
+        project["defs"]["tilesets"][0]["__cWid"] = 0x1_0000.into();
+        project["defs"]["tilesets"][0]["__cHei"] = 0x1_0000.into();
+        let json = serde_json::to_vec(&project).unwrap();
+        assert!(parse_ldtk(&json[..], "Level_0").is_err());
+    }
+
+    #[test]
+    fn test_index() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+
+        assert_eq!(map["Tile Layer 1"], map.layers[0]);
+        assert!(map.layer("missing").is_none());
+        assert_eq!((&map).into_iter().count(), map.layers.len());
+
+        if let LayerType::TileLayer(layer) = &map["Tile Layer 1"].data {
+            assert_eq!(layer[(3, 2)], layer.get_tile(3, 2));
+        }
+        for layer in &map {
+            assert!(!layer.name.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_map_state() {
+        let map = parse_file(Path::new("assets/map.json")).unwrap();
+        let object_id = map.objects().next().unwrap().id;
+
+        let mut state = MapState::new();
+        state.set_tile("Tile Layer 1", 1, 2, 42);
+        state.set_layer_visible("Tile Layer 2", false);
+        state.remove_object(object_id);
+        state.set_tile("missing", 0, 0, 1);
+
+        let json = serde_json::to_string(&state).unwrap();
+        let state: MapState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.tile("Tile Layer 1", 1, 2), Some(42));
+
+        let mut reloaded = map.clone();
+        assert_eq!(state.apply(&mut reloaded), 1);
+        assert_eq!(reloaded.objects().count(), map.objects().count() - 1);
+        assert!(!reloaded["Tile Layer 2"].visible);
+        if let LayerType::TileLayer(layer) = &reloaded["Tile Layer 1"].data {
+            assert_eq!(layer.get_tile(1, 2), 42);
+        }
+    }
 }
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TiledError;
+use crate::options::ParseOptions;
+use crate::utils::json_from_slice;
+use crate::{parse_slice_with_options, Map, Object, ObjectId};
+
+/// Parse options chosen by the class of the map, see `Loader`.
+#[derive(Debug, Clone, Default)]
+pub struct LoaderConfig {
+    default: ParseOptions,
+    profiles: HashMap<String, ParseOptions>,
+}
+
+impl LoaderConfig {
+    /// Default options for all maps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for maps without a class or with a class without profile.
+    pub fn default_options(mut self, options: ParseOptions) -> Self {
+        self.default = options;
+        self
+    }
+
+    /// Options for maps of `class`.
+    pub fn profile(mut self, class: &str, options: ParseOptions) -> Self {
+        self.profiles.insert(class.to_string(), options);
+        self
+    }
+
+    /// Options used for maps of `class`.
+    pub fn options_for(&self, class: Option<&str>) -> &ParseOptions {
+        class
+            .and_then(|class| self.profiles.get(class))
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Reference to an object by map path and object ID. Unlike layer and object
+/// indexes, IDs don't change when the map is edited, so references can be
+/// kept across reloads and in save games.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct ObjectRef {
+    pub map: PathBuf,
+    pub id: ObjectId,
+}
+
+impl ObjectRef {
+    pub fn new<P: Into<PathBuf>>(map: P, id: ObjectId) -> Self {
+        ObjectRef {
+            map: map.into(),
+            id,
+        }
+    }
+}
+
+/// Only the class of a map, read before choosing the options.
+#[derive(Deserialize)]
+struct MapClass {
+    #[serde(default)]
+    class: Option<String>,
+}
+
+/// Loads maps with the options of their class and caches them by path. The
+/// loader can be shared between threads.
+#[derive(Debug, Default)]
+pub struct Loader {
+    config: LoaderConfig,
+    maps: Mutex<HashMap<PathBuf, Arc<Map>>>,
+}
+
+impl Loader {
+    pub fn new(config: LoaderConfig) -> Self {
+        Loader {
+            config,
+            maps: Mutex::default(),
+        }
+    }
+
+    pub fn config(&self) -> &LoaderConfig {
+        &self.config
+    }
+
+    /// Map at `path`, parsed on first use.
+    pub fn load(&self, path: &Path) -> Result<Arc<Map>, TiledError> {
+        if let Some(map) = self.maps.lock().unwrap().get(path) {
+            return Ok(map.clone());
+        }
+
+        let bytes = fs::read(path).map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+        let class = json_from_slice::<MapClass>(&bytes)?.class;
+        let options = self.config.options_for(class.as_deref());
+        let map = Arc::new(parse_slice_with_options(&bytes, options)?);
+
+        // Another thread may have loaded the map meanwhile, keep the first.
+        Ok(self
+            .maps
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_insert(map)
+            .clone())
+    }
+
+    /// Object referenced by `object`, loading the map if needed. `None` if
+    /// the object has been removed from the map.
+    pub fn resolve(&self, object: &ObjectRef) -> Result<Option<Object>, TiledError> {
+        Ok(self.load(&object.map)?.object_by_id(object.id).cloned())
+    }
+
+    /// Drop the cached map at `path`, eg. after the file changed.
+    pub fn evict(&self, path: &Path) -> Option<Arc<Map>> {
+        self.maps.lock().unwrap().remove(path)
+    }
+
+    /// Drop all cached maps.
+    pub fn clear(&self) {
+        self.maps.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use serde_json::{json, Map as JsonObject, Value};
+
+use crate::error::TiledError;
+use crate::properties::property_value_to_json;
+use crate::{Object, ObjectGroup};
+
+/// Output format of `ObjectGroup::export_manifest`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ManifestFormat {
+    /// Header row and one row per object. Properties are columns named after
+    /// the property, empty for objects without the property.
+    Csv,
+    /// One JSON object per line with properties in a `properties` object.
+    NdJson,
+}
+
+impl ObjectGroup {
+    /// Write the objects as a flat list of `id, name, type, x, y` and custom
+    /// properties, eg. spawn tables for spreadsheets and other tools.
+    pub fn export_manifest<W: Write>(
+        &self,
+        mut writer: W,
+        format: ManifestFormat,
+    ) -> Result<(), TiledError> {
+        match format {
+            ManifestFormat::Csv => self.write_csv(&mut writer),
+            ManifestFormat::NdJson => self.write_ndjson(&mut writer),
+        }
+        .map_err(|err| TiledError::Other(format!("{:?}", err)))
+    }
+
+    fn write_csv<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let names: BTreeSet<&str> = self
+            .objects
+            .iter()
+            .flat_map(|object| object.properties.iter().flatten())
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        let header = ["id", "name", "type", "x", "y"]
+            .iter()
+            .copied()
+            .chain(names.iter().copied())
+            .map(csv_field)
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", header.join(","))?;
+
+        for object in &self.objects {
+            let mut row = vec![
+                object.id.to_string(),
+                csv_field(&object.name),
+                csv_field(&object.r#type),
+                object.x.to_string(),
+                object.y.to_string(),
+            ];
+            row.extend(names.iter().map(|name| {
+                match object.properties.as_ref().and_then(|p| p.get(*name)) {
+                    Some(property) => csv_field(&property.to_string()),
+                    None => String::new(),
+                }
+            }));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_ndjson<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for object in &self.objects {
+            serde_json::to_writer(&mut *writer, &manifest_entry(object))?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn manifest_entry(object: &Object) -> Value {
+    let properties: JsonObject<String, Value> = object
+        .properties
+        .iter()
+        .flatten()
+        .map(|(name, property)| (name.clone(), property_value_to_json(property)))
+        .collect();
+
+    json!({
+        "id": object.id,
+        "name": object.name,
+        "type": object.r#type,
+        "x": object.x,
+        "y": object.y,
+        "properties": properties,
+    })
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
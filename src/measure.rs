@@ -0,0 +1,141 @@
+use std::f32::consts::PI;
+
+use crate::{Object, ObjectShape, Point};
+
+impl ObjectShape {
+    /// Length of a polyline, or perimeter of a polygon, in pixels.
+    pub fn length(&self) -> Option<f32> {
+        let (points, closed) = match self {
+            ObjectShape::Polyline { points } => (points, false),
+            ObjectShape::Polygon { points } => (points, true),
+            _ => return None,
+        };
+
+        Some(segments(points, closed).map(|(a, b)| distance(a, b)).sum())
+    }
+
+    /// Area of a polygon, rectangle or ellipse in square pixels.
+    pub fn area(&self) -> Option<f32> {
+        match self {
+            ObjectShape::Polygon { points } => Some(signed_area(points).abs()),
+            ObjectShape::Rect { width, height } => Some(width * height),
+            ObjectShape::Ellipse { width, height } => Some(PI * width * height / 4.0),
+            _ => None,
+        }
+    }
+
+    /// Center of mass relative to the object position, without rotation.
+    /// Polylines are weighted by segment length. Tile objects are aligned to
+    /// their bottom left corner, see `Object::centroid`.
+    pub fn centroid(&self) -> Option<Point> {
+        match self {
+            ObjectShape::Point => Some(Point { x: 0.0, y: 0.0 }),
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => Some(Point {
+                x: width / 2.0,
+                y: height / 2.0,
+            }),
+            ObjectShape::Polygon { points } => polygon_centroid(points),
+            ObjectShape::Polyline { points } => polyline_centroid(points),
+            ObjectShape::Unknown(_) | ObjectShape::Invalid { .. } => None,
+        }
+    }
+}
+
+impl Object {
+    /// Point relative to the object, eg. a polygon point, in map pixels.
+    /// Object rotation is taken into account.
+    pub fn to_world(&self, point: &Point) -> Point {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Point {
+            x: self.x + point.x * cos - point.y * sin,
+            y: self.y + point.x * sin + point.y * cos,
+        }
+    }
+
+    /// Points of a polyline or polygon in map pixels.
+    pub fn world_points(&self) -> Option<Vec<Point>> {
+        match &self.shape {
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                Some(points.iter().map(|point| self.to_world(point)).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Center of mass of the shape in map pixels, see
+    /// `ObjectShape::centroid`.
+    pub fn centroid(&self) -> Option<Point> {
+        let mut centroid = self.shape.centroid()?;
+        if let (Some(_), ObjectShape::Rect { height, .. }) = (self.gid, &self.shape) {
+            centroid.y -= height;
+        }
+        Some(self.to_world(&centroid))
+    }
+}
+
+fn segments(points: &[Point], closed: bool) -> impl Iterator<Item = (&Point, &Point)> {
+    let closing = if closed && points.len() > 2 {
+        points.last().zip(points.first())
+    } else {
+        None
+    };
+    points
+        .windows(2)
+        .map(|pair| (&pair[0], &pair[1]))
+        .chain(closing)
+}
+
+fn distance(a: &Point, b: &Point) -> f32 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+/// Signed area of a closed polygon. Positive when the points wind clockwise
+/// as drawn in Tiled, where y points down, which is counter-clockwise in y-up
+/// coordinates.
+pub(crate) fn signed_area(points: &[Point]) -> f32 {
+    segments(points, true)
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        / 2.0
+}
+
+fn polygon_centroid(points: &[Point]) -> Option<Point> {
+    let area = signed_area(points);
+    if area.abs() <= f32::EPSILON {
+        return polyline_centroid(points);
+    }
+
+    let (mut x, mut y) = (0.0, 0.0);
+    for (a, b) in segments(points, true) {
+        let cross = a.x * b.y - b.x * a.y;
+        x += (a.x + b.x) * cross;
+        y += (a.y + b.y) * cross;
+    }
+
+    Some(Point {
+        x: x / (6.0 * area),
+        y: y / (6.0 * area),
+    })
+}
+
+fn polyline_centroid(points: &[Point]) -> Option<Point> {
+    let first = points.first()?;
+    let (mut x, mut y, mut length) = (0.0, 0.0, 0.0);
+    for (a, b) in segments(points, false) {
+        let segment = distance(a, b);
+        x += (a.x + b.x) / 2.0 * segment;
+        y += (a.y + b.y) / 2.0 * segment;
+        length += segment;
+    }
+
+    Some(if length > 0.0 {
+        Point {
+            x: x / length,
+            y: y / length,
+        }
+    } else {
+        first.clone()
+    })
+}
@@ -0,0 +1,137 @@
+//! Vertex and index buffers of tile layers, enabled with the `mesh` feature.
+
+use crate::tileset::GID_FLAGS;
+use crate::{ResolvedGid, TileLayer, FLIPPED_DIAGONALLY, FLIPPED_HORIZONTALLY, FLIPPED_VERTICALLY};
+
+/// Vertex of a tile quad, laid out for direct upload to the GPU.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Vertex {
+    /// Position in map pixels.
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    /// Color multiplied with the texture, rgba from 0 to 1.
+    pub color: [f32; 4],
+}
+
+/// Triangles of a tile layer, two per quad, see `TileLayer::build_mesh`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+/// Options of `TileLayer::build_mesh`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct MeshOptions {
+    /// Grid cell width in pixels, usually the tile width of the map.
+    pub tile_width: f32,
+    /// Grid cell height in pixels, usually the tile height of the map.
+    pub tile_height: f32,
+    /// Vertex color, eg. layer tint and opacity.
+    pub color: [f32; 4],
+    /// Draw runs of identical unflipped tiles on a row as one quad. Texture
+    /// coordinates of merged quads extend `length` tiles to the right, so
+    /// the renderer has to wrap them to the tile, eg. with `fract` in the
+    /// shader.
+    pub merge_runs: bool,
+}
+
+impl MeshOptions {
+    pub fn new(tile_width: f32, tile_height: f32) -> Self {
+        MeshOptions {
+            tile_width,
+            tile_height,
+            color: [1.0; 4],
+            merge_runs: false,
+        }
+    }
+}
+
+impl Mesh {
+    /// Add a quad with corners top-left, top-right, bottom-right and
+    /// bottom-left.
+    fn push_quad(&mut self, positions: [[f32; 2]; 4], uvs: [[f32; 2]; 4], color: [f32; 4]) {
+        let first = self.vertices.len() as u32;
+
+        for (position, uv) in positions.iter().zip(uvs.iter()) {
+            self.vertices.push(Vertex {
+                position: *position,
+                uv: *uv,
+                color,
+            });
+        }
+
+        self.indices
+            .extend([first, first + 1, first + 2, first, first + 2, first + 3].iter());
+    }
+}
+
+/// Texture coordinates of the corners of a tile drawn with flip flags of
+/// `gid`, see `gid_transform`.
+fn corner_uvs(uv: [f32; 4], gid: u32) -> [[f32; 2]; 4] {
+    let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+    let mut uvs = [[0.0; 2]; 4];
+    for (corner, (s, t)) in uvs.iter_mut().zip(corners.iter()) {
+        // Flips are undone in reverse order: vertical, horizontal, diagonal.
+        let (mut s, mut t) = (*s, *t);
+        if gid & FLIPPED_VERTICALLY != 0 {
+            t = 1.0 - t;
+        }
+        if gid & FLIPPED_HORIZONTALLY != 0 {
+            s = 1.0 - s;
+        }
+        if gid & FLIPPED_DIAGONALLY != 0 {
+            std::mem::swap(&mut s, &mut t);
+        }
+
+        *corner = [uv[0] + s * (uv[2] - uv[0]), uv[1] + t * (uv[3] - uv[1])];
+    }
+
+    uvs
+}
+
+impl TileLayer {
+    /// Quads of the non-empty tiles of an orthogonal layer, with texture
+    /// coordinates from a table built with `Map::build_gid_table`. Tiles
+    /// larger than the grid are aligned to the bottom-left corner of their
+    /// cell like in Tiled. Tiles missing from the table are skipped.
+    pub fn build_mesh(&self, table: &[ResolvedGid], options: &MeshOptions) -> Mesh {
+        let mut mesh = Mesh::default();
+
+        for (x, y, length, gid) in self.runs() {
+            let resolved = match table.get((gid & !GID_FLAGS) as usize) {
+                Some(resolved) if gid != 0 && resolved.tileset.is_some() => resolved,
+                _ => continue,
+            };
+
+            let (width, height) = match resolved.rect {
+                [_, _, 0, _] | [_, _, _, 0] => (options.tile_width, options.tile_height),
+                [_, _, width, height] => (width as f32, height as f32),
+            };
+
+            let merge = options.merge_runs && gid & GID_FLAGS == 0;
+            let (count, quads) = if merge { (length, 1) } else { (1, length) };
+
+            for quad in 0..quads {
+                let column = (self.start_x() + (x + quad) as i32) as f32;
+                let left = column * options.tile_width;
+                let bottom = (self.start_y() + y as i32 + 1) as f32 * options.tile_height;
+                let right = left + width + (count - 1) as f32 * options.tile_width;
+                let top = bottom - height;
+
+                let mut uv = resolved.uv;
+                uv[2] += (count - 1) as f32 * (uv[2] - uv[0]);
+
+                mesh.push_quad(
+                    [[left, top], [right, top], [right, bottom], [left, bottom]],
+                    corner_uvs(uv, gid),
+                    options.color,
+                );
+            }
+        }
+
+        mesh
+    }
+}
@@ -0,0 +1,40 @@
+//! Parsing of memory-mapped map files, enabled with the `mmap` feature.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::TiledError;
+use crate::{parse_slice_with_options, Map, ParseOptions};
+
+/// Parse a map file mapped to memory instead of reading it to a buffer first.
+/// Huge maps are parsed straight from the page cache, which is shared with
+/// other processes mapping the same file.
+///
+/// The file must not be modified while it's parsed.
+pub fn parse_file_mmap(path: &Path) -> Result<Map, TiledError> {
+    parse_file_mmap_with_options(path, &ParseOptions::default())
+}
+
+/// Parse a memory-mapped map file with given options, see `parse_file_mmap`.
+pub fn parse_file_mmap_with_options(
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<Map, TiledError> {
+    let io_error = |err: io::Error| TiledError::Other(format!("{:?}", err));
+    let file = File::open(path).map_err(io_error)?;
+
+    // Empty files can't be mapped on every platform.
+    if file.metadata().map_err(io_error)?.len() == 0 {
+        return parse_slice_with_options(&[], options);
+    }
+
+    // Safety: the mapping is only read and lives until the map is parsed.
+    // Other processes truncating the file while it's parsed is documented
+    // as unsupported.
+    let mmap = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+
+    parse_slice_with_options(&mmap, options)
+}
@@ -0,0 +1,311 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::TiledError;
+#[cfg(feature = "http")]
+use crate::http::HttpResolver;
+#[cfg(feature = "intern")]
+use crate::intern::Interner;
+use crate::project::Project;
+use crate::warning::ParseWarning;
+
+/// Options controlling how maps are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) lenient: bool,
+    pub(crate) strict: bool,
+    pub(crate) preallocate_hint: Option<usize>,
+    pub(crate) keep_encoded: bool,
+    pub(crate) build_object_index: bool,
+    #[cfg(feature = "intern")]
+    pub(crate) interner: Option<Interner>,
+    pub(crate) max_decompressed_bytes: Option<usize>,
+    pub(crate) project: Option<Arc<Project>>,
+    pub(crate) gid_transform: Option<GidTransform>,
+    #[cfg(feature = "http")]
+    pub(crate) http: Option<HttpResolver>,
+    /// Set by `parse_with_errors`.
+    pub(crate) collect_errors: bool,
+}
+
+impl ParseOptions {
+    /// Default options. Maps must be in the format written by Tiled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept files from other exporters and older Tiled versions, eg.
+    /// `"Orthogonal"` or `"base64 "` instead of `"orthogonal"` and `"base64"`.
+    /// Object coordinates written as strings (`"12,5"`) are converted to
+    /// numbers and non-finite numbers are replaced with zero. Array values
+    /// of properties are read as `Property::List`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Reject fields that aren't part of the Tiled format, eg. typos in
+    /// generated maps. Fails with `TiledError::UnknownField` wrapped in
+    /// `TiledError::Path`, or `TiledError::NonStandardProperty` for list
+    /// properties. Staggered and hexagonal maps must have the fields of their
+    /// orientation, see `Map::validate`. Ignored in lenient mode.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Expected size of the input in bytes. When set, the input is read into
+    /// a buffer of this capacity and parsed from memory, which is much faster
+    /// than parsing directly from an unbuffered reader.
+    /// `parse_file_with_options` uses the file size when no hint is given.
+    pub fn preallocate_hint(mut self, bytes: usize) -> Self {
+        self.preallocate_hint = Some(bytes);
+        self
+    }
+
+    /// Don't decode base64 encoded tile layers. The layers keep the raw
+    /// (possibly compressed) bytes until `TileLayer::decode` is called, so
+    /// decoding can be done on a worker thread or skipped entirely.
+    pub fn keep_encoded(mut self, keep_encoded: bool) -> Self {
+        self.keep_encoded = keep_encoded;
+        self
+    }
+
+    /// Build object indexes after parsing, see `Map::find_objects_fast`.
+    pub fn build_object_index(mut self, build_object_index: bool) -> Self {
+        self.build_object_index = build_object_index;
+        self
+    }
+
+    /// Share storage of equal object names and types, which repeat a lot in
+    /// maps with many objects. The interner can be shared between maps.
+    #[cfg(feature = "intern")]
+    pub fn interner(mut self, interner: &Interner) -> Self {
+        self.interner = Some(interner.clone());
+        self
+    }
+
+    /// Maximum size of decompressed tile data of a layer or chunk, and of
+    /// the tiles of infinite layers merged from their chunks. Parsing fails
+    /// with `TiledError::DecompressionLimitExceeded` instead of inflating a
+    /// tiny payload to gigabytes. Defaults to the size of the tiles the layer
+    /// declares, `width * height * 4` bytes.
+    pub fn max_decompressed_bytes(mut self, bytes: usize) -> Self {
+        self.max_decompressed_bytes = Some(bytes);
+        self
+    }
+
+    /// Fill in default properties of object classes defined in the project,
+    /// see `Map::apply_class_defaults`.
+    pub fn project(mut self, project: &Project) -> Self {
+        self.project = Some(Arc::new(project.clone()));
+        self
+    }
+
+    /// Convert GIDs of tile layers while decoding, see `GidTransform`.
+    pub fn gid_transform(mut self, transform: GidTransform) -> Self {
+        self.gid_transform = Some(transform);
+        self
+    }
+
+    /// Load external tilesets and templates referenced by URL in
+    /// `parse_file_with_externals`. Relative references in files loaded from
+    /// a URL are resolved against the URL.
+    #[cfg(feature = "http")]
+    pub fn http(mut self, resolver: HttpResolver) -> Self {
+        self.http = Some(resolver);
+        self
+    }
+}
+
+/// Conversion between GIDs stored in files and GIDs used by the game, eg.
+/// for pipelines that keep extra bits below the flip flags. `decode` is
+/// applied to every cell of tile layers when they're decoded and `encode`
+/// when they're encoded with `TileLayer::encode`.
+#[derive(Clone)]
+pub struct GidTransform {
+    decode: Arc<dyn Fn(u32) -> u32 + Send + Sync>,
+    encode: Arc<dyn Fn(u32) -> u32 + Send + Sync>,
+}
+
+impl GidTransform {
+    /// `encode` should be the inverse of `decode`.
+    pub fn new<D, E>(decode: D, encode: E) -> Self
+    where
+        D: Fn(u32) -> u32 + Send + Sync + 'static,
+        E: Fn(u32) -> u32 + Send + Sync + 'static,
+    {
+        GidTransform {
+            decode: Arc::new(decode),
+            encode: Arc::new(encode),
+        }
+    }
+
+    /// GID in the file to GID in memory.
+    pub fn decode(&self, gid: u32) -> u32 {
+        (self.decode)(gid)
+    }
+
+    /// GID in memory to GID in the file.
+    pub fn encode(&self, gid: u32) -> u32 {
+        (self.encode)(gid)
+    }
+}
+
+impl fmt::Debug for GidTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("GidTransform")
+    }
+}
+
+/// Transforms are equal if they're clones of each other.
+impl PartialEq for GidTransform {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.decode, &other.decode) && Arc::ptr_eq(&self.encode, &other.encode)
+    }
+}
+
+thread_local! {
+    /// Options of the parse in progress on this thread. Serde can't pass
+    /// context to `Deserialize` implementations.
+    static CURRENT: RefCell<ParseOptions> = RefCell::new(ParseOptions::default());
+
+    /// Errors reported by `Deserialize` implementations. Serde only supports
+    /// string errors and untagged enums swallow them completely.
+    static REPORTED: RefCell<Vec<TiledError>> = const { RefCell::new(Vec::new()) };
+
+    /// Warnings reported by `Deserialize` implementations, see `Map::warnings`.
+    static WARNINGS: RefCell<Vec<ParseWarning>> = const { RefCell::new(Vec::new()) };
+
+    /// Number of parses in progress, nested for external files.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Restores previous options when dropped, even if parsing panics.
+struct Restore(Option<ParseOptions>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            CURRENT.with(|current| *current.borrow_mut() = previous);
+        }
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Run `f` with `options` as the options of the parse in progress. The first
+/// error reported with `report_error` takes precedence over the result of `f`.
+pub(crate) fn with_options<T, F>(options: &ParseOptions, f: F) -> Result<T, TiledError>
+where
+    F: FnOnce() -> Result<T, TiledError>,
+{
+    let (result, errors) = run(options, f);
+
+    match (errors.into_iter().next(), result) {
+        // Keep the position of the failure that the reported error caused.
+        (Some(err), Err(TiledError::Path { path, .. })) => Err(TiledError::Path {
+            path,
+            source: Box::new(err),
+        }),
+        (Some(err), _) => Err(err),
+        (None, result) => result,
+    }
+}
+
+/// Run `f` with `options` as the options of the parse in progress and return
+/// all errors reported with `report_error`.
+pub(crate) fn with_options_collecting<T, F>(
+    options: &ParseOptions,
+    f: F,
+) -> Result<(T, Vec<TiledError>), TiledError>
+where
+    F: FnOnce() -> Result<T, TiledError>,
+{
+    let (result, errors) = run(options, f);
+
+    match result {
+        Ok(value) => Ok((value, errors)),
+        // Errors of individual layers didn't fail the parse.
+        Err(err) => Err(errors
+            .into_iter()
+            .find(|err| !matches!(err, TiledError::Layer { .. }))
+            .unwrap_or(err)),
+    }
+}
+
+fn run<T, F>(options: &ParseOptions, f: F) -> (Result<T, TiledError>, Vec<TiledError>)
+where
+    F: FnOnce() -> Result<T, TiledError>,
+{
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let _restore = Restore(Some(
+        CURRENT.with(|current| current.replace(options.clone())),
+    ));
+    let previous = REPORTED.with(|reported| reported.replace(Vec::new()));
+    let previous_warnings = WARNINGS.with(|warnings| warnings.replace(Vec::new()));
+
+    let result = f();
+
+    WARNINGS.with(|warnings| warnings.replace(previous_warnings));
+    (result, REPORTED.with(|reported| reported.replace(previous)))
+}
+
+/// Whether a parse started by this crate is in progress, as opposed to a
+/// `Deserialize` implementation called by serde directly.
+pub(crate) fn parse_in_progress() -> bool {
+    DEPTH.with(Cell::get) > 0
+}
+
+/// Run `f` with empty errors and warnings and return the ones it reported.
+/// The previous errors and warnings are restored afterwards.
+pub(crate) fn isolated<T, F>(f: F) -> (T, Vec<TiledError>, Vec<ParseWarning>)
+where
+    F: FnOnce() -> T,
+{
+    let previous = REPORTED.with(|reported| reported.replace(Vec::new()));
+    let previous_warnings = WARNINGS.with(|warnings| warnings.replace(Vec::new()));
+
+    let result = f();
+
+    (
+        result,
+        REPORTED.with(|reported| reported.replace(previous)),
+        WARNINGS.with(|warnings| warnings.replace(previous_warnings)),
+    )
+}
+
+/// Report an error that should fail the parse in progress, or be returned
+/// from `parse_with_errors`.
+pub(crate) fn report_error(err: TiledError) {
+    REPORTED.with(|reported| reported.borrow_mut().push(err));
+}
+
+/// Report a non-fatal issue of the parse in progress.
+pub(crate) fn report_warning(warning: ParseWarning) {
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(warning));
+}
+
+/// Warnings reported so far. Clears them.
+pub(crate) fn take_warnings() -> Vec<ParseWarning> {
+    WARNINGS.with(|warnings| warnings.take())
+}
+
+/// Number of errors reported so far.
+pub(crate) fn reported_count() -> usize {
+    REPORTED.with(|reported| reported.borrow().len())
+}
+
+/// Add context to errors reported after the first `from` errors.
+pub(crate) fn wrap_reported<F: Fn(TiledError) -> TiledError>(from: usize, wrap: F) {
+    REPORTED.with(|reported| {
+        let mut reported = reported.borrow_mut();
+        let wrapped: Vec<_> = reported.drain(from..).map(wrap).collect();
+        reported.extend(wrapped);
+    });
+}
+
+/// Access options of the parse in progress.
+pub(crate) fn current_options<T, F: FnOnce(&ParseOptions) -> T>(f: F) -> T {
+    CURRENT.with(|current| f(&current.borrow()))
+}
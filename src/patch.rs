@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use crate::error::TiledError;
+use crate::TileLayer;
+
+/// Change of a single tile in a `LayerPatch`.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct TileChange {
+    pub x: u32,
+    pub y: u32,
+    pub old_gid: u32,
+    pub new_gid: u32,
+}
+
+impl From<TileChange> for (u32, u32, u32, u32) {
+    /// `(x, y, old_gid, new_gid)`
+    fn from(change: TileChange) -> Self {
+        (change.x, change.y, change.old_gid, change.new_gid)
+    }
+}
+
+/// Minimal set of tile changes between two states of a tile layer, eg. for
+/// broadcasting updates to clients that share the same map.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct LayerPatch {
+    pub changes: Vec<TileChange>,
+}
+
+impl LayerPatch {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Patch that undoes this patch.
+    pub fn inverse(&self) -> LayerPatch {
+        LayerPatch {
+            changes: self
+                .changes
+                .iter()
+                .rev()
+                .map(|change| TileChange {
+                    old_gid: change.new_gid,
+                    new_gid: change.old_gid,
+                    ..*change
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TileLayer {
+    /// Set tile with x and y coordinates and return the previous GID.
+    ///
+    /// # Panics
+    ///
+    /// If the coordinates are outside the layer or the layer is still
+    /// encoded.
+    pub fn set_tile(&mut self, x: u32, y: u32, gid: u32) -> u32 {
+        assert!(x < self.width && y < self.height, "tile out of bounds");
+        let index = (x + y * self.width) as usize;
+        self.stats.0.take();
+        std::mem::replace(&mut Arc::make_mut(&mut self.tiles)[index], gid)
+    }
+
+    /// Set tiles from `(x, y, gid)` triples and return the changes. Tiles
+    /// that already have the GID are not included in the patch. Fails
+    /// without modifying the layer if a tile is outside the layer.
+    ///
+    /// The layer is decoded first if it's still encoded.
+    pub fn set_tiles<I>(&mut self, tiles: I) -> Result<LayerPatch, TiledError>
+    where
+        I: IntoIterator<Item = (u32, u32, u32)>,
+    {
+        self.decode()?;
+
+        let tiles: Vec<_> = tiles.into_iter().collect();
+        if let Some((x, y, _)) = tiles.iter().find(|(x, y, _)| !self.contains(*x, *y)) {
+            return Err(outside(*x, *y));
+        }
+
+        let changes = tiles
+            .into_iter()
+            .filter_map(|(x, y, gid)| {
+                let old_gid = self.set_tile(x, y, gid);
+                Some(TileChange {
+                    x,
+                    y,
+                    old_gid,
+                    new_gid: gid,
+                })
+                .filter(|_| old_gid != gid)
+            })
+            .collect();
+
+        Ok(LayerPatch { changes })
+    }
+
+    /// Changes that turn this layer into `other`, eg. for comparing a
+    /// generated layer against a golden one. Both layers must have the same
+    /// origin and dimensions and be decoded.
+    pub fn diff(&self, other: &TileLayer) -> Result<LayerPatch, TiledError> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(TiledError::Other(format!(
+                "Can't diff {}x{} layer with {}x{} layer",
+                self.width, self.height, other.width, other.height
+            )));
+        }
+
+        if (self.start_x, self.start_y) != (other.start_x, other.start_y) {
+            return Err(TiledError::Other(format!(
+                "Can't diff layer at ({}, {}) with layer at ({}, {})",
+                self.start_x, self.start_y, other.start_x, other.start_y
+            )));
+        }
+
+        if self.is_encoded() || other.is_encoded() {
+            return Err(TiledError::Other(
+                "Can't diff layers that haven't been decoded".to_string(),
+            ));
+        }
+
+        let width = self.width.max(1);
+        let changes = self
+            .tiles
+            .iter()
+            .zip(other.tiles.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, (old, new))| TileChange {
+                x: index as u32 % width,
+                y: index as u32 / width,
+                old_gid: *old,
+                new_gid: *new,
+            })
+            .collect();
+
+        Ok(LayerPatch { changes })
+    }
+
+    /// Apply changes of a patch. Fails without modifying the layer if a
+    /// change is outside the layer or the current GID of a tile isn't the
+    /// old GID of the change.
+    ///
+    /// The layer is decoded first if it's still encoded.
+    pub fn apply_patch(&mut self, patch: &LayerPatch) -> Result<(), TiledError> {
+        self.decode()?;
+
+        // Changes are checked in order, a tile may change more than once.
+        let mut pending = std::collections::HashMap::new();
+
+        for change in &patch.changes {
+            if !self.contains(change.x, change.y) {
+                return Err(outside(change.x, change.y));
+            }
+
+            let current = pending
+                .get(&(change.x, change.y))
+                .copied()
+                .unwrap_or_else(|| self.get_tile(change.x, change.y));
+            if current != change.old_gid {
+                return Err(TiledError::Other(format!(
+                    "Tile ({}, {}) is {}, expected {}",
+                    change.x, change.y, current, change.old_gid
+                )));
+            }
+
+            pending.insert((change.x, change.y), change.new_gid);
+        }
+
+        for change in &patch.changes {
+            self.set_tile(change.x, change.y, change.new_gid);
+        }
+
+        Ok(())
+    }
+
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+}
+
+fn outside(x: u32, y: u32) -> TiledError {
+    TiledError::Other(format!("Tile ({}, {}) is outside the layer", x, y))
+}
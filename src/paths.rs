@@ -0,0 +1,118 @@
+//! Resolution of paths referenced by maps. Tiled writes paths relative to the
+//! file that contains them, with `/` separators, but files from Windows tools
+//! may use `\`.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::{LayerType, Map, Properties, Property};
+
+/// Replace `\` separators with `/`.
+pub fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Path of a file referenced as `source` from a file in `base_dir`. `.` and
+/// `..` are resolved without accessing the file system.
+pub fn resolve(base_dir: &Path, source: &str) -> PathBuf {
+    lexical_normalize(&base_dir.join(normalize_separators(source)))
+}
+
+/// Remove `.` and resolve `..` components. `..` that would go above the
+/// start of a relative path is kept.
+fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+
+    components.iter().collect()
+}
+
+/// `path` relative to `dir`, with `/` separators. Both must be absolute or
+/// both relative to the same directory. Returns `None` if `dir` goes above
+/// the common prefix with `..`.
+fn relative_to(path: &Path, dir: &Path) -> Option<String> {
+    let path = lexical_normalize(path);
+    let dir = lexical_normalize(dir);
+
+    let common = path
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts = Vec::new();
+    for component in dir.components().skip(common) {
+        match component {
+            Component::Normal(_) => parts.push("..".to_string()),
+            _ => return None,
+        }
+    }
+    for component in path.components().skip(common) {
+        parts.push(component.as_os_str().to_str()?.to_string());
+    }
+
+    Some(parts.join("/"))
+}
+
+impl Map {
+    /// Rewrite relative paths of images and file properties for a map moved
+    /// from `old_dir` to `new_dir`, eg. when repacking assets. Paths are
+    /// normalized to `/` separators. Absolute paths and paths that can't be
+    /// expressed relative to `new_dir` are only normalized.
+    pub fn rebase_paths(&mut self, old_dir: &Path, new_dir: &Path) {
+        let rebase = |path: &mut String| {
+            let normalized = normalize_separators(path);
+            *path = if normalized.is_empty() || Path::new(&normalized).is_absolute() {
+                normalized
+            } else {
+                relative_to(&resolve(old_dir, &normalized), new_dir).unwrap_or(normalized)
+            };
+        };
+
+        let rebase_properties = |properties: &mut Option<Properties>| {
+            for property in properties
+                .iter_mut()
+                .flat_map(|properties| properties.values_mut())
+            {
+                if let Property::File(path) = property {
+                    rebase(path);
+                }
+            }
+        };
+
+        rebase_properties(&mut self.properties);
+
+        for tileset in &mut self.tilesets {
+            rebase(&mut tileset.image);
+            rebase_properties(&mut tileset.properties);
+            for tile in tileset.tiles.iter_mut().flatten() {
+                rebase_properties(&mut tile.properties);
+            }
+        }
+
+        for layer in &mut self.layers {
+            rebase_properties(&mut layer.properties);
+            match &mut layer.data {
+                LayerType::ImageLayer(image) => rebase(&mut image.image),
+                LayerType::ObjectGroup(group) => {
+                    for object in &mut group.objects {
+                        rebase_properties(&mut object.properties);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
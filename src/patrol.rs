@@ -0,0 +1,111 @@
+use crate::{Object, ObjectShape, Point};
+
+/// What happens at the end of a `PatrolPath`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PathMode {
+    /// Stop at the last point.
+    Once,
+    /// Continue from the first point.
+    Loop,
+    /// Turn around and walk back to the first point.
+    PingPong,
+}
+
+/// Path through the points of a polyline or polygon object in map pixels,
+/// sampled by distance travelled, eg. for enemies or moving platforms.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatrolPath {
+    points: Vec<Point>,
+    /// Distance from the first point to each point.
+    distances: Vec<f32>,
+    mode: PathMode,
+}
+
+impl PatrolPath {
+    /// Path through `points`. Returns `None` if there are no points.
+    pub fn new(points: Vec<Point>, mode: PathMode) -> Option<Self> {
+        points.first()?;
+
+        let mut distances = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        distances.push(total);
+        for pair in points.windows(2) {
+            total += (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y);
+            distances.push(total);
+        }
+
+        Some(PatrolPath {
+            points,
+            distances,
+            mode,
+        })
+    }
+
+    /// Length of one pass from the first point to the last.
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn mode(&self) -> PathMode {
+        self.mode
+    }
+
+    /// Position after travelling `distance` pixels from the first point, and
+    /// the unit vector of the direction of travel there. The direction is
+    /// zero for paths without length.
+    pub fn sample(&self, distance: f32) -> (Point, Point) {
+        let length = self.length();
+        if length <= 0.0 {
+            return (self.points[0].clone(), Point { x: 0.0, y: 0.0 });
+        }
+
+        let (distance, forward) = match self.mode {
+            PathMode::Once => (distance.max(0.0).min(length), true),
+            PathMode::Loop => (distance.rem_euclid(length), true),
+            PathMode::PingPong => {
+                let distance = distance.rem_euclid(2.0 * length);
+                if distance <= length {
+                    (distance, true)
+                } else {
+                    (2.0 * length - distance, false)
+                }
+            }
+        };
+
+        // First segment that ends at or after the distance and has a length.
+        let segment = self
+            .distances
+            .windows(2)
+            .position(|pair| pair[1] >= distance && pair[1] > pair[0])
+            .unwrap_or(self.distances.len() - 2);
+
+        let (a, b) = (&self.points[segment], &self.points[segment + 1]);
+        let (start, end) = (self.distances[segment], self.distances[segment + 1]);
+        let t = (distance - start) / (end - start);
+        let sign = if forward { 1.0 } else { -1.0 };
+
+        (
+            Point {
+                x: a.x + (b.x - a.x) * t,
+                y: a.y + (b.y - a.y) * t,
+            },
+            Point {
+                x: sign * (b.x - a.x) / (end - start),
+                y: sign * (b.y - a.y) / (end - start),
+            },
+        )
+    }
+}
+
+impl Object {
+    /// Path through the points of a polyline or polygon in map pixels. Paths
+    /// of polygons end at the first point.
+    pub fn patrol_path(&self, mode: PathMode) -> Option<PatrolPath> {
+        let mut points = self.world_points()?;
+        if let (ObjectShape::Polygon { .. }, Some(first)) = (&self.shape, points.first()) {
+            points.push(first.clone());
+        }
+
+        PatrolPath::new(points, mode)
+    }
+}
@@ -0,0 +1,127 @@
+//! Custom types of Tiled project files (`.tiled-project`).
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::error::TiledError;
+use crate::utils::json_from_reader;
+use crate::{LayerType, Map, Properties, Property};
+
+/// Tiled project, see `parse_project`. Only custom property types are read.
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct Project {
+    #[serde(default, rename = "propertyTypes")]
+    pub property_types: Vec<PropertyType>,
+}
+
+/// Custom property type of a project.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "type")]
+pub enum PropertyType {
+    Class(ClassType),
+    Enum(EnumType),
+}
+
+/// Custom class with default values of its members.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct ClassType {
+    pub id: u32,
+    pub name: String,
+    /// Members and their default values. Members whose type is another
+    /// class are left out.
+    #[serde(deserialize_with = "deserialize_members")]
+    pub members: Properties,
+}
+
+/// Custom enum. Values of enum properties are written as strings, or ints
+/// with `valuesAsFlags`.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct EnumType {
+    pub id: u32,
+    pub name: String,
+    pub values: Vec<String>,
+    #[serde(default, rename = "valuesAsFlags")]
+    pub values_as_flags: bool,
+}
+
+fn deserialize_members<'de, D>(deserializer: D) -> Result<Properties, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let members = Vec::<Value>::deserialize(deserializer)?;
+
+    Ok(members
+        .iter()
+        .filter_map(|member| {
+            let name = member.get("name")?.as_str()?;
+            let value = Property::deserialize(member).ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect())
+}
+
+impl Project {
+    /// Class type by name.
+    pub fn class(&self, name: &str) -> Option<&ClassType> {
+        self.property_types.iter().find_map(|ty| match ty {
+            PropertyType::Class(class) if class.name == name => Some(class),
+            _ => None,
+        })
+    }
+
+    /// Enum type by name.
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        self.property_types.iter().find_map(|ty| match ty {
+            PropertyType::Enum(ty) if ty.name == name => Some(ty),
+            _ => None,
+        })
+    }
+}
+
+/// Parse a Tiled project file.
+pub fn parse_project<R: Read>(reader: R) -> Result<Project, TiledError> {
+    json_from_reader(reader)
+}
+
+/// Read and parse a Tiled project file.
+pub fn parse_project_file(path: &Path) -> Result<Project, TiledError> {
+    let file = File::open(path).map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+
+    parse_project(file)
+}
+
+/// Add defaults of `class` that are missing from `properties`.
+fn fill_defaults(properties: &mut Option<Properties>, class: &ClassType) {
+    if class.members.is_empty() {
+        return;
+    }
+
+    let properties = properties.get_or_insert_with(Properties::new);
+    for (name, value) in &class.members {
+        properties
+            .entry(name.clone())
+            .or_insert_with(|| value.clone());
+    }
+}
+
+impl Map {
+    /// Fill in default properties of object classes. Tiled leaves members
+    /// that have their default value out of the export, so object properties
+    /// are incomplete without the project. Done while parsing with
+    /// `ParseOptions::project`.
+    pub fn apply_class_defaults(&mut self, project: &Project) {
+        for layer in &mut self.layers {
+            if let LayerType::ObjectGroup(group) = &mut layer.data {
+                for object in &mut group.objects {
+                    if let Some(class) = project.class(&object.r#type) {
+                        fill_defaults(&mut object.properties, class);
+                    }
+                }
+            }
+        }
+    }
+}
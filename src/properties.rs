@@ -1,9 +1,17 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 
 use serde::{de, Deserialize, Deserializer};
+use serde_json::{json, Value};
 
-use crate::Color;
+use crate::error::TiledError;
+use crate::options::current_options;
+use crate::{Color, Layer, Map, Object};
+
+/// Maximum depth of nested `${...}` references. Guards against cycles.
+const MAX_EXPAND_DEPTH: usize = 8;
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 #[serde(rename_all = "lowercase", tag = "type", content = "value")]
@@ -14,10 +22,415 @@ pub enum Property {
 	Color(Color),
 	String(String),
 	File(String),
+	/// Array value written by some export plugins. Only read in lenient
+	/// mode, see `ParseOptions::lenient`.
+	#[serde(skip_deserializing)]
+	List(Vec<Property>),
+}
+
+impl Property {
+	/// Value of a string or file property.
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Property::String(value) | Property::File(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Name of the property type as written by Tiled.
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Property::Bool(_) => "bool",
+			Property::Float(_) => "float",
+			Property::Int(_) => "int",
+			Property::Color(_) => "color",
+			Property::String(_) => "string",
+			Property::File(_) => "file",
+			Property::List(_) => "list",
+		}
+	}
+
+	/// Parse a value of given Tiled property type, eg. `("int", "10")`.
+	pub fn parse_typed(type_name: &str, value: &str) -> Result<Property, TiledError> {
+		let invalid = || TiledError::Other(format!("Invalid {} property {:?}", type_name, value));
+
+		match type_name {
+			"bool" => value.parse().map(Property::Bool).map_err(|_| invalid()),
+			"float" => value.parse().map(Property::Float).map_err(|_| invalid()),
+			"int" => value.parse().map(Property::Int).map_err(|_| invalid()),
+			"color" => value.parse().map(Property::Color),
+			"string" => Ok(Property::String(value.to_string())),
+			"file" => Ok(Property::File(value.to_string())),
+			_ => Err(TiledError::Other(format!(
+				"Unknown property type {:?}",
+				type_name
+			))),
+		}
+	}
+
+	fn type_error(&self, expected: &str) -> TiledError {
+		TiledError::Other(format!(
+			"Expected {} property, found {}",
+			expected,
+			self.type_name()
+		))
+	}
+}
+
+impl fmt::Display for Property {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Property::Bool(value) => value.fmt(f),
+			Property::Float(value) => value.fmt(f),
+			Property::Int(value) => value.fmt(f),
+			Property::Color(value) => f.write_str(&value.to_hex()),
+			Property::String(value) | Property::File(value) => f.write_str(value),
+			Property::List(values) => {
+				f.write_str("[")?;
+				for (i, value) in values.iter().enumerate() {
+					if i > 0 {
+						f.write_str(", ")?;
+					}
+					value.fmt(f)?;
+				}
+				f.write_str("]")
+			}
+		}
+	}
+}
+
+/// Infers the type from the value: `true` and `false` are bools, numbers are
+/// ints or floats, `#rrggbb` and `#aarrggbb` are colors and everything else
+/// is a string.
+impl FromStr for Property {
+	type Err = TiledError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Ok(value) = s.parse() {
+			return Ok(Property::Bool(value));
+		}
+		if let Ok(value) = s.parse() {
+			return Ok(Property::Int(value));
+		}
+		if let Ok(value) = s.parse() {
+			return Ok(Property::Float(value));
+		}
+		if s.starts_with('#') {
+			if let Ok(value) = s.parse() {
+				return Ok(Property::Color(value));
+			}
+		}
+
+		Ok(Property::String(s.to_string()))
+	}
+}
+
+impl From<bool> for Property {
+	fn from(value: bool) -> Self {
+		Property::Bool(value)
+	}
+}
+
+impl From<f32> for Property {
+	fn from(value: f32) -> Self {
+		Property::Float(value)
+	}
+}
+
+impl From<i32> for Property {
+	fn from(value: i32) -> Self {
+		Property::Int(value)
+	}
+}
+
+impl From<Color> for Property {
+	fn from(value: Color) -> Self {
+		Property::Color(value)
+	}
+}
+
+impl From<&str> for Property {
+	fn from(value: &str) -> Self {
+		Property::String(value.to_string())
+	}
+}
+
+impl From<String> for Property {
+	fn from(value: String) -> Self {
+		Property::String(value)
+	}
+}
+
+impl TryFrom<&Property> for bool {
+	type Error = TiledError;
+
+	fn try_from(property: &Property) -> Result<Self, Self::Error> {
+		match property {
+			Property::Bool(value) => Ok(*value),
+			_ => Err(property.type_error("bool")),
+		}
+	}
+}
+
+/// Int properties are converted to floats.
+impl TryFrom<&Property> for f32 {
+	type Error = TiledError;
+
+	fn try_from(property: &Property) -> Result<Self, Self::Error> {
+		match property {
+			Property::Float(value) => Ok(*value),
+			Property::Int(value) => Ok(*value as f32),
+			_ => Err(property.type_error("float")),
+		}
+	}
+}
+
+impl TryFrom<&Property> for i32 {
+	type Error = TiledError;
+
+	fn try_from(property: &Property) -> Result<Self, Self::Error> {
+		match property {
+			Property::Int(value) => Ok(*value),
+			_ => Err(property.type_error("int")),
+		}
+	}
+}
+
+impl TryFrom<&Property> for Color {
+	type Error = TiledError;
+
+	fn try_from(property: &Property) -> Result<Self, Self::Error> {
+		match property {
+			Property::Color(value) => Ok(*value),
+			_ => Err(property.type_error("color")),
+		}
+	}
+}
+
+impl<'a> TryFrom<&'a Property> for &'a str {
+	type Error = TiledError;
+
+	fn try_from(property: &'a Property) -> Result<Self, Self::Error> {
+		property
+			.as_str()
+			.ok_or_else(|| property.type_error("string"))
+	}
 }
 
 pub type Properties = HashMap<String, Property>;
 
+/// Extension methods for `Properties`.
+pub trait PropertiesExt {
+	/// Expand `${name}` placeholders in string and file properties.
+	///
+	/// Names are looked up from the expanded properties themselves and then
+	/// from object, layer and map properties in that order. Use `map.`,
+	/// `layer.` or `object.` prefix to look up from a specific scope, eg.
+	/// `${map.width}` or `${layer.name}`. Placeholders that can't be resolved
+	/// are left as is.
+	fn expand(&self, context: &ExpandContext) -> Properties;
+
+	/// Changes that turn these properties into `other`.
+	fn diff(&self, other: &Properties) -> PropertiesDiff;
+
+	/// Layer `other` on top of these properties, eg. a tuning override
+	/// file that only contains changed keys.
+	fn merge_from(&mut self, other: &Properties, strategy: MergeStrategy);
+}
+
+/// Result of `PropertiesExt::diff`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PropertiesDiff {
+	/// Added properties and properties with a different value.
+	pub changed: Properties,
+	/// Names of removed properties, sorted.
+	pub removed: Vec<String>,
+}
+
+impl PropertiesDiff {
+	pub fn is_empty(&self) -> bool {
+		self.changed.is_empty() && self.removed.is_empty()
+	}
+}
+
+/// How `PropertiesExt::merge_from` treats properties that exist on both
+/// sides.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MergeStrategy {
+	/// Values of the other properties win.
+	Overwrite,
+	/// Only add properties that don't exist yet.
+	KeepExisting,
+	/// Values of the other properties win if the types are the same. Guards
+	/// against override files that change the type of a property.
+	SameType,
+}
+
+impl PropertiesExt for Properties {
+	fn expand(&self, context: &ExpandContext) -> Properties {
+		let context = ExpandContext {
+			local: Some(self),
+			..*context
+		};
+
+		self.iter()
+			.map(|(name, property)| {
+				let property = match property {
+					Property::String(value) => Property::String(context.expand_str(value, 0)),
+					Property::File(value) => Property::File(context.expand_str(value, 0)),
+					property => property.clone(),
+				};
+
+				(name.clone(), property)
+			})
+			.collect()
+	}
+
+	fn diff(&self, other: &Properties) -> PropertiesDiff {
+		let changed = other
+			.iter()
+			.filter(|(name, property)| self.get(*name) != Some(property))
+			.map(|(name, property)| (name.clone(), property.clone()))
+			.collect();
+
+		let mut removed: Vec<String> = self
+			.keys()
+			.filter(|name| !other.contains_key(*name))
+			.cloned()
+			.collect();
+		removed.sort();
+
+		PropertiesDiff { changed, removed }
+	}
+
+	fn merge_from(&mut self, other: &Properties, strategy: MergeStrategy) {
+		for (name, property) in other {
+			let merge = match (self.get(name), strategy) {
+				(None, _) | (Some(_), MergeStrategy::Overwrite) => true,
+				(Some(_), MergeStrategy::KeepExisting) => false,
+				(Some(existing), MergeStrategy::SameType) => {
+					existing.type_name() == property.type_name()
+				}
+			};
+
+			if merge {
+				self.insert(name.clone(), property.clone());
+			}
+		}
+	}
+}
+
+/// Scopes used to resolve placeholders in `PropertiesExt::expand`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpandContext<'a> {
+	local: Option<&'a Properties>,
+	map: Option<&'a Map>,
+	layer: Option<&'a Layer>,
+	object: Option<&'a Object>,
+}
+
+impl<'a> ExpandContext<'a> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_map(mut self, map: &'a Map) -> Self {
+		self.map = Some(map);
+		self
+	}
+
+	pub fn with_layer(mut self, layer: &'a Layer) -> Self {
+		self.layer = Some(layer);
+		self
+	}
+
+	pub fn with_object(mut self, object: &'a Object) -> Self {
+		self.object = Some(object);
+		self
+	}
+
+	/// Resolve a single name to its unexpanded string value.
+	pub fn lookup(&self, name: &str) -> Option<String> {
+		let mut parts = name.splitn(2, '.');
+		let (scope, key) = (parts.next()?, parts.next());
+
+		match (scope, key) {
+			("map", Some(key)) => self.lookup_map(key),
+			("layer", Some(key)) => self.lookup_layer(key),
+			("object", Some(key)) => self.lookup_object(key),
+			_ => find_property(self.local, name)
+				.or_else(|| find_property(self.object.and_then(|o| o.properties.as_ref()), name))
+				.or_else(|| find_property(self.layer.and_then(|l| l.properties.as_ref()), name))
+				.or_else(|| find_property(self.map.and_then(|m| m.properties.as_ref()), name)),
+		}
+	}
+
+	fn lookup_map(&self, key: &str) -> Option<String> {
+		let map = self.map?;
+
+		match key {
+			"width" => Some(map.width.to_string()),
+			"height" => Some(map.height.to_string()),
+			"tilewidth" => Some(map.tile_width.to_string()),
+			"tileheight" => Some(map.tile_height.to_string()),
+			"version" => Some(map.version.clone()),
+			_ => find_property(map.properties.as_ref(), key),
+		}
+	}
+
+	fn lookup_layer(&self, key: &str) -> Option<String> {
+		let layer = self.layer?;
+
+		match key {
+			"name" => Some(layer.name.clone()),
+			_ => find_property(layer.properties.as_ref(), key),
+		}
+	}
+
+	fn lookup_object(&self, key: &str) -> Option<String> {
+		let object = self.object?;
+
+		match key {
+			"id" => Some(object.id.to_string()),
+			"name" => Some(object.name.to_string()),
+			"type" => Some(object.r#type.to_string()),
+			_ => find_property(object.properties.as_ref(), key),
+		}
+	}
+
+	fn expand_str(&self, value: &str, depth: usize) -> String {
+		let mut result = String::with_capacity(value.len());
+		let mut rest = value;
+
+		while let Some(start) = rest.find("${") {
+			result.push_str(&rest[..start]);
+			rest = &rest[start..];
+
+			let end = match rest.find('}') {
+				Some(end) => end,
+				None => break,
+			};
+
+			let placeholder = &rest[..=end];
+			match self.lookup(placeholder[2..placeholder.len() - 1].trim()) {
+				Some(value) if depth < MAX_EXPAND_DEPTH => {
+					result.push_str(&self.expand_str(&value, depth + 1))
+				}
+				_ => result.push_str(placeholder),
+			}
+
+			rest = &rest[end + 1..];
+		}
+
+		result.push_str(rest);
+		result
+	}
+}
+
+fn find_property(properties: Option<&Properties>, name: &str) -> Option<String> {
+	properties?.get(name).map(Property::to_string)
+}
+
 /// Helper struct
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -42,6 +455,15 @@ impl<'de> de::Visitor<'de> for PropertiesVisitor {
 	{
 		let mut map = Properties::with_capacity(seq.size_hint().unwrap_or(0));
 
+		if current_options(|options| options.lenient) {
+			while let Some(value) = seq.next_element::<Value>()? {
+				if let Some((name, property)) = lenient_property(value) {
+					map.insert(name, property);
+				}
+			}
+			return Ok(map);
+		}
+
 		// First deserialize array items to PropertyValue.
 		while let Some(value) = (seq.next_element() as Result<Option<PropertyValue>, _>)? {
 			// Then add Property to hashmap.
@@ -52,6 +474,40 @@ impl<'de> de::Visitor<'de> for PropertiesVisitor {
 	}
 }
 
+/// Property that may have an array value. Other invalid properties are
+/// skipped.
+fn lenient_property(value: Value) -> Option<(String, Property)> {
+	let name = value.get("name")?.as_str()?.to_string();
+
+	let property = match value.get("value") {
+		Some(Value::Array(values)) => Property::List(list_values(values)),
+		_ => PropertyValue::deserialize(&value).ok()?.value,
+	};
+
+	Some((name, property))
+}
+
+/// Infer types of list elements like `FromStr` does for strings.
+fn list_values(values: &[Value]) -> Vec<Property> {
+	values
+		.iter()
+		.filter_map(|value| match value {
+			Value::Bool(value) => Some(Property::Bool(*value)),
+			Value::Number(number) => Some(
+				number
+					.as_i64()
+					.and_then(|value| i32::try_from(value).ok())
+					.map(Property::Int)
+					.unwrap_or_else(|| Property::Float(number.as_f64().unwrap_or_default() as f32)),
+			),
+			Value::String(value) => Some(Property::String(value.clone())),
+			Value::Array(values) => Some(Property::List(list_values(values))),
+			Value::Object(_) => Property::deserialize(value).ok(),
+			Value::Null => None,
+		})
+		.collect()
+}
+
 pub fn deserialize_properties<'de, D>(deserializer: D) -> Result<Option<Properties>, D::Error>
 where
 	D: Deserializer<'de>,
@@ -62,3 +518,36 @@ where
 		Ok(None)
 	}
 }
+
+/// Value of a property as Tiled writes it.
+pub(crate) fn property_value_to_json(property: &Property) -> Value {
+	match property {
+		Property::Bool(value) => json!(value),
+		Property::Float(value) => json!(value),
+		Property::Int(value) => json!(value),
+		Property::Color(_) => json!(property.to_string()),
+		Property::String(value) | Property::File(value) => json!(value),
+		Property::List(values) => values.iter().map(property_value_to_json).collect(),
+	}
+}
+
+/// Properties in the form Tiled writes them, sorted by name.
+pub(crate) fn properties_to_json(properties: &Properties) -> Value {
+	let mut names: Vec<_> = properties.keys().collect();
+	names.sort();
+
+	let properties = names
+		.into_iter()
+		.map(|name| {
+			let property = &properties[name];
+
+			json!({
+				"name": name,
+				"type": property.type_name(),
+				"value": property_value_to_json(property),
+			})
+		})
+		.collect();
+
+	Value::Array(properties)
+}
@@ -0,0 +1,137 @@
+//! Conversion of `Properties` to and from TOML and YAML, enabled with the
+//! `toml` and `yaml` features, eg. for override sets kept next to maps.
+//!
+//! Bools, ints, floats, strings and lists are written as values of the same
+//! type. Colors and files are written as tagged strings, `"color:#aarrggbb"`
+//! and `"file:path"`, and strings that start with a tag get a `"string:"`
+//! tag so that they are read back as strings.
+
+use std::convert::TryFrom;
+
+use serde_json::{Map as JsonObject, Value};
+
+use crate::error::TiledError;
+use crate::{Properties, Property};
+
+const COLOR_TAG: &str = "color:";
+const FILE_TAG: &str = "file:";
+const STRING_TAG: &str = "string:";
+
+/// Write properties as TOML, sorted by name.
+#[cfg(feature = "toml")]
+pub fn properties_to_toml(properties: &Properties) -> Result<String, TiledError> {
+    toml::to_string(&to_tagged(properties)?).map_err(|err| TiledError::Other(err.to_string()))
+}
+
+/// Read properties written by `properties_to_toml`.
+#[cfg(feature = "toml")]
+pub fn properties_from_toml(toml: &str) -> Result<Properties, TiledError> {
+    from_tagged(toml::from_str(toml).map_err(|err| TiledError::Other(err.to_string()))?)
+}
+
+/// Write properties as YAML, sorted by name.
+#[cfg(feature = "yaml")]
+pub fn properties_to_yaml(properties: &Properties) -> Result<String, TiledError> {
+    serde_yaml::to_string(&to_tagged(properties)?).map_err(|err| TiledError::Other(err.to_string()))
+}
+
+/// Read properties written by `properties_to_yaml`.
+#[cfg(feature = "yaml")]
+pub fn properties_from_yaml(yaml: &str) -> Result<Properties, TiledError> {
+    from_tagged(serde_yaml::from_str(yaml).map_err(|err| TiledError::Other(err.to_string()))?)
+}
+
+fn to_tagged(properties: &Properties) -> Result<JsonObject<String, Value>, TiledError> {
+    properties
+        .iter()
+        .map(|(name, property)| Ok((name.clone(), tagged_value(name, property)?)))
+        .collect()
+}
+
+fn tagged_value(name: &str, property: &Property) -> Result<Value, TiledError> {
+    Ok(match property {
+        Property::Bool(value) => Value::from(*value),
+        Property::Int(value) => Value::from(*value),
+        // Through the shortest decimal form of the float, so that eg. 0.1 is
+        // written as 0.1 instead of 0.10000000149011612.
+        Property::Float(value) if value.is_finite() => {
+            Value::from(value.to_string().parse::<f64>().unwrap_or_default())
+        }
+        Property::Float(value) => {
+            return Err(TiledError::Other(format!(
+                "Can't write float {} of property {}",
+                value, name
+            )))
+        }
+        Property::Color(value) => Value::from(format!("{}{}", COLOR_TAG, value.to_hex())),
+        Property::File(value) => Value::from(format!("{}{}", FILE_TAG, value)),
+        Property::String(value) => {
+            if [COLOR_TAG, FILE_TAG, STRING_TAG]
+                .iter()
+                .any(|tag| value.starts_with(tag))
+            {
+                Value::from(format!("{}{}", STRING_TAG, value))
+            } else {
+                Value::from(value.as_str())
+            }
+        }
+        Property::List(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| tagged_value(name, value))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+fn from_tagged(value: Value) -> Result<Properties, TiledError> {
+    match value {
+        Value::Object(properties) => properties
+            .into_iter()
+            .map(|(name, value)| {
+                let property = property_from_tagged(&name, value)?;
+                Ok((name, property))
+            })
+            .collect(),
+        _ => Err(TiledError::Other(
+            "Expected a table of properties".to_string(),
+        )),
+    }
+}
+
+fn property_from_tagged(name: &str, value: Value) -> Result<Property, TiledError> {
+    let unsupported = |value: &Value| {
+        TiledError::Other(format!("Unsupported value {} of property {}", value, name))
+    };
+
+    match value {
+        Value::Bool(value) => Ok(Property::Bool(value)),
+        Value::Number(ref number) => {
+            if number.is_f64() {
+                return Ok(Property::Float(number.as_f64().unwrap_or_default() as f32));
+            }
+            number
+                .as_i64()
+                .and_then(|number| i32::try_from(number).ok())
+                .map(Property::Int)
+                .ok_or_else(|| unsupported(&value))
+        }
+        Value::String(value) => {
+            if let Some(color) = value.strip_prefix(COLOR_TAG) {
+                color.parse().map(Property::Color)
+            } else if let Some(file) = value.strip_prefix(FILE_TAG) {
+                Ok(Property::File(file.to_string()))
+            } else if let Some(string) = value.strip_prefix(STRING_TAG) {
+                Ok(Property::String(string.to_string()))
+            } else {
+                Ok(Property::String(value))
+            }
+        }
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| property_from_tagged(name, value))
+            .collect::<Result<_, _>>()
+            .map(Property::List),
+        Value::Null | Value::Object(_) => Err(unsupported(&value)),
+    }
+}
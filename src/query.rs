@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::error::TiledError;
+use crate::{Layer, LayerType, Map, Object, Properties, Property};
+
+/// What kind of items a query selects.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum QueryTarget {
+    Layers,
+    Objects,
+}
+
+/// Comparison operator of a property filter.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum QueryOp {
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+}
+
+impl QueryOp {
+    fn matches(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            QueryOp::Eq => ordering == Some(Ordering::Equal),
+            QueryOp::Ne => ordering != Some(Ordering::Equal),
+            QueryOp::Gt => ordering == Some(Ordering::Greater),
+            QueryOp::Lt => ordering == Some(Ordering::Less),
+            QueryOp::Ge => ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal),
+            QueryOp::Le => ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum QueryFilter {
+    /// Item has property with given name.
+    Has(String),
+    /// Compare a builtin field (eg. name or type) or a property to a value.
+    Compare {
+        key: String,
+        op: QueryOp,
+        value: String,
+    },
+}
+
+/// Item selected by a query.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Selected<'a> {
+    Layer(&'a Layer),
+    Object(&'a Object),
+}
+
+/// Select layers or objects by their name, type and custom properties.
+///
+/// Queries can be built with methods or parsed from a string such as
+/// `objects[type=enemy][hp>10]`. Filters in brackets are `key`, `key=value`,
+/// `key!=value`, `key>value`, `key<value`, `key>=value` or `key<=value` where
+/// key is `name`, `type` (and `id` for objects) or a property name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Query {
+    target: QueryTarget,
+    filters: Vec<QueryFilter>,
+}
+
+impl Query {
+    /// Query that selects layers.
+    pub fn layers() -> Self {
+        Query {
+            target: QueryTarget::Layers,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Query that selects objects from all object layers.
+    pub fn objects() -> Self {
+        Query {
+            target: QueryTarget::Objects,
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn target(&self) -> QueryTarget {
+        self.target
+    }
+
+    pub fn with_name(self, name: &str) -> Self {
+        self.compare("name", QueryOp::Eq, name)
+    }
+
+    pub fn with_type(self, r#type: &str) -> Self {
+        self.compare("type", QueryOp::Eq, r#type)
+    }
+
+    /// Require that property with given name exists.
+    pub fn has_prop(mut self, name: &str) -> Self {
+        self.filters.push(QueryFilter::Has(name.to_string()));
+        self
+    }
+
+    pub fn prop_eq<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Eq, value)
+    }
+
+    pub fn prop_ne<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Ne, value)
+    }
+
+    pub fn prop_gt<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Gt, value)
+    }
+
+    pub fn prop_lt<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Lt, value)
+    }
+
+    pub fn prop_ge<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Ge, value)
+    }
+
+    pub fn prop_le<T: ToString>(self, name: &str, value: T) -> Self {
+        self.compare(name, QueryOp::Le, value)
+    }
+
+    /// Compare builtin field or property to a value.
+    pub fn compare<T: ToString>(mut self, key: &str, op: QueryOp, value: T) -> Self {
+        self.filters.push(QueryFilter::Compare {
+            key: key.to_string(),
+            op,
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// Run the query on a map.
+    pub fn select<'a>(&self, map: &'a Map) -> Vec<Selected<'a>> {
+        match self.target {
+            QueryTarget::Layers => map
+                .layers
+                .iter()
+                .filter(|layer| self.matches_layer(layer))
+                .map(Selected::Layer)
+                .collect(),
+            QueryTarget::Objects => map
+                .objects()
+                .filter(|object| self.matches_object(object))
+                .map(Selected::Object)
+                .collect(),
+        }
+    }
+
+    fn matches_layer(&self, layer: &Layer) -> bool {
+        let layer_type = match layer.data {
+            LayerType::TileLayer(_) => "tilelayer",
+            LayerType::ImageLayer(_) => "imagelayer",
+            LayerType::ObjectGroup(_) => "objectgroup",
+            LayerType::Invalid { .. } => "invalid",
+        };
+
+        self.filters.iter().all(|filter| {
+            filter.matches(layer.properties.as_ref(), |key| match key {
+                "name" => Some(layer.name.clone()),
+                "type" => Some(layer_type.to_string()),
+                _ => None,
+            })
+        })
+    }
+
+    fn matches_object(&self, object: &Object) -> bool {
+        self.filters.iter().all(|filter| {
+            filter.matches(object.properties.as_ref(), |key| match key {
+                "id" => Some(object.id.to_string()),
+                "name" => Some(object.name.to_string()),
+                "type" => Some(object.r#type.to_string()),
+                _ => None,
+            })
+        })
+    }
+}
+
+impl QueryFilter {
+    fn matches<F>(&self, properties: Option<&Properties>, builtin: F) -> bool
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match self {
+            QueryFilter::Has(name) => properties.is_some_and(|p| p.contains_key(name)),
+            QueryFilter::Compare { key, op, value } => {
+                let ordering = if let Some(field) = builtin(key) {
+                    Some(compare_str(&field, value))
+                } else if let Some(property) = properties.and_then(|p| p.get(key)) {
+                    compare_property(property, value)
+                } else {
+                    return false;
+                };
+
+                op.matches(ordering)
+            }
+        }
+    }
+}
+
+/// Compare strings numerically if both are numbers.
+fn compare_str(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+fn compare_property(property: &Property, value: &str) -> Option<Ordering> {
+    match property {
+        Property::Int(i) => f64::from(*i).partial_cmp(&value.parse().ok()?),
+        Property::Float(f) => f64::from(*f).partial_cmp(&value.parse().ok()?),
+        Property::Bool(b) => Some(b.cmp(&value.parse().ok()?)),
+        Property::Color(c) => Some(c.to_hex().cmp(&value.to_lowercase())),
+        Property::String(s) | Property::File(s) => Some(s.as_str().cmp(value)),
+        Property::List(_) => None,
+    }
+}
+
+impl FromStr for Query {
+    type Err = TiledError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TiledError::Other(format!("Invalid query {:?}", s));
+
+        let (target, mut rest) = match s.find('[') {
+            Some(index) => (&s[..index], &s[index..]),
+            None => (s, ""),
+        };
+
+        let mut query = match target.trim() {
+            "layers" => Query::layers(),
+            "objects" => Query::objects(),
+            _ => return Err(invalid()),
+        };
+
+        while let Some(filter) = rest.trim_start().strip_prefix('[') {
+            let end = filter.find(']').ok_or_else(invalid)?;
+            let (filter, tail) = (&filter[..end], &filter[end + 1..]);
+            rest = tail;
+
+            // Two character operators must be tried first.
+            let op = [
+                (">=", QueryOp::Ge),
+                ("<=", QueryOp::Le),
+                ("!=", QueryOp::Ne),
+                ("=", QueryOp::Eq),
+                (">", QueryOp::Gt),
+                ("<", QueryOp::Lt),
+            ]
+            .iter()
+            .filter_map(|(token, op)| filter.find(token).map(|index| (index, *token, *op)))
+            .min_by_key(|(index, token, _)| (*index, std::cmp::Reverse(token.len())));
+
+            query = match op {
+                Some((index, token, op)) => {
+                    let key = filter[..index].trim();
+                    let value = filter[index + token.len()..].trim();
+                    let value = value.trim_matches(|c| c == '"' || c == '\'');
+
+                    if key.is_empty() {
+                        return Err(invalid());
+                    }
+
+                    query.compare(key, op, value)
+                }
+                None if !filter.trim().is_empty() => query.has_prop(filter.trim()),
+                None => return Err(invalid()),
+            };
+        }
+
+        if !rest.trim().is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(query)
+    }
+}
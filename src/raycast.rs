@@ -0,0 +1,75 @@
+use crate::TileLayer;
+
+/// First blocking tile found by `TileLayer::raycast`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RaycastHit {
+    /// Column of the tile in map coordinates.
+    pub x: i32,
+    /// Row of the tile in map coordinates.
+    pub y: i32,
+    /// GID of the tile, including flip flags.
+    pub gid: u32,
+    /// Point where the ray enters the tile, in tiles.
+    pub point: (f32, f32),
+}
+
+impl TileLayer {
+    /// Walk the tiles on the line from `from` to `to` and return the first
+    /// tile for which `is_blocking` returns true. Coordinates are in tiles on
+    /// an orthogonal grid, eg. `(2.5, 0.5)` is the center of the third tile
+    /// on the first row. Tiles outside the layer never block.
+    pub fn raycast<F>(&self, from: (f32, f32), to: (f32, f32), is_blocking: F) -> Option<RaycastHit>
+    where
+        F: Fn(u32) -> bool,
+    {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let (mut x, mut y) = (from.0.floor() as i32, from.1.floor() as i32);
+        let end = (to.0.floor() as i32, to.1.floor() as i32);
+
+        // Amanatides & Woo: `t_max` is the ray parameter of the next vertical
+        // or horizontal grid line, `t_delta` the distance between grid lines.
+        let axis = |origin: f32, cell: i32, delta: f32| {
+            if delta > 0.0 {
+                (1, (cell as f32 + 1.0 - origin) / delta, 1.0 / delta)
+            } else if delta < 0.0 {
+                (-1, (origin - cell as f32) / -delta, -1.0 / delta)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (step_x, mut t_max_x, t_delta_x) = axis(from.0, x, dx);
+        let (step_y, mut t_max_y, t_delta_y) = axis(from.1, y, dy);
+        let mut t = 0.0;
+
+        loop {
+            if let Some(gid) = self.get_tile_signed(x, y) {
+                if is_blocking(gid) {
+                    return Some(RaycastHit {
+                        x,
+                        y,
+                        gid,
+                        point: (from.0 + t * dx, from.1 + t * dy),
+                    });
+                }
+            }
+
+            if (x, y) == end {
+                return None;
+            }
+
+            if t_max_x < t_max_y {
+                x += step_x;
+                t = t_max_x;
+                t_max_x += t_delta_x;
+            } else {
+                y += step_y;
+                t = t_max_y;
+                t_max_y += t_delta_y;
+            }
+
+            if t > 1.0 {
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use crate::error::TiledError;
+use crate::{LayerType, Map, Object, ObjectShape, TileLayer};
+
+/// Which part of the content stays in place when a map or layer is resized.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Offset in tiles that moves the content from the old size to the new
+    /// size.
+    fn offset(self, old: (u32, u32), new: (u32, u32)) -> (i64, i64) {
+        let (horizontal, vertical) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+
+        let delta =
+            |old: u32, new: u32, position: i64| (i64::from(new) - i64::from(old)) * position / 2;
+
+        (
+            delta(old.0, new.0, horizontal),
+            delta(old.1, new.1, vertical),
+        )
+    }
+}
+
+/// What happens to objects that end up outside of a resized map.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum OutsideObjects {
+    /// Keep all objects.
+    Keep,
+    /// Remove objects whose position is outside of the map.
+    Remove,
+}
+
+impl Map {
+    /// Grow or shrink the map to `width` x `height` tiles. Tile layers that
+    /// cover the map are resized, new cells are empty (GID 0). Layers of
+    /// infinite maps, which don't match the map size, are only moved. Objects
+    /// and image layers are moved with the tiles.
+    ///
+    /// Encoded tile layers are decoded first. The map isn't changed if any of
+    /// them fails to decode.
+    pub fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+        anchor: Anchor,
+        outside: OutsideObjects,
+    ) -> Result<(), TiledError> {
+        let old = (self.width, self.height);
+        let (dx, dy) = anchor.offset(old, (width, height));
+        let shift_x = (dx * i64::from(self.tile_width)) as f32;
+        let shift_y = (dy * i64::from(self.tile_height)) as f32;
+        let bounds = (
+            (u64::from(width) * u64::from(self.tile_width)) as f32,
+            (u64::from(height) * u64::from(self.tile_height)) as f32,
+        );
+
+        for layer in &mut self.layers {
+            if let LayerType::TileLayer(tile_layer) = &mut layer.data {
+                tile_layer.decode()?;
+            }
+        }
+
+        for layer in &mut self.layers {
+            match &mut layer.data {
+                LayerType::TileLayer(tile_layer) => {
+                    let covers_map = (tile_layer.start_x, tile_layer.start_y) == (0, 0)
+                        && (tile_layer.width, tile_layer.height) == old;
+                    if covers_map {
+                        tile_layer.shift_resize((0, 0), width, height, dx, dy);
+                    } else {
+                        tile_layer.start_x = shift_start(tile_layer.start_x, dx);
+                        tile_layer.start_y = shift_start(tile_layer.start_y, dy);
+                    }
+                }
+                LayerType::ObjectGroup(group) => {
+                    for object in &mut group.objects {
+                        object.x += shift_x;
+                        object.y += shift_y;
+                    }
+
+                    if outside == OutsideObjects::Remove {
+                        group.objects.retain(|object| {
+                            (0.0..bounds.0).contains(&object.x)
+                                && (0.0..bounds.1).contains(&object.y)
+                        });
+                    }
+                }
+                LayerType::ImageLayer(image_layer) => {
+                    image_layer.offset_x += shift_x;
+                    image_layer.offset_y += shift_y;
+                }
+                LayerType::Invalid { .. } => {}
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        // Object indexes contain positions of removed objects.
+        self.object_index = None;
+
+        Ok(())
+    }
+
+    /// Scale positions and sizes of all objects, eg. by 2 when migrating
+    /// from 16px to 32px tiles. Tile sizes of the map and tilesets are not
+    /// changed.
+    pub fn scale_objects(&mut self, factor: f32) {
+        for layer in &mut self.layers {
+            if let LayerType::ObjectGroup(group) = &mut layer.data {
+                for object in &mut group.objects {
+                    object.scale(factor);
+                }
+            }
+        }
+    }
+}
+
+/// Move the start of an infinite layer, saturating at the coordinate range.
+fn shift_start(start: i32, delta: i64) -> i32 {
+    (i64::from(start) + delta).clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+impl Object {
+    fn scale(&mut self, factor: f32) {
+        self.x *= factor;
+        self.y *= factor;
+
+        match &mut self.shape {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => {
+                *width *= factor;
+                *height *= factor;
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                for point in points {
+                    point.x *= factor;
+                    point.y *= factor;
+                }
+            }
+            ObjectShape::Point | ObjectShape::Unknown(_) | ObjectShape::Invalid { .. } => {}
+        }
+    }
+}
+
+impl TileLayer {
+    /// Grow or shrink the layer to `width` x `height` tiles. New cells are
+    /// empty (GID 0) and tiles outside of the new size are dropped.
+    ///
+    /// The layer is decoded first if it's still encoded.
+    pub fn resize(&mut self, width: u32, height: u32, anchor: Anchor) -> Result<(), TiledError> {
+        self.decode()?;
+
+        let (dx, dy) = anchor.offset((self.width, self.height), (width, height));
+        self.shift_resize((self.start_x, self.start_y), width, height, dx, dy);
+        Ok(())
+    }
+
+    /// Resize decoded layer to `width` x `height` tiles starting at `start`
+    /// and move the old tiles by `dx`, `dy`.
+    fn shift_resize(&mut self, start: (i32, i32), width: u32, height: u32, dx: i64, dy: i64) {
+        let mut tiles = vec![0; width as usize * height as usize];
+        let (old_x, old_y) = (i64::from(self.start_x), i64::from(self.start_y));
+        let (new_x, new_y) = (i64::from(start.0), i64::from(start.1));
+
+        for (y, row) in self.tiles.chunks(self.width.max(1) as usize).enumerate() {
+            let target_y = old_y + y as i64 + dy - new_y;
+            if target_y < 0 || target_y >= i64::from(height) {
+                continue;
+            }
+
+            for (x, tile) in row.iter().enumerate() {
+                let target_x = old_x + x as i64 + dx - new_x;
+                if target_x >= 0 && target_x < i64::from(width) {
+                    tiles[(target_y * i64::from(width) + target_x) as usize] = *tile;
+                }
+            }
+        }
+
+        self.start_x = start.0;
+        self.start_y = start.1;
+        self.width = width;
+        self.height = height;
+        self.tiles = Arc::new(tiles);
+        self.stats.0.take();
+    }
+}
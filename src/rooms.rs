@@ -0,0 +1,92 @@
+use crate::{LayerType, Map, ObjectId};
+
+/// Room-sized part of a map, see `Map::rooms`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Room {
+    /// Column of the room in the room grid.
+    pub column: u32,
+    /// Row of the room in the room grid.
+    pub row: u32,
+    /// First tile column of the room.
+    pub x: u32,
+    /// First tile row of the room.
+    pub y: u32,
+    /// Width in tiles. Rooms on the right edge may be narrower.
+    pub width: u32,
+    /// Height in tiles. Rooms on the bottom edge may be lower.
+    pub height: u32,
+    /// Objects whose position is in the room, in layer order.
+    pub objects: Vec<ObjectId>,
+    /// Whether any tile layer has a non-empty tile in the room.
+    pub has_tiles: bool,
+}
+
+impl Map {
+    /// Partition the map into rooms of `room_width` x `room_height` tiles,
+    /// eg. for screen-by-screen cameras and minimaps. Rooms are listed row
+    /// by row. Objects belong to the room their position (`x`, `y`) is in;
+    /// objects outside the map are not in any room. Hidden layers are
+    /// included and layers that are still encoded are ignored.
+    ///
+    /// # Panics
+    ///
+    /// If the room width or height is zero.
+    pub fn rooms(&self, room_width: u32, room_height: u32) -> Vec<Room> {
+        assert!(room_width > 0 && room_height > 0, "empty room size");
+
+        let columns = self.width.div_ceil(room_width);
+        let rows = self.height.div_ceil(room_height);
+
+        let mut rooms: Vec<Room> = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let (x, y) = (column * room_width, row * room_height);
+                Room {
+                    column,
+                    row,
+                    x,
+                    y,
+                    width: room_width.min(self.width - x),
+                    height: room_height.min(self.height - y),
+                    objects: Vec::new(),
+                    has_tiles: false,
+                }
+            })
+            .collect();
+
+        for layer in &self.layers {
+            match &layer.data {
+                LayerType::TileLayer(tiles) if !tiles.is_encoded() => {
+                    for room in rooms.iter_mut().filter(|room| !room.has_tiles) {
+                        room.has_tiles = (room.y..room.y + room.height).any(|y| {
+                            (room.x..room.x + room.width).any(|x| {
+                                tiles.get_tile_signed(x as i32, y as i32).unwrap_or(0) != 0
+                            })
+                        });
+                    }
+                }
+                LayerType::ObjectGroup(group) => {
+                    let tile_width = self.tile_width.max(1) as f32;
+                    let tile_height = self.tile_height.max(1) as f32;
+
+                    for object in &group.objects {
+                        let x = (object.x / tile_width).floor();
+                        let y = (object.y / tile_height).floor();
+                        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+                            continue;
+                        }
+
+                        let column = x as u32 / room_width;
+                        let row = y as u32 / room_height;
+                        rooms[(row * columns + column) as usize]
+                            .objects
+                            .push(object.id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        rooms
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{LayerPatch, LayerType, Map, ObjectId};
+
+/// Runtime changes to a map, eg. for save games. The state is kept apart from
+/// the map, so it can be serialized on its own and applied again to a
+/// reloaded map. Layers are identified by name.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MapState {
+    #[serde(default)]
+    layers: BTreeMap<String, LayerState>,
+    #[serde(default)]
+    removed_objects: BTreeSet<ObjectId>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+struct LayerState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    visible: Option<bool>,
+    /// Serialized as `[x, y, gid]` triples, JSON keys must be strings.
+    #[serde(default, with = "tile_list")]
+    tiles: BTreeMap<(u32, u32), u32>,
+}
+
+mod tile_list {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(tiles: &BTreeMap<(u32, u32), u32>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let list: Vec<[u32; 3]> = tiles.iter().map(|((x, y), gid)| [*x, *y, *gid]).collect();
+        list.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<(u32, u32), u32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let list: Vec<[u32; 3]> = Deserialize::deserialize(deserializer)?;
+        Ok(list.into_iter().map(|[x, y, gid]| ((x, y), gid)).collect())
+    }
+}
+
+impl MapState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty() && self.removed_objects.is_empty()
+    }
+
+    /// Record a changed tile of a tile layer.
+    pub fn set_tile(&mut self, layer: &str, x: u32, y: u32, gid: u32) {
+        self.layer_mut(layer).tiles.insert((x, y), gid);
+    }
+
+    /// Record all changes of a patch, see `TileLayer::set_tiles`.
+    pub fn add_patch(&mut self, layer: &str, patch: &LayerPatch) {
+        let tiles = &mut self.layer_mut(layer).tiles;
+        for change in &patch.changes {
+            tiles.insert((change.x, change.y), change.new_gid);
+        }
+    }
+
+    /// Changed tile of a tile layer.
+    pub fn tile(&self, layer: &str, x: u32, y: u32) -> Option<u32> {
+        self.layers.get(layer)?.tiles.get(&(x, y)).copied()
+    }
+
+    /// Record a removed object.
+    pub fn remove_object(&mut self, id: ObjectId) {
+        self.removed_objects.insert(id);
+    }
+
+    pub fn is_object_removed(&self, id: ObjectId) -> bool {
+        self.removed_objects.contains(&id)
+    }
+
+    /// Record changed visibility of a layer.
+    pub fn set_layer_visible(&mut self, layer: &str, visible: bool) {
+        self.layer_mut(layer).visible = Some(visible);
+    }
+
+    /// Changed visibility of a layer.
+    pub fn layer_visible(&self, layer: &str) -> Option<bool> {
+        self.layers.get(layer)?.visible
+    }
+
+    fn layer_mut(&mut self, layer: &str) -> &mut LayerState {
+        self.layers.entry(layer.to_string()).or_default()
+    }
+
+    /// Apply the changes to a map. Changes are applied to the first layer
+    /// with the recorded name. Returns the number of changes that couldn't
+    /// be applied because the layer, tile or object doesn't exist.
+    pub fn apply(&self, map: &mut Map) -> usize {
+        let mut skipped = 0;
+
+        for (name, state) in &self.layers {
+            let layer = match map.layers.iter_mut().find(|layer| layer.name == *name) {
+                Some(layer) => layer,
+                None => {
+                    skipped += state.tiles.len() + state.visible.map_or(0, |_| 1);
+                    continue;
+                }
+            };
+
+            if let Some(visible) = state.visible {
+                layer.visible = visible;
+            }
+
+            let tile_layer = match &mut layer.data {
+                LayerType::TileLayer(tile_layer) => tile_layer,
+                _ => {
+                    skipped += state.tiles.len();
+                    continue;
+                }
+            };
+
+            if tile_layer.decode().is_err() {
+                skipped += state.tiles.len();
+                continue;
+            }
+
+            for ((x, y), gid) in &state.tiles {
+                if *x < tile_layer.width() && *y < tile_layer.height() {
+                    tile_layer.set_tile(*x, *y, *gid);
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+
+        let mut removed = BTreeSet::new();
+        for layer in &mut map.layers {
+            if let LayerType::ObjectGroup(group) = &mut layer.data {
+                group.objects.retain(|object| {
+                    let remove = self.removed_objects.contains(&object.id);
+                    if remove {
+                        removed.insert(object.id);
+                    }
+                    !remove
+                });
+            }
+        }
+
+        if !removed.is_empty() {
+            // Object indexes contain positions of removed objects.
+            map.object_index = None;
+        }
+
+        skipped + self.removed_objects.difference(&removed).count()
+    }
+}
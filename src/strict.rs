@@ -0,0 +1,345 @@
+//! Check for fields that aren't part of the Tiled format, see
+//! `ParseOptions::strict`.
+
+use serde_json::Value;
+
+use crate::error::TiledError;
+
+const MAP: &[&str] = &[
+    "backgroundcolor",
+    "class",
+    "compressionlevel",
+    "editorsettings",
+    "height",
+    "hexsidelength",
+    "infinite",
+    "layers",
+    "nextlayerid",
+    "nextobjectid",
+    "orientation",
+    "parallaxoriginx",
+    "parallaxoriginy",
+    "properties",
+    "renderorder",
+    "staggeraxis",
+    "staggerindex",
+    "tiledversion",
+    "tileheight",
+    "tilesets",
+    "tilewidth",
+    "type",
+    "version",
+    "width",
+];
+
+const LAYER: &[&str] = &[
+    "blendmode",
+    "chunks",
+    "class",
+    "color",
+    "compression",
+    "data",
+    "draworder",
+    "encoding",
+    "height",
+    "id",
+    "image",
+    "imageheight",
+    "imagewidth",
+    "layers",
+    "locked",
+    "name",
+    "objects",
+    "offsetx",
+    "offsety",
+    "opacity",
+    "parallaxx",
+    "parallaxy",
+    "properties",
+    "repeatx",
+    "repeaty",
+    "startx",
+    "starty",
+    "tintcolor",
+    "transparentcolor",
+    "type",
+    "visible",
+    "width",
+    "x",
+    "y",
+];
+
+const CHUNK: &[&str] = &["data", "height", "width", "x", "y"];
+
+const OBJECT: &[&str] = &[
+    "class",
+    "ellipse",
+    "gid",
+    "height",
+    "id",
+    "name",
+    "point",
+    "polygon",
+    "polyline",
+    "properties",
+    "rotation",
+    "template",
+    "text",
+    "type",
+    "visible",
+    "width",
+    "x",
+    "y",
+];
+
+const TEXT: &[&str] = &[
+    "bold",
+    "color",
+    "fontfamily",
+    "halign",
+    "italic",
+    "kerning",
+    "pixelsize",
+    "strikeout",
+    "text",
+    "underline",
+    "valign",
+    "wrap",
+];
+
+const POINT: &[&str] = &["x", "y"];
+
+const TILESET: &[&str] = &[
+    "backgroundcolor",
+    "class",
+    "columns",
+    "fillmode",
+    "firstgid",
+    "grid",
+    "image",
+    "imageheight",
+    "imagewidth",
+    "margin",
+    "name",
+    "objectalignment",
+    "properties",
+    "source",
+    "spacing",
+    "terrains",
+    "tilecount",
+    "tiledversion",
+    "tileheight",
+    "tileoffset",
+    "tilerendersize",
+    "tiles",
+    "tilewidth",
+    "transformations",
+    "transparentcolor",
+    "type",
+    "version",
+    "wangsets",
+];
+
+const GRID: &[&str] = &["height", "orientation", "width"];
+
+const TRANSFORMATIONS: &[&str] = &["hflip", "preferuntransformed", "rotate", "vflip"];
+
+const TILE: &[&str] = &[
+    "animation",
+    "class",
+    "height",
+    "id",
+    "image",
+    "imageheight",
+    "imagewidth",
+    "objectgroup",
+    "probability",
+    "properties",
+    "terrain",
+    "type",
+    "width",
+    "x",
+    "y",
+];
+
+const FRAME: &[&str] = &["duration", "tileid"];
+
+const PROPERTY: &[&str] = &["name", "propertytype", "type", "value"];
+
+const TERRAIN: &[&str] = &["name", "properties", "tile"];
+
+const WANG_SET: &[&str] = &[
+    "class",
+    "colors",
+    "cornercolors",
+    "edgecolors",
+    "name",
+    "properties",
+    "tile",
+    "type",
+    "wangtiles",
+];
+
+const WANG_COLOR: &[&str] = &[
+    "class",
+    "color",
+    "name",
+    "probability",
+    "properties",
+    "tile",
+];
+
+const WANG_TILE: &[&str] = &["dflip", "hflip", "tileid", "vflip", "wangid"];
+
+/// Fail on the first field of `map` that Tiled doesn't write, with the path
+/// of the field.
+pub(crate) fn check_unknown_fields(map: &Value) -> Result<(), TiledError> {
+    let mut path = String::new();
+    check_map(map, &mut path)
+}
+
+/// Check keys of an object and call `nested` for values of known keys.
+fn check_object<F>(
+    value: &Value,
+    known: &[&str],
+    path: &mut String,
+    mut nested: F,
+) -> Result<(), TiledError>
+where
+    F: FnMut(&str, &Value, &mut String) -> Result<(), TiledError>,
+{
+    let object = match value.as_object() {
+        Some(object) => object,
+        // Wrong types are reported when deserializing.
+        None => return Ok(()),
+    };
+
+    for (key, value) in object {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(key);
+
+        if !known.contains(&key.as_str()) {
+            return Err(TiledError::Path {
+                path: path.clone(),
+                source: Box::new(TiledError::UnknownField(key.clone())),
+            });
+        }
+        nested(key, value, path)?;
+
+        path.truncate(len);
+    }
+
+    Ok(())
+}
+
+/// Call `check` for each element of an array.
+fn check_array<F>(value: &Value, path: &mut String, check: F) -> Result<(), TiledError>
+where
+    F: Fn(&Value, &mut String) -> Result<(), TiledError>,
+{
+    for (index, item) in value.as_array().into_iter().flatten().enumerate() {
+        let len = path.len();
+        path.push_str(&format!("[{}]", index));
+        check(item, path)?;
+        path.truncate(len);
+    }
+
+    Ok(())
+}
+
+fn check_fields(
+    known: &'static [&'static str],
+) -> impl Fn(&Value, &mut String) -> Result<(), TiledError> {
+    move |value, path| check_object(value, known, path, |_, _, _| Ok(()))
+}
+
+/// Properties must have fields Tiled writes and a value of a Tiled type.
+fn check_property(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_fields(PROPERTY)(value, path)?;
+
+    if value.get("value").is_some_and(Value::is_array) {
+        let name = value["name"].as_str().unwrap_or_default();
+        return Err(TiledError::Path {
+            path: path.clone(),
+            source: Box::new(TiledError::NonStandardProperty(name.to_string())),
+        });
+    }
+
+    Ok(())
+}
+
+fn check_map(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, MAP, path, |key, value, path| match key {
+        "layers" => check_array(value, path, check_layer),
+        "tilesets" => check_array(value, path, check_tileset),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_layer(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, LAYER, path, |key, value, path| match key {
+        "chunks" => check_array(value, path, check_fields(CHUNK)),
+        "layers" => check_array(value, path, check_layer),
+        "objects" => check_array(value, path, check_object_fields),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_object_fields(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, OBJECT, path, |key, value, path| match key {
+        "polygon" | "polyline" => check_array(value, path, check_fields(POINT)),
+        "text" => check_fields(TEXT)(value, path),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_tileset(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, TILESET, path, |key, value, path| match key {
+        "grid" => check_fields(GRID)(value, path),
+        "tileoffset" => check_fields(POINT)(value, path),
+        "transformations" => check_fields(TRANSFORMATIONS)(value, path),
+        "tiles" => check_array(value, path, check_tile),
+        "terrains" => check_array(value, path, check_terrain),
+        "wangsets" => check_array(value, path, check_wang_set),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_tile(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, TILE, path, |key, value, path| match key {
+        "animation" => check_array(value, path, check_fields(FRAME)),
+        "objectgroup" => check_layer(value, path),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_terrain(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, TERRAIN, path, |key, value, path| match key {
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_wang_set(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, WANG_SET, path, |key, value, path| match key {
+        "colors" | "cornercolors" | "edgecolors" => check_array(value, path, check_wang_color),
+        "wangtiles" => check_array(value, path, check_fields(WANG_TILE)),
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
+
+fn check_wang_color(value: &Value, path: &mut String) -> Result<(), TiledError> {
+    check_object(value, WANG_COLOR, path, |key, value, path| match key {
+        "properties" => check_array(value, path, check_property),
+        _ => Ok(()),
+    })
+}
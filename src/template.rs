@@ -0,0 +1,159 @@
+//! Export of objects as Tiled object templates.
+
+use std::io::Write;
+
+use serde_json::{json, Map as JsonObject, Value};
+
+use crate::error::TiledError;
+use crate::properties::properties_to_json;
+use crate::tileset::GID_FLAGS;
+use crate::writer::{write_json, WriteOptions};
+use crate::{Object, ObjectShape, Point};
+
+/// Object template, the contents of a JSON template file (`.tj`, or `.tx`
+/// with Tiled set to write JSON).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ObjectTemplate {
+    pub object: Object,
+    /// Tileset of tile objects as `(first_gid, source)`. The GID of the
+    /// object is relative to this first GID.
+    pub tileset: Option<(u32, String)>,
+}
+
+impl Object {
+    /// Template of the object. Position and ID are not part of templates,
+    /// tile objects also need a tileset, see `ObjectTemplate::tileset`.
+    pub fn to_template(&self) -> ObjectTemplate {
+        ObjectTemplate {
+            object: self.clone(),
+            tileset: None,
+        }
+    }
+}
+
+impl ObjectTemplate {
+    /// Set the tileset of a tile object. `first_gid` is the first GID of the
+    /// tileset in the map the object came from, the GID of the object is
+    /// written relative to it.
+    pub fn tileset(mut self, first_gid: u32, source: &str) -> Self {
+        self.tileset = Some((first_gid, source.to_string()));
+        self
+    }
+
+    /// Template as Tiled JSON.
+    pub fn to_json(&self) -> Result<Value, TiledError> {
+        let object = &self.object;
+        let mut fields = object_fields(object)?;
+
+        let mut template = JsonObject::new();
+        template.insert("type".to_string(), json!("template"));
+
+        if let Some(gid) = object.gid {
+            let (first_gid, source) = self.tileset.as_ref().ok_or_else(|| {
+                TiledError::Other("Template of a tile object needs a tileset".to_string())
+            })?;
+
+            // Templates refer to their own tileset with first GID 1.
+            let flags = gid & GID_FLAGS;
+            let local = (gid & !GID_FLAGS)
+                .checked_sub(*first_gid)
+                .ok_or_else(|| TiledError::Other(format!("GID {} isn't in the tileset", gid)))?;
+
+            fields.insert("gid".to_string(), json!((local + 1) | flags));
+            template.insert(
+                "tileset".to_string(),
+                json!({ "firstgid": 1, "source": source }),
+            );
+        }
+
+        template.insert("object".to_string(), Value::Object(fields));
+        Ok(Value::Object(template))
+    }
+
+    /// Write the template as Tiled JSON.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), TiledError> {
+        self.write_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the template as Tiled JSON with given options.
+    pub fn write_with_options<W: Write>(
+        &self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> Result<(), TiledError> {
+        write_json(&self.to_json()?, writer, options)
+    }
+}
+
+/// Fields of an object as Tiled JSON, except ID, position and GID.
+pub(crate) fn object_fields(object: &Object) -> Result<JsonObject<String, Value>, TiledError> {
+    let mut fields = JsonObject::new();
+
+    fields.insert("name".to_string(), json!(object.name));
+    fields.insert("type".to_string(), json!(object.r#type));
+    fields.insert("rotation".to_string(), json!(object.rotation));
+    fields.insert("visible".to_string(), json!(object.visible));
+
+    let points = |points: &[Point]| -> Value {
+        points
+            .iter()
+            .map(|point| json!({ "x": point.x, "y": point.y }))
+            .collect()
+    };
+
+    let (width, height) = match &object.shape {
+        ObjectShape::Point => {
+            fields.insert("point".to_string(), json!(true));
+            (0.0, 0.0)
+        }
+        ObjectShape::Rect { width, height } => (*width, *height),
+        ObjectShape::Ellipse { width, height } => {
+            fields.insert("ellipse".to_string(), json!(true));
+            (*width, *height)
+        }
+        ObjectShape::Polyline { points: line } => {
+            fields.insert("polyline".to_string(), points(line));
+            (0.0, 0.0)
+        }
+        ObjectShape::Polygon { points: polygon } => {
+            fields.insert("polygon".to_string(), points(polygon));
+            (0.0, 0.0)
+        }
+        ObjectShape::Text {
+            text,
+            width,
+            height,
+        } => {
+            let mut value = Value::Object(text.other.clone());
+            value["text"] = json!(text.text);
+            value["wrap"] = json!(text.wrap);
+            if let Some(font_family) = &text.font_family {
+                value["fontfamily"] = json!(font_family);
+            }
+            if let Some(pixel_size) = text.pixel_size {
+                value["pixelsize"] = json!(pixel_size);
+            }
+            fields.insert("text".to_string(), value);
+            (*width, *height)
+        }
+        ObjectShape::Unknown(value) => {
+            if let Value::Object(shape) = value {
+                fields.extend(shape.clone());
+            }
+            (0.0, 0.0)
+        }
+        ObjectShape::Invalid { .. } => {
+            return Err(TiledError::Other(
+                "Can't write an invalid object".to_string(),
+            ))
+        }
+    };
+    fields.entry("width").or_insert_with(|| json!(width));
+    fields.entry("height").or_insert_with(|| json!(height));
+
+    if let Some(properties) = &object.properties {
+        fields.insert("properties".to_string(), properties_to_json(properties));
+    }
+
+    Ok(fields)
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::TiledError;
+use crate::Map;
+
+/// On-disk cache of map previews keyed by `Map::content_hash`, for tools that
+/// list many maps. The crate doesn't render maps itself: previews are made by
+/// a callback and stored as the bytes it returns, eg. an encoded PNG.
+#[derive(Debug, Clone)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+}
+
+impl ThumbnailCache {
+    /// Cache in `dir`. The directory is created when the first preview is
+    /// stored.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        ThumbnailCache { dir: dir.into() }
+    }
+
+    /// File of the preview of `map` at `size`. Maps with the same contents
+    /// share previews.
+    pub fn path(&self, map: &Map, size: u32) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}-{}.thumb", map.content_hash(), size))
+    }
+
+    /// Cached preview of `map` at `size`.
+    pub fn get(&self, map: &Map, size: u32) -> Option<Vec<u8>> {
+        fs::read(self.path(map, size)).ok()
+    }
+
+    /// Cached preview of `map` at `size`, or the output of `render(map, size)`
+    /// which is then stored in the cache.
+    pub fn get_or_render<F>(&self, map: &Map, size: u32, render: F) -> Result<Vec<u8>, TiledError>
+    where
+        F: FnOnce(&Map, u32) -> Result<Vec<u8>, TiledError>,
+    {
+        let path = self.path(map, size);
+        if let Ok(bytes) = fs::read(&path) {
+            return Ok(bytes);
+        }
+
+        let bytes = render(map, size)?;
+        self.store(&path, &bytes)
+            .map_err(|err| TiledError::Other(format!("{:?}", err)))?;
+
+        Ok(bytes)
+    }
+
+    /// Write through a temporary file, so other processes never read a
+    /// partially written preview.
+    fn store(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temporary, bytes)?;
+        fs::rename(&temporary, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+    }
+}
@@ -1,15 +1,172 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 
-#[derive(Debug, PartialEq, Clone, Deserialize)]
+use crate::properties::deserialize_properties;
+use crate::wang::{Terrain, WangSet};
+use crate::{Layer, Properties, Property};
+
+/// Bits of a GID used for flipping and rotation flags.
+pub(crate) const GID_FLAGS: u32 = 0xf000_0000;
+
+/// GID flag for tiles flipped horizontally.
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+/// GID flag for tiles flipped vertically.
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// GID flag for tiles flipped diagonally (x and y axis swapped).
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// Affine transform `[[a, b, tx], [c, d, ty]]` that maps pixel coordinates of
+/// a `tile_width` x `tile_height` tile image to the cell it's drawn in, taking
+/// flip flags of the GID into account:
+///
+/// ```text
+/// x' = a * x + b * y + tx
+/// y' = c * x + d * y + ty
+/// ```
+///
+/// Like in Tiled, the diagonal flip is applied first and then the horizontal
+/// and vertical flips, so eg. diagonal + horizontal is a 90° clockwise
+/// rotation. Diagonally flipped tiles cover `tile_height` x `tile_width`
+/// pixels.
+pub fn gid_transform(gid: u32, tile_width: f32, tile_height: f32) -> [[f32; 3]; 2] {
+	let mut matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+	let (mut width, mut height) = (tile_width, tile_height);
+
+	if gid & FLIPPED_DIAGONALLY != 0 {
+		matrix = [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+		std::mem::swap(&mut width, &mut height);
+	}
+
+	if gid & FLIPPED_HORIZONTALLY != 0 {
+		let row = &mut matrix[0];
+		*row = [-row[0], -row[1], width - row[2]];
+	}
+
+	if gid & FLIPPED_VERTICALLY != 0 {
+		let row = &mut matrix[1];
+		*row = [-row[0], -row[1], height - row[2]];
+	}
+
+	matrix
+}
+
+/// A frame of tile animation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
 pub struct Frame {
-	tile_id: u32,
-	duration: u32,
+	/// Local ID of the tile shown in this frame
+	#[serde(rename = "tileid")]
+	pub tile_id: u32,
+	/// How long the frame is shown (milliseconds)
+	pub duration: u32,
+}
+
+impl Frame {
+	/// Frame duration in milliseconds.
+	pub fn duration_ms(&self) -> u32 {
+		self.duration
+	}
+
+	/// How long the frame is shown.
+	pub fn duration(&self) -> Duration {
+		Duration::from_millis(u64::from(self.duration))
+	}
+}
+
+/// Total length in milliseconds of frames with given durations, saturating
+/// at `u32::MAX`.
+fn total_duration_ms<I: Iterator<Item = u32>>(durations: I) -> u32 {
+	durations.fold(0, u32::saturating_add)
+}
+
+/// Index of the frame shown `time_ms` milliseconds after a looping animation
+/// with given frame durations started.
+fn frame_index_at<I>(durations: I, time_ms: u128) -> Option<usize>
+where
+	I: Iterator<Item = u32> + Clone,
+{
+	let total = u128::from(total_duration_ms(durations.clone()));
+	if total == 0 {
+		return None;
+	}
+
+	let mut time = time_ms % total;
+	for (index, duration) in durations.enumerate() {
+		if time < u128::from(duration) {
+			return Some(index);
+		}
+		time -= u128::from(duration);
+	}
+
+	None
+}
+
+/// Animation of a tile with frames resolved to global tile IDs, see
+/// `Map::animations`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Animation {
+	/// Global ID of the animated tile.
+	pub gid: u32,
+	/// Global tile IDs and durations (milliseconds) of the frames.
+	pub frames: Vec<(u32, u32)>,
+}
+
+impl Animation {
+	/// Total length of the animation in milliseconds.
+	pub fn duration_ms(&self) -> u32 {
+		total_duration_ms(self.frames.iter().map(|(_, duration)| *duration))
+	}
+
+	/// Total length of the animation.
+	pub fn total_duration(&self) -> Duration {
+		Duration::from_millis(u64::from(self.duration_ms()))
+	}
+
+	/// GID of the frame shown `time` after the animation started. The
+	/// animation loops.
+	pub fn frame_at(&self, time: Duration) -> Option<u32> {
+		let durations = self.frames.iter().map(|(_, duration)| *duration);
+		let index = frame_index_at(durations, time.as_millis())?;
+		Some(self.frames[index].0)
+	}
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub struct Tile {
 	/// Local ID of the tile
 	pub id: u32,
+	/// Custom properties of the tile. Override properties of the tileset.
+	#[serde(default, deserialize_with = "deserialize_properties")]
+	pub properties: Option<Properties>,
+	/// Frames of tile animation
+	pub animation: Option<Vec<Frame>>,
+	/// Legacy terrain indices of the top-left, top-right, bottom-left and
+	/// bottom-right corners, -1 for none. See `Tileset::upgrade_terrains`.
+	pub terrain: Option<[i32; 4]>,
+	/// Collision shapes of the tile, an object layer.
+	#[serde(rename = "objectgroup")]
+	pub object_group: Option<Layer>,
+}
+
+impl Tile {
+	/// Total length of the animation in milliseconds. Zero if the tile isn't
+	/// animated.
+	pub fn animation_duration_ms(&self) -> u32 {
+		total_duration_ms(self.animation.iter().flatten().map(|frame| frame.duration))
+	}
+
+	/// Frame shown at `time_ms` milliseconds after the animation started.
+	/// The animation loops.
+	pub fn frame_at(&self, time_ms: u64) -> Option<&Frame> {
+		self.frame_at_time(Duration::from_millis(time_ms))
+	}
+
+	/// Frame shown `time` after the animation started. The animation loops.
+	pub fn frame_at_time(&self, time: Duration) -> Option<&Frame> {
+		let frames = self.animation.as_deref()?;
+		let index = frame_index_at(frames.iter().map(|frame| frame.duration), time.as_millis())?;
+		frames.get(index)
+	}
 }
 
 /// A tileset, usually the tilesheet image.
@@ -32,7 +189,111 @@ pub struct Tileset {
 	pub margin: u32,
 	/// Image used for tiles in this set
 	pub image: String,
+	/// Number of tiles in this set
+	#[serde(rename = "tilecount")]
+	pub tile_count: Option<u32>,
 	/// Tileset can associate information with each tile, like its image path
 	/// or terrain type.
 	pub tiles: Option<Vec<Tile>>,
+	/// Custom properties shared by all tiles of the set.
+	#[serde(default, deserialize_with = "deserialize_properties")]
+	pub properties: Option<Properties>,
+	/// Legacy terrain types, replaced by wang sets in Tiled 1.5.
+	pub terrains: Option<Vec<Terrain>>,
+	/// Wang sets for auto-tiling, replace `terrains` since Tiled 1.5.
+	#[serde(rename = "wangsets")]
+	pub wang_sets: Option<Vec<WangSet>>,
+	/// Path of the external tileset file relative to the map, see
+	/// `parse_file_with_externals`. Paths in the tileset are relative to it.
+	#[serde(default)]
+	pub source: Option<String>,
+}
+
+impl Tileset {
+	/// Whether the global tile ID belongs to this tileset. Flip flags are
+	/// ignored. Tilesets without tile count contain all GIDs from first GID.
+	pub fn contains_gid(&self, gid: u32) -> bool {
+		let gid = gid & !GID_FLAGS;
+		gid >= self.first_gid
+			&& self
+				.tile_count
+				.is_none_or(|count| gid - self.first_gid < count)
+	}
+
+	/// Tile with per-tile information for global tile ID.
+	pub fn get_tile(&self, gid: u32) -> Option<&Tile> {
+		if !self.contains_gid(gid) {
+			return None;
+		}
+
+		let id = (gid & !GID_FLAGS) - self.first_gid;
+		self.tiles.as_ref()?.iter().find(|tile| tile.id == id)
+	}
+
+	/// Resolve property of a global tile ID. Properties of the tile override
+	/// properties of the tileset.
+	pub fn property(&self, gid: u32, name: &str) -> Option<&Property> {
+		if !self.contains_gid(gid) {
+			return None;
+		}
+
+		self.get_tile(gid)
+			.and_then(|tile| tile.properties.as_ref())
+			.and_then(|properties| properties.get(name))
+			.or_else(|| self.properties.as_ref()?.get(name))
+	}
+
+	/// Number of tile columns in an image of `image_width` pixels.
+	pub fn columns(&self, image_width: u32) -> u32 {
+		let stride = self.tile_width.saturating_add(self.spacing);
+		if stride == 0 {
+			return 0;
+		}
+
+		image_width
+			.saturating_sub(self.margin.saturating_mul(2))
+			.saturating_add(self.spacing)
+			/ stride
+	}
+
+	/// Source rectangle `[x, y, width, height]` of a tile in an image of
+	/// `image_size` (width, height) pixels.
+	pub fn tile_rect(&self, local_id: u32, image_size: (u32, u32)) -> [u32; 4] {
+		let columns = self.columns(image_size.0).max(1);
+		let (column, row) = (local_id % columns, local_id / columns);
+
+		// Saturates instead of overflowing for broken tile sizes.
+		let offset = |index: u32, size: u32| {
+			index
+				.saturating_mul(size.saturating_add(self.spacing))
+				.saturating_add(self.margin)
+		};
+
+		[
+			offset(column, self.tile_width),
+			offset(row, self.tile_height),
+			self.tile_width,
+			self.tile_height,
+		]
+	}
+
+	/// Texture coordinates `[u0, v0, u1, v1]` of a tile in an image of
+	/// `image_size` pixels, see `tile_rect`.
+	pub fn tile_uv(&self, local_id: u32, image_size: (u32, u32)) -> [f32; 4] {
+		self.tile_uv_inset(local_id, image_size, 0.0)
+	}
+
+	/// Texture coordinates moved `inset` texels inside the tile. An inset of
+	/// 0.5 keeps linear filtering from bleeding in neighboring tiles.
+	pub fn tile_uv_inset(&self, local_id: u32, image_size: (u32, u32), inset: f32) -> [f32; 4] {
+		let [x, y, width, height] = self.tile_rect(local_id, image_size);
+		let (image_width, image_height) = (image_size.0.max(1) as f32, image_size.1.max(1) as f32);
+
+		[
+			(x as f32 + inset) / image_width,
+			(y as f32 + inset) / image_height,
+			(x as f32 + width as f32 - inset) / image_width,
+			(y as f32 + height as f32 - inset) / image_height,
+		]
+	}
 }
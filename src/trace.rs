@@ -0,0 +1,44 @@
+//! Spans and events of parse phases, enabled with the `tracing` feature.
+//! Without the feature the phases are run as is.
+
+use std::path::Path;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// Run a parse phase, eg. deserializing the map or decoding a layer, in a
+/// span and report how long it took. `size` is the size of the input in
+/// bytes, or in tiles for layers, and 0 when it isn't known.
+#[cfg(feature = "tracing")]
+pub(crate) fn phase<T>(name: &'static str, size: usize, f: impl FnOnce() -> T) -> T {
+    let span = tracing::debug_span!("tiled_json", phase = name, size);
+    let _entered = span.enter();
+
+    let start = Instant::now();
+    let result = f();
+    tracing::debug!(elapsed_us = start.elapsed().as_micros() as u64, "done");
+
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn phase<T>(_name: &'static str, _size: usize, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// Run loading of an external tileset or template in a span with its path.
+#[cfg(feature = "tracing")]
+pub(crate) fn load<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    let span = tracing::debug_span!("tiled_json", phase = "external", path = %path.display());
+    let _entered = span.enter();
+
+    let start = Instant::now();
+    let result = f();
+    tracing::debug!(elapsed_us = start.elapsed().as_micros() as u64, "done");
+
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn load<T>(_path: &Path, f: impl FnOnce() -> T) -> T {
+    f()
+}
@@ -0,0 +1,81 @@
+use crate::measure::signed_area;
+use crate::{ObjectShape, Point};
+
+impl ObjectShape {
+    /// Split a polygon into triangles by ear clipping. Triangles are indices
+    /// into the polygon points, which are in object-local coordinates.
+    ///
+    /// Returns `None` for other shapes and for polygons that can't be
+    /// triangulated, eg. because they intersect themselves.
+    pub fn triangulate(&self) -> Option<Vec<[usize; 3]>> {
+        match self {
+            ObjectShape::Polygon { points } => triangulate(points),
+            _ => None,
+        }
+    }
+}
+
+fn triangulate(points: &[Point]) -> Option<Vec<[usize; 3]>> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    let mut indices: Vec<usize> = (0..points.len()).collect();
+    // Ears are found with positive winding, see `signed_area`.
+    if signed_area(points) < 0.0 {
+        indices.reverse();
+    }
+
+    let cross = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (&points[a], &points[b], &points[c]);
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    };
+
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while indices.len() > 3 {
+        let count = indices.len();
+        let corner = |i: usize| {
+            (
+                indices[(i + count - 1) % count],
+                indices[i],
+                indices[(i + 1) % count],
+            )
+        };
+
+        let ear = (0..count).find(|i| {
+            let (a, b, c) = corner(*i);
+            cross(a, b, c) > 0.0
+                && indices.iter().all(|p| {
+                    *p == a
+                        || *p == b
+                        || *p == c
+                        || cross(a, b, *p) < 0.0
+                        || cross(b, c, *p) < 0.0
+                        || cross(c, a, *p) < 0.0
+                })
+        });
+
+        match ear {
+            Some(i) => {
+                let (a, b, c) = corner(i);
+                triangles.push([a, b, c]);
+                indices.remove(i);
+            }
+            None => {
+                // Collinear points don't form ears, but can be dropped.
+                let i = (0..count).find(|i| {
+                    let (a, b, c) = corner(*i);
+                    cross(a, b, c) == 0.0
+                })?;
+                indices.remove(i);
+            }
+        }
+    }
+
+    if cross(indices[0], indices[1], indices[2]) != 0.0 {
+        triangles.push([indices[0], indices[1], indices[2]]);
+    }
+
+    Some(triangles)
+}
@@ -0,0 +1,93 @@
+//! Typed positions, so tile indices and pixel coordinates can't be mixed up.
+
+use std::ops::Index;
+
+use crate::cull::Grid;
+use crate::{Map, RaycastHit, TileLayer};
+
+/// Column and row of a tile layer cell.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct TilePos(pub u32, pub u32);
+
+/// Position in map pixels.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct PixelPos(pub f32, pub f32);
+
+impl From<(u32, u32)> for TilePos {
+    fn from((x, y): (u32, u32)) -> Self {
+        TilePos(x, y)
+    }
+}
+
+impl From<(f32, f32)> for PixelPos {
+    fn from((x, y): (f32, f32)) -> Self {
+        PixelPos(x, y)
+    }
+}
+
+impl TileLayer {
+    /// Tile at a cell, `None` outside the layer.
+    pub fn tile_at(&self, pos: TilePos) -> Option<u32> {
+        if pos.0 >= self.width() || pos.1 >= self.height() {
+            return None;
+        }
+
+        self.tiles()
+            .get((pos.0 + pos.1 * self.width()) as usize)
+            .copied()
+    }
+}
+
+/// Tile at a cell, same as `TileLayer::get_tile`.
+impl Index<TilePos> for TileLayer {
+    type Output = u32;
+
+    fn index(&self, pos: TilePos) -> &u32 {
+        &self[(pos.0, pos.1)]
+    }
+}
+
+impl Map {
+    /// Top-left corner of the bounding box of a tile, following the
+    /// orientation of the map.
+    pub fn tile_to_pixel(&self, pos: TilePos) -> PixelPos {
+        let (x, y) = Grid::new(self).tile_origin(pos.0 as i32, pos.1 as i32);
+        PixelPos(x, y)
+    }
+
+    /// Cell at a pixel, `None` left or above the map. Exact for orthogonal
+    /// and isometric maps; staggered and hexagonal maps are approximated by
+    /// a grid of tile bounding boxes.
+    pub fn pixel_to_tile(&self, pos: PixelPos) -> Option<TilePos> {
+        let (x, y) = Grid::new(self).tile_at(pos.0, pos.1);
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        Some(TilePos(x as u32, y as u32))
+    }
+
+    /// `TileLayer::raycast` between two points in pixels of an orthogonal
+    /// map.
+    pub fn raycast<F>(
+        &self,
+        layer: &TileLayer,
+        from: PixelPos,
+        to: PixelPos,
+        is_blocking: F,
+    ) -> Option<RaycastHit>
+    where
+        F: Fn(u32) -> bool,
+    {
+        let (width, height) = (
+            self.tile_width.max(1) as f32,
+            self.tile_height.max(1) as f32,
+        );
+
+        layer.raycast(
+            (from.0 / width, from.1 / height),
+            (to.0 / width, to.1 / height),
+            is_blocking,
+        )
+    }
+}
@@ -0,0 +1,195 @@
+use serde_json::{json, Value};
+
+use crate::error::TiledError;
+use crate::properties::properties_to_json;
+use crate::utils::{parse_version, Color};
+use crate::wang::{Terrain, WangSet};
+
+/// Tiled version that started writing the version number as a string.
+pub(crate) const STRING_VERSION: (u32, u32) = (1, 6);
+/// Tiled version that replaced terrains with wang sets.
+pub(crate) const WANG_SETS: (u32, u32) = (1, 5);
+/// Tiled version that renamed object and tile `type` to `class`.
+pub(crate) const CLASS: (u32, u32) = (1, 9);
+
+/// Colors Tiled gives to wang colors converted from legacy terrains, by
+/// terrain index. Repeats for tilesets with more terrains.
+const TERRAIN_COLORS: [Color; 12] = [
+    Color::new(0xff, 0x00, 0x00, 0xff),
+    Color::new(0x00, 0xff, 0x00, 0xff),
+    Color::new(0x00, 0x00, 0xff, 0xff),
+    Color::new(0xff, 0x77, 0x00, 0xff),
+    Color::new(0x00, 0xe9, 0xff, 0xff),
+    Color::new(0xff, 0x00, 0xd8, 0xff),
+    Color::new(0xe9, 0xff, 0x00, 0xff),
+    Color::new(0xff, 0x00, 0x6e, 0xff),
+    Color::new(0x00, 0xff, 0xa1, 0xff),
+    Color::new(0xff, 0xa8, 0x00, 0xff),
+    Color::new(0x00, 0x5f, 0xff, 0xff),
+    Color::new(0xfb, 0x00, 0xff, 0xff),
+];
+
+/// Color of the wang color converted from the legacy terrain at `index`.
+pub(crate) fn terrain_color(index: usize) -> Color {
+    TERRAIN_COLORS[index % TERRAIN_COLORS.len()]
+}
+
+/// Rewrite legacy fields of a map in json format to their modern equivalents
+/// so that the re-serialized file opens cleanly in given Tiled version.
+///
+/// - numeric `version` is written as a string (1.6)
+/// - tileset `terrains` are converted to corner wang sets (1.5)
+/// - object and tile `type` is renamed to `class` (1.9)
+///
+/// Downgrading is not supported.
+pub fn upgrade_json(map: &mut Value, version: &str) -> Result<(), TiledError> {
+    let target = parse_version(version)
+        .ok_or_else(|| TiledError::Other(format!("Invalid version {:?}", version)))?;
+
+    let map = map
+        .as_object_mut()
+        .ok_or_else(|| TiledError::Other("Improperly formatted map".to_string()))?;
+
+    let current = match map.get("version") {
+        Some(Value::Number(version)) => parse_version(&version.to_string()),
+        Some(Value::String(version)) => parse_version(version),
+        _ => None,
+    }
+    .ok_or_else(|| TiledError::Other("Map has no valid version".to_string()))?;
+
+    if target < current {
+        return Err(TiledError::Other(format!(
+            "Can't downgrade map from {}.{} to {}",
+            current.0, current.1, version
+        )));
+    }
+
+    if target >= WANG_SETS {
+        if let Some(Value::Array(tilesets)) = map.get_mut("tilesets") {
+            tilesets.iter_mut().for_each(upgrade_terrains);
+        }
+    }
+
+    if target >= CLASS {
+        if let Some(Value::Array(tilesets)) = map.get_mut("tilesets") {
+            for tileset in tilesets {
+                if let Some(Value::Array(tiles)) = tileset.get_mut("tiles") {
+                    for tile in tiles {
+                        rename_type_to_class(tile);
+                        if let Some(group) = tile.get_mut("objectgroup") {
+                            upgrade_layer_classes(group);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Array(layers)) = map.get_mut("layers") {
+            layers.iter_mut().for_each(upgrade_layer_classes);
+        }
+    }
+
+    map.insert(
+        "version".to_string(),
+        if target >= STRING_VERSION {
+            Value::String(version.trim().to_string())
+        } else {
+            serde_json::from_str(version.trim()).map_err(TiledError::ParsingError)?
+        },
+    );
+
+    Ok(())
+}
+
+/// Rename `type` of objects to `class` in object layers and groups.
+fn upgrade_layer_classes(layer: &mut Value) {
+    if let Some(Value::Array(objects)) = layer.get_mut("objects") {
+        objects.iter_mut().for_each(rename_type_to_class);
+    }
+
+    if let Some(Value::Array(layers)) = layer.get_mut("layers") {
+        layers.iter_mut().for_each(upgrade_layer_classes);
+    }
+}
+
+fn rename_type_to_class(value: &mut Value) {
+    if let Some(object) = value.as_object_mut() {
+        if let Some(class) = object.remove("type") {
+            object.entry("class").or_insert(class);
+        }
+    }
+}
+
+/// Convert legacy terrains of a tileset to a corner wang set, see
+/// `WangSet::from_terrains`.
+fn upgrade_terrains(tileset: &mut Value) {
+    let tileset = match tileset.as_object_mut() {
+        Some(tileset) => tileset,
+        None => return,
+    };
+
+    let terrains: Vec<Terrain> = match tileset.remove("terrains") {
+        Some(terrains) => match serde_json::from_value(terrains) {
+            Ok(terrains) => terrains,
+            Err(_) => return,
+        },
+        None => return,
+    };
+
+    let mut corners = Vec::new();
+    if let Some(Value::Array(tiles)) = tileset.get_mut("tiles") {
+        for tile in tiles.iter_mut().filter_map(Value::as_object_mut) {
+            let terrain = match tile.remove("terrain") {
+                Some(terrain) => terrain,
+                None => continue,
+            };
+            let id = tile.get("id").and_then(Value::as_u64);
+
+            if let (Some(id), Ok(terrain)) = (id, serde_json::from_value(terrain)) {
+                corners.push((id as u32, terrain));
+            }
+        }
+    }
+
+    let wang_set = wang_set_to_json(&WangSet::from_terrains(&terrains, corners));
+    let wang_sets = tileset
+        .entry("wangsets")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if let Value::Array(wang_sets) = wang_sets {
+        wang_sets.push(wang_set);
+    }
+}
+
+/// Wang set in the form Tiled writes it.
+fn wang_set_to_json(wang_set: &WangSet) -> Value {
+    let colors: Vec<Value> = wang_set
+        .colors
+        .iter()
+        .map(|color| {
+            let mut value = json!({
+                "name": color.name,
+                "color": color.color.to_tiled_string(false),
+                "tile": color.tile,
+                "probability": color.probability,
+            });
+            if let Some(properties) = &color.properties {
+                value["properties"] = properties_to_json(properties);
+            }
+            value
+        })
+        .collect();
+
+    let wang_tiles: Vec<Value> = wang_set
+        .wang_tiles
+        .iter()
+        .map(|tile| json!({ "tileid": tile.tile_id, "wangid": tile.wang_id }))
+        .collect();
+
+    json!({
+        "name": wang_set.name,
+        "type": wang_set.wang_type,
+        "tile": wang_set.tile,
+        "colors": colors,
+        "wangtiles": wang_tiles,
+    })
+}
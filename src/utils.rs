@@ -1,13 +1,20 @@
-use std::io::Read;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Read, Write};
 use std::str::FromStr;
 
+use serde::de::{DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
 use serde::{de, Deserialize, Deserializer};
-use serde_json::{Number, Value};
+use serde_json::error::Category;
+use serde_json::Value;
+use serde_path_to_error::Segment;
 
 use crate::error::TiledError;
+use crate::options::{current_options, report_error, report_warning};
+use crate::warning::ParseWarning;
 
 /// Algoritm used to compress the tile layer data.
-#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Compression {
     Zlib,
@@ -15,35 +22,55 @@ pub enum Compression {
 }
 
 /// Encoding used to encode the tile layer data.
-#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Encoding {
     Csv,
     Base64,
 }
 
-pub fn decode_zlib(data: Vec<u8>) -> Result<Vec<u8>, TiledError> {
+/// Decompress zlib data. Fails with `DecompressionLimitExceeded` if the
+/// output would be larger than `limit` bytes.
+pub fn decode_zlib(data: Vec<u8>, limit: usize) -> Result<Vec<u8>, TiledError> {
     use libflate::zlib::Decoder;
-    let mut buffer = Vec::new();
-
-    Decoder::new(&data[..])
-        .and_then(|mut decoder| decoder.read_to_end(&mut buffer))
-        .map_err(TiledError::DecompressingError)?;
 
-    Ok(buffer)
+    let decoder = Decoder::new(&data[..]).map_err(TiledError::DecompressingError)?;
+    read_limited(decoder, limit)
 }
 
-pub fn decode_gzip(data: Vec<u8>) -> Result<Vec<u8>, TiledError> {
+/// Decompress gzip data. Fails with `DecompressionLimitExceeded` if the
+/// output would be larger than `limit` bytes.
+pub fn decode_gzip(data: Vec<u8>, limit: usize) -> Result<Vec<u8>, TiledError> {
     use libflate::gzip::Decoder;
+
+    let decoder = Decoder::new(&data[..]).map_err(TiledError::DecompressingError)?;
+    read_limited(decoder, limit)
+}
+
+/// Read at most `limit` bytes, without reading the rest of a larger input.
+fn read_limited<R: Read>(reader: R, limit: usize) -> Result<Vec<u8>, TiledError> {
     let mut buffer = Vec::new();
 
-    Decoder::new(&data[..])
-        .and_then(|mut decoder| decoder.read_to_end(&mut buffer))
+    // One extra byte tells if there's more.
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut buffer)
         .map_err(TiledError::DecompressingError)?;
 
+    if buffer.len() > limit {
+        return Err(TiledError::DecompressionLimitExceeded { limit });
+    }
+
     Ok(buffer)
 }
 
+/// Limit of the decompressed size of `tiles` tiles, see
+/// `ParseOptions::max_decompressed_bytes`.
+pub fn decompressed_limit(tiles: usize) -> usize {
+    current_options(|options| options.max_decompressed_bytes)
+        .unwrap_or_else(|| tiles.saturating_mul(4))
+}
+
 pub fn decode_tiledata(
     data: Value,
     width: u32,
@@ -57,7 +84,10 @@ pub fn decode_tiledata(
     let mut tiles = Vec::with_capacity(usize::try_from(width * height).unwrap_or(0));
 
     match encoding {
-        Some(Encoding::Base64) => decode_base64_tiledata(data, compression, &mut tiles),
+        Some(Encoding::Base64) => {
+            let limit = decompressed_limit(width as usize * height as usize);
+            decode_base64_tiledata(data, compression, limit, &mut tiles)
+        }
         Some(Encoding::Csv) | None => decode_csv_tiledata(data, &mut tiles),
     }?;
 
@@ -68,33 +98,76 @@ pub fn decode_tiledata(
 pub fn decode_base64_tiledata(
     data: Value,
     compression: Option<Compression>,
+    limit: usize,
     tiles: &mut Vec<u32>,
 ) -> Result<(), TiledError> {
-    use std::convert::TryInto;
+    decode_tile_bytes(decode_base64(&data)?, compression, limit, tiles)
+}
 
+/// Decode base64 string to (possibly compressed) bytes.
+pub fn decode_base64(data: &Value) -> Result<Vec<u8>, TiledError> {
     let data = data
         .as_str()
         .ok_or_else(|| TiledError::Other("Improperly formatted data".to_string()))?;
 
-    let bytes = base64::decode(data.trim().as_bytes()).map_err(TiledError::Base64DecodingError)?;
+    base64::decode(data.trim().as_bytes()).map_err(TiledError::Base64DecodingError)
+}
+
+/// Write tiles as little endian u32s and compress them.
+pub fn encode_tile_bytes(
+    tiles: &[u32],
+    compression: Option<Compression>,
+) -> Result<Vec<u8>, TiledError> {
+    let bytes: Vec<u8> = tiles.iter().flat_map(|gid| gid.to_le_bytes()).collect();
 
+    let compressed = match compression {
+        None => return Ok(bytes),
+        Some(Compression::Zlib) => {
+            libflate::zlib::Encoder::new(Vec::new()).and_then(|mut encoder| {
+                encoder.write_all(&bytes)?;
+                encoder.finish().into_result()
+            })
+        }
+        Some(Compression::Gzip) => {
+            libflate::gzip::Encoder::new(Vec::new()).and_then(|mut encoder| {
+                encoder.write_all(&bytes)?;
+                encoder.finish().into_result()
+            })
+        }
+    };
+
+    compressed.map_err(|err| TiledError::Other(format!("{:?}", err)))
+}
+
+/// Decompress bytes and read them as little endian u32s. `limit` is the
+/// maximum decompressed size.
+pub fn decode_tile_bytes(
+    bytes: Vec<u8>,
+    compression: Option<Compression>,
+    limit: usize,
+    tiles: &mut Vec<u32>,
+) -> Result<(), TiledError> {
     let bytes = match compression {
-        Some(Compression::Gzip) => decode_gzip(bytes),
-        Some(Compression::Zlib) => decode_zlib(bytes),
+        Some(Compression::Gzip) => decode_gzip(bytes, limit),
+        Some(Compression::Zlib) => decode_zlib(bytes, limit),
         None => Ok(bytes),
     }?;
 
-    // Read u32s from buffer into 1d vec of u32.
-    for chunk in bytes.chunks(std::mem::size_of::<u32>()) {
-        let tile_value = u32::from_le_bytes(
-            chunk
-                .try_into()
-                .map_err(|err| TiledError::Other(format!("{:?}", err)))?,
-        );
-
-        tiles.push(tile_value);
+    if bytes.len() % 4 != 0 {
+        return Err(TiledError::Other(format!(
+            "Tile data length {} is not a multiple of 4",
+            bytes.len()
+        )));
     }
 
+    // Read u32s from buffer into 1d vec of u32. `from_le_bytes` compiles to a
+    // plain load on little endian targets and swaps bytes on big endian ones.
+    tiles.extend(
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+    );
+
     Ok(())
 }
 
@@ -115,46 +188,259 @@ pub fn decode_csv_tiledata(data: Value, tiles: &mut Vec<u32>) -> Result<(), Tile
     }
 }
 
-/// Deserialize map version number from json number to string.
+/// Read a number field. In lenient mode strings are parsed with either `.` or
+/// `,` as decimal separator and invalid or non-finite values become zero.
+/// Otherwise they are reported as `TiledError::InvalidNumber`.
+fn deserialize_number<'de, D>(field: &'static str, deserializer: D) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+
+    let number = match &value {
+        Value::Number(number) => number.as_f64().map(|n| n as f32),
+        Value::String(s) => s.trim().replace(',', ".").parse::<f32>().ok(),
+        _ => None,
+    };
+
+    let lenient = current_options(|options| options.lenient);
+
+    match number {
+        Some(number) if number.is_finite() && (lenient || value.is_number()) => Ok(number),
+        _ if lenient => Ok(0.0),
+        _ => {
+            let err = TiledError::InvalidNumber {
+                field: field.to_string(),
+                value: value.to_string(),
+            };
+            let message = err.to_string();
+
+            report_error(err);
+            Err(de::Error::custom(message))
+        }
+    }
+}
+
+pub fn deserialize_x<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_number("x", deserializer)
+}
+
+pub fn deserialize_y<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_number("y", deserializer)
+}
+
+pub fn deserialize_width<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_number("width", deserializer)
+}
+
+pub fn deserialize_height<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_number("height", deserializer)
+}
+
+pub fn deserialize_rotation<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_number("rotation", deserializer)
+}
+
+/// Normalize enum values to the form Tiled writes them: trim whitespace,
+/// lowercase and resolve common aliases. Used in lenient mode.
+pub fn normalize_enum_values(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match (key.as_str(), field) {
+                    ("orientation", Value::String(s))
+                    | ("encoding", Value::String(s))
+                    | ("compression", Value::String(s)) => {
+                        let normalized = s.trim().to_lowercase();
+                        *s = match normalized.as_str() {
+                            "ortho" => "orthogonal".to_string(),
+                            "iso" => "isometric".to_string(),
+                            "hex" => "hexagonal".to_string(),
+                            "gz" => "gzip".to_string(),
+                            _ => normalized,
+                        };
+                    }
+                    (_, field) => normalize_enum_values(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_enum_values),
+        _ => {}
+    }
+}
+
+/// Deserialize map version number from json number to string. Tiled 1.6 and
+/// newer write the version as a string.
 /// This function could also signal error if version number is not supported.
 pub fn deserialize_version<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let version: Number = Deserialize::deserialize(deserializer)?;
-    Ok(version.to_string())
+    match Deserialize::deserialize(deserializer)? {
+        Value::Number(version) => {
+            report_warning(ParseWarning::NumericVersion);
+            Ok(version.to_string())
+        }
+        Value::String(version) => Ok(version),
+        _ => Err(de::Error::custom("Invalid version number")),
+    }
+}
+
+/// Top-level `type` of a map document. Deserializing fails unless the type
+/// is `map`. Older versions don't write the type.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct MapType;
+
+impl<'de> Deserialize<'de> for MapType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let found = String::deserialize(deserializer)?;
+        if found == "map" {
+            return Ok(MapType);
+        }
+
+        let message = format!("Expected map document, found {}", found);
+        report_error(TiledError::WrongDocumentType {
+            expected: "map".to_string(),
+            found,
+        });
+
+        Err(de::Error::custom(message))
+    }
+}
+
+/// Deserializer of a map document that checks the top-level `type` with
+/// `MapType` and leaves it out of the map, so the map doesn't need a field
+/// for it. The same wrapper is the visitor and map access of the document.
+pub struct TypeChecked<T>(pub T);
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for TypeChecked<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_map(TypeChecked(visitor))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for TypeChecked<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.0.visit_map(TypeChecked(map))
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for TypeChecked<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.0.next_key::<String>()? {
+            if key != "type" {
+                return seed.deserialize(key.into_deserializer()).map(Some);
+            }
+            self.0.next_value::<MapType>()?;
+        }
+
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.0.next_value_seed(seed)
+    }
+}
+
+/// Parse `major.minor` version string. Missing minor version is zero.
+pub fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = match parts.next() {
+        Some(minor) => minor.parse().ok()?,
+        None => 0,
+    };
+
+    Some((major, minor))
 }
 
 /// Color as rgba.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub struct Color([u8; 4]);
 
-/// Convert hex string to rgb bytes.
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color([r, g, b, a])
+    }
+
+    /// Channels as `[r, g, b, a]`.
+    pub fn rgba(self) -> [u8; 4] {
+        self.0
+    }
+
+    /// Format color as `#aarrggbb` hex string.
+    pub(crate) fn to_hex(self) -> String {
+        self.to_tiled_string(true)
+    }
+
+    /// Format color the way Tiled writes it: `#aarrggbb` with alpha and
+    /// `#rrggbb` without. Parsing the string gives the same color, except
+    /// that alpha is opaque when it's left out.
+    pub fn to_tiled_string(self, with_alpha: bool) -> String {
+        let [r, g, b, a] = self.0;
+        if with_alpha {
+            format!("#{:02x}{:02x}{:02x}{:02x}", a, r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+}
+
+/// Convert hex string to rgb bytes. Accepts `#rrggbb`, `#aarrggbb` and the
+/// short forms `#rgb` and `#argb` in any case. The `#` is optional.
 impl FromStr for Color {
     type Err = TiledError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = if s.starts_with('#') { &s[1..] } else { s };
-        let mut color = [std::u8::MAX; 4];
+        let invalid = || TiledError::Other(format!("Invalid color value {:?}", s));
+        let hex = s.strip_prefix('#').unwrap_or(s);
 
-        // Tiled colors are ither #rrggbb or #aarrggbb ('#' is trimmed above).
-        if s.len() != 6 && s.len() != 8 {
-            return Err(TiledError::Other(format!("Invalid color value {:?}", s)));
-        }
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
 
-        // Read two characters to u8 with u8::from_str_radix(..., 16).
-        for i in (0..s.len()).step_by(2) {
-            color[i / 2] = u8::from_str_radix(&s[i..i + 2], 16)
-                .map_err(|_| TiledError::Other(format!("Invalid color value {:?}", s)))?;
-        }
+        // Short forms repeat each digit, eg. #f80 is #ff8800.
+        let channels: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|digit| digit * 0x11).collect(),
+            6 | 8 => digits
+                .chunks(2)
+                .map(|pair| pair[0] << 4 | pair[1])
+                .collect(),
+            _ => return Err(invalid()),
+        };
 
-        if s.len() == 8 {
+        match *channels.as_slice() {
+            [r, g, b] => Ok(Color([r, g, b, u8::MAX])),
             // Swap alpha channel to last byte (argb -> rgba).
-            Ok(Color {
-                0: [color[1], color[2], color[3], color[0]],
-            })
-        } else {
-            Ok(Color { 0: color })
+            [a, r, g, b] => Ok(Color([r, g, b, a])),
+            _ => unreachable!(),
         }
     }
 }
@@ -168,3 +454,167 @@ impl<'de> Deserialize<'de> for Color {
             .map_err(de::Error::custom)
     }
 }
+
+/// Convert UTF-16 text to UTF-8. The encoding is detected from the byte order
+/// mark or, without one, from zero bytes around the first ASCII character of
+/// the JSON. Anything else is assumed to be UTF-8 and returned as is.
+pub(crate) fn transcode_to_utf8(bytes: &[u8]) -> Result<Cow<'_, [u8]>, TiledError> {
+    let unsupported = |encoding: &str| Err(TiledError::UnsupportedEncoding(encoding.to_string()));
+
+    let (big_endian, text) = match bytes {
+        [0, 0, 0xfe, 0xff, ..] | [0xff, 0xfe, 0, 0, ..] => return unsupported("UTF-32"),
+        [0, 0, 0, _, ..] | [_, 0, 0, 0, ..] => return unsupported("UTF-32"),
+        [0xfe, 0xff, text @ ..] => (true, text),
+        [0xff, 0xfe, text @ ..] => (false, text),
+        [0, _, ..] => (true, bytes),
+        [_, 0, ..] => (false, bytes),
+        _ => return Ok(Cow::Borrowed(bytes)),
+    };
+
+    let encoding = if big_endian { "UTF-16BE" } else { "UTF-16LE" };
+    if text.len() % 2 != 0 {
+        return Err(TiledError::UnsupportedEncoding(format!(
+            "{} with odd byte count",
+            encoding
+        )));
+    }
+
+    let units: Vec<u16> = text
+        .chunks(2)
+        .map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if big_endian {
+                u16::from_be_bytes(pair)
+            } else {
+                u16::from_le_bytes(pair)
+            }
+        })
+        .collect();
+
+    String::from_utf16(&units)
+        .map(|text| Cow::Owned(text.into_bytes()))
+        .map_err(|_| TiledError::UnsupportedEncoding(format!("invalid {}", encoding)))
+}
+
+/// UTF-8 byte order mark written by some Windows tools.
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Byte order marks of other encodings. UTF-32 comes first, its little
+/// endian mark starts with the UTF-16 one.
+const OTHER_BOMS: &[(&[u8], &str)] = &[
+    (b"\0\0\xfe\xff", "UTF-32BE"),
+    (b"\xff\xfe\0\0", "UTF-32LE"),
+    (b"\xfe\xff", "UTF-16BE"),
+    (b"\xff\xfe", "UTF-16LE"),
+    (b"+/v", "UTF-7"),
+    (b"\xf7\x64\x4c", "UTF-1"),
+    (b"\xdd\x73\x66\x73", "UTF-EBCDIC"),
+    (b"\x0e\xfe\xff", "SCSU"),
+    (b"\xfb\xee\x28", "BOCU-1"),
+    (b"\x84\x31\x95\x33", "GB18030"),
+];
+
+/// Fail with `TiledError::UnsupportedEncoding` if the text starts with the
+/// byte order mark of an encoding other than UTF-8.
+fn check_bom(bytes: &[u8]) -> Result<(), TiledError> {
+    match OTHER_BOMS.iter().find(|(bom, _)| bytes.starts_with(bom)) {
+        Some((_, encoding)) => Err(TiledError::UnsupportedEncoding(encoding.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Deserialize JSON from memory. A UTF-8 byte order mark is skipped and
+/// trailing whitespace is allowed, anything else after the JSON fails with
+/// `TiledError::TrailingData`.
+pub(crate) fn json_from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, TiledError> {
+    check_bom(bytes)?;
+    let bytes = bytes.strip_prefix(BOM).unwrap_or(bytes);
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = deserialize_with_path(&mut deserializer)?;
+    deserializer.end().map_err(trailing_data)?;
+
+    Ok(value)
+}
+
+/// Deserialize JSON from a reader, like `json_from_slice`.
+pub(crate) fn json_from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T, TiledError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(skip_bom(reader)?);
+    let value = deserialize_with_path(&mut deserializer)?;
+    deserializer.end().map_err(trailing_data)?;
+
+    Ok(value)
+}
+
+/// Deserialize with the path of the value that failed to parse, eg.
+/// `layers[3].objects[17].width`, added to the error.
+pub(crate) fn deserialize_with_path<'de, D, T>(deserializer: D) -> Result<T, TiledError>
+where
+    D: Deserializer<'de, Error = serde_json::Error>,
+    T: Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        let path = path_string(err.path());
+        let err = TiledError::ParsingError(err.into_inner());
+
+        if path.is_empty() {
+            return err;
+        }
+
+        TiledError::Path {
+            path,
+            source: Box::new(err),
+        }
+    })
+}
+
+/// Path in the form `layers[3].objects[17]`, empty for the root. Values read
+/// through flattened fields have no key and are left out.
+fn path_string(path: &serde_path_to_error::Path) -> String {
+    let mut string = String::new();
+    for segment in path {
+        match segment {
+            Segment::Seq { index } => string.push_str(&format!("[{}]", index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                if !string.is_empty() {
+                    string.push('.');
+                }
+                string.push_str(key);
+            }
+            Segment::Unknown => {}
+        }
+    }
+
+    string
+}
+
+fn skip_bom<R: Read>(mut reader: R) -> Result<impl Read, TiledError> {
+    let mut prefix = [0; 4];
+    let mut len = 0;
+
+    while len < prefix.len() {
+        match reader.read(&mut prefix[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(TiledError::Other(format!("{:?}", err))),
+        }
+    }
+
+    check_bom(&prefix[..len])?;
+    let start = if prefix[..len].starts_with(BOM) {
+        BOM.len()
+    } else {
+        0
+    };
+    Ok(io::Cursor::new(prefix[start..len].to_vec()).chain(reader))
+}
+
+fn trailing_data(err: serde_json::Error) -> TiledError {
+    match err.classify() {
+        Category::Syntax => TiledError::TrailingData {
+            line: err.line(),
+            column: err.column(),
+        },
+        _ => TiledError::ParsingError(err),
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::TiledError;
+use crate::Map;
+
+/// Version of Tiled or of the file format, eg. `1.10.2`. Missing parts are
+/// zero.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct TiledVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl TiledVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        TiledVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for TiledVersion {
+    type Err = TiledError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TiledError::Other(format!("Invalid version {:?}", s));
+
+        let mut parts = s.trim().split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) => part.parse().map_err(|_| invalid()),
+            None if required => Err(invalid()),
+            None => Ok(0),
+        };
+
+        let version = TiledVersion::new(next(true)?, next(false)?, next(false)?);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(version)
+    }
+}
+
+impl fmt::Display for TiledVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl Map {
+    /// File format version of the map.
+    pub fn tiled_version(&self) -> Result<TiledVersion, TiledError> {
+        self.version.parse()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{ImageLayer, Layer, LayerType, Map, Object, ObjectGroup, TileLayer};
+
+/// Callbacks for `Map::walk`. All methods do nothing by default, so visitors
+/// only implement what they need.
+pub trait MapVisitor {
+    /// Called for each layer before its contents. Return `false` to skip the
+    /// contents of the layer.
+    fn visit_layer(&mut self, _layer: &Layer) -> bool {
+        true
+    }
+
+    /// Called for tile layers before their tiles.
+    fn visit_tile_layer(&mut self, _layer: &Layer, _tiles: &TileLayer) {}
+
+    /// Called for each non-empty tile of the last visited tile layer.
+    /// Coordinates are relative to the layer. Layers that are still encoded
+    /// have no tiles.
+    fn visit_tile(&mut self, _x: u32, _y: u32, _gid: u32) {}
+
+    /// Called for object layers before their objects.
+    fn visit_object_group(&mut self, _layer: &Layer, _group: &ObjectGroup) {}
+
+    /// Called for each object of the last visited object layer.
+    fn visit_object(&mut self, _layer: &Layer, _object: &Object) {}
+
+    fn visit_image_layer(&mut self, _layer: &Layer, _image: &ImageLayer) {}
+}
+
+impl Map {
+    /// Walk all layers, tiles and objects in one pass, in layer order.
+    pub fn walk<V: MapVisitor>(&self, visitor: &mut V) {
+        for layer in &self.layers {
+            if !visitor.visit_layer(layer) {
+                continue;
+            }
+
+            match &layer.data {
+                LayerType::TileLayer(tiles) => {
+                    visitor.visit_tile_layer(layer, tiles);
+
+                    let width = tiles.width().max(1);
+                    for (index, gid) in tiles.tiles().iter().enumerate() {
+                        if *gid != 0 {
+                            let index = index as u32;
+                            visitor.visit_tile(index % width, index / width, *gid);
+                        }
+                    }
+                }
+                LayerType::ObjectGroup(group) => {
+                    visitor.visit_object_group(layer, group);
+                    for object in &group.objects {
+                        visitor.visit_object(layer, object);
+                    }
+                }
+                LayerType::ImageLayer(image) => visitor.visit_image_layer(layer, image),
+                LayerType::Invalid { .. } => {}
+            }
+        }
+    }
+}
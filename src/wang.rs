@@ -0,0 +1,186 @@
+use std::convert::TryFrom;
+
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::properties::deserialize_properties;
+use crate::tileset::Tileset;
+use crate::upgrade::terrain_color;
+use crate::{Color, Properties};
+
+/// Legacy terrain type of a tileset, replaced by wang sets in Tiled 1.5.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Terrain {
+    pub name: String,
+    /// Local ID of the tile representing the terrain, -1 for none.
+    #[serde(default = "no_tile")]
+    pub tile: i32,
+    #[serde(default, deserialize_with = "deserialize_properties")]
+    pub properties: Option<Properties>,
+}
+
+/// Set of wang colors and tiles used for auto-tiling.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct WangSet {
+    pub name: String,
+    /// `corner`, `edge` or `mixed`.
+    #[serde(rename = "type", default = "default_wang_type")]
+    pub wang_type: String,
+    /// Local ID of the tile representing the set, -1 for none.
+    #[serde(default = "no_tile")]
+    pub tile: i32,
+    #[serde(default)]
+    pub colors: Vec<WangColor>,
+    #[serde(default, rename = "wangtiles")]
+    pub wang_tiles: Vec<WangTile>,
+    #[serde(default, deserialize_with = "deserialize_properties")]
+    pub properties: Option<Properties>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct WangColor {
+    pub name: String,
+    pub color: Color,
+    /// Local ID of the tile representing the color, -1 for none.
+    #[serde(default = "no_tile")]
+    pub tile: i32,
+    #[serde(default = "default_probability")]
+    pub probability: f32,
+    #[serde(default, deserialize_with = "deserialize_properties")]
+    pub properties: Option<Properties>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Deserialize)]
+pub struct WangTile {
+    /// Local ID of the tile
+    #[serde(rename = "tileid")]
+    pub tile_id: u32,
+    /// Colors clockwise from the top edge: top, top-right, right,
+    /// bottom-right, bottom, bottom-left, left and top-left. Zero means no
+    /// color, other values are indices to `WangSet::colors` plus one.
+    #[serde(rename = "wangid", deserialize_with = "deserialize_wang_id")]
+    pub wang_id: [u8; 8],
+}
+
+fn no_tile() -> i32 {
+    -1
+}
+
+fn default_wang_type() -> String {
+    "corner".to_string()
+}
+
+fn default_probability() -> f32 {
+    1.0
+}
+
+/// Wang ids are arrays since Tiled 1.5 and packed nibbles before that.
+fn deserialize_wang_id<'de, D>(deserializer: D) -> Result<[u8; 8], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut wang_id = [0; 8];
+
+    match Value::deserialize(deserializer)? {
+        Value::Array(colors) if colors.len() == 8 => {
+            for (id, color) in wang_id.iter_mut().zip(colors) {
+                *id = color
+                    .as_u64()
+                    .and_then(|color| u8::try_from(color).ok())
+                    .ok_or_else(|| de::Error::custom("Invalid wang color"))?;
+            }
+        }
+        Value::Number(packed) => {
+            let packed = packed
+                .as_u64()
+                .ok_or_else(|| de::Error::custom("Invalid wang id"))?;
+            for (i, id) in wang_id.iter_mut().enumerate() {
+                *id = ((packed >> (i * 4)) & 0xf) as u8;
+            }
+        }
+        _ => return Err(de::Error::custom("Invalid wang id")),
+    }
+
+    Ok(wang_id)
+}
+
+impl WangSet {
+    /// Corner wang set converted from legacy terrains and the terrain
+    /// corners of tiles, given as `(tile id, corners)`. Both `upgrade_json`
+    /// and `Tileset::terrains_to_wang_set` convert terrains with this.
+    pub(crate) fn from_terrains<I>(terrains: &[Terrain], tiles: I) -> WangSet
+    where
+        I: IntoIterator<Item = (u32, [i32; 4])>,
+    {
+        let colors = terrains
+            .iter()
+            .enumerate()
+            .map(|(index, terrain)| WangColor {
+                name: terrain.name.clone(),
+                color: terrain_color(index),
+                tile: terrain.tile,
+                probability: 1.0,
+                properties: terrain.properties.clone(),
+            })
+            .collect();
+
+        let wang_tiles = tiles
+            .into_iter()
+            .map(|(tile_id, corners)| {
+                // Terrain corners are top-left, top-right, bottom-left,
+                // bottom-right with -1 meaning no terrain.
+                let color = |corner: i32| (corner + 1).clamp(0, i32::from(u8::MAX)) as u8;
+
+                WangTile {
+                    tile_id,
+                    wang_id: [
+                        0,
+                        color(corners[1]),
+                        0,
+                        color(corners[3]),
+                        0,
+                        color(corners[2]),
+                        0,
+                        color(corners[0]),
+                    ],
+                }
+            })
+            .collect();
+
+        WangSet {
+            name: "Terrains".to_string(),
+            wang_type: "corner".to_string(),
+            tile: -1,
+            colors,
+            wang_tiles,
+            properties: None,
+        }
+    }
+}
+
+impl Tileset {
+    /// Legacy terrains of the tileset as a corner wang set, or `None` if the
+    /// tileset has no terrains. Same conversion as `upgrade_json`.
+    pub fn terrains_to_wang_set(&self) -> Option<WangSet> {
+        let terrains = self.terrains.as_ref()?;
+        let tiles = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter_map(|tile| Some((tile.id, tile.terrain?)));
+
+        Some(WangSet::from_terrains(terrains, tiles))
+    }
+
+    /// Replace legacy terrains with a corner wang set, so auto-tiling code
+    /// only has to handle wang sets.
+    pub fn upgrade_terrains(&mut self) {
+        if let Some(wang_set) = self.terrains_to_wang_set() {
+            self.wang_sets.get_or_insert_with(Vec::new).push(wang_set);
+            self.terrains = None;
+            for tile in self.tiles.iter_mut().flatten() {
+                tile.terrain = None;
+            }
+        }
+    }
+}
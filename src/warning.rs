@@ -0,0 +1,84 @@
+use std::fmt;
+
+use crate::version::TiledVersion;
+use crate::{LayerType, Map};
+
+/// Oldest file format version without known issues.
+const OLDEST_SUPPORTED: TiledVersion = TiledVersion::new(1, 2, 0);
+
+/// Non-fatal issue found while parsing a map, see `Map::warnings`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseWarning {
+    /// `version` is a number, as written before Tiled 1.6. Versions like
+    /// 1.10 can't be told apart from 1.1.
+    NumericVersion,
+    /// `version` isn't a valid version number.
+    InvalidVersion(String),
+    /// File format is older than 1.2, eg. layers have no ids.
+    OldVersion(TiledVersion),
+    /// A field that newer versions of Tiled replace, eg. terrains.
+    DeprecatedField { field: String, replacement: String },
+    /// Object layer without an id.
+    MissingLayerId { layer: usize },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::NumericVersion => f.write_str("Version is a number instead of a string"),
+            ParseWarning::InvalidVersion(version) => write!(f, "Invalid version {:?}", version),
+            ParseWarning::OldVersion(version) => {
+                write!(f, "Version {} is older than {}", version, OLDEST_SUPPORTED)
+            }
+            ParseWarning::DeprecatedField { field, replacement } => {
+                write!(f, "{} is deprecated, use {}", field, replacement)
+            }
+            ParseWarning::MissingLayerId { layer } => write!(f, "layer {} has no id", layer),
+        }
+    }
+}
+
+impl Map {
+    /// Non-fatal issues found while parsing. Empty for maps saved by recent
+    /// versions of Tiled.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Add warnings that can be found from the parsed map.
+    pub(crate) fn check_warnings(&mut self) {
+        let mut warnings = Vec::new();
+
+        match self.tiled_version() {
+            Ok(version) if version < OLDEST_SUPPORTED => {
+                warnings.push(ParseWarning::OldVersion(version))
+            }
+            Ok(_) => {}
+            Err(_) => warnings.push(ParseWarning::InvalidVersion(self.version.clone())),
+        }
+
+        for (index, tileset) in self.tilesets.iter().enumerate() {
+            let tile_terrains = tileset
+                .tiles
+                .iter()
+                .flatten()
+                .any(|tile| tile.terrain.is_some());
+
+            if tileset.terrains.is_some() || tile_terrains {
+                warnings.push(ParseWarning::DeprecatedField {
+                    field: format!("tilesets[{}].terrains", index),
+                    replacement: "wangsets".to_string(),
+                });
+            }
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let invalid = matches!(layer.data, LayerType::Invalid { .. });
+            if layer.id.is_none() && !invalid {
+                warnings.push(ParseWarning::MissingLayerId { layer: index });
+            }
+        }
+
+        self.warnings.extend(warnings);
+    }
+}
@@ -0,0 +1,148 @@
+//! Writing Tiled JSON.
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use crate::error::TiledError;
+
+/// Options controlling how JSON is written.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub(crate) canonical: bool,
+    pub(crate) line_ending: LineEnding,
+}
+
+/// Line ending of written files.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl WriteOptions {
+    /// Pretty-printed JSON with keys in alphabetical order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write in the layout Tiled uses when saving, so files written by this
+    /// crate and by Tiled don't differ in formatting. Keys are sorted like
+    /// Tiled does and whole numbers are written without fraction.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Line ending, Tiled uses the one of the platform.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+}
+
+/// Write a JSON document with given options.
+pub fn write_json<W: Write>(
+    value: &Value,
+    mut writer: W,
+    options: &WriteOptions,
+) -> Result<(), TiledError> {
+    let text = if options.canonical {
+        let mut text = String::new();
+        write_canonical(value, 0, &mut text);
+        text
+    } else {
+        serde_json::to_string_pretty(value).map_err(TiledError::ParsingError)?
+    };
+
+    let text = match options.line_ending {
+        LineEnding::Lf => text,
+        LineEnding::CrLf => text.replace('\n', "\r\n"),
+    };
+
+    writer
+        .write_all(text.as_bytes())
+        .map_err(|err| TiledError::Other(format!("{:?}", err)))
+}
+
+/// Layout of Tiled: containers are indented by 4 spaces per level and their
+/// keys by one more, objects start on their own line and array elements are
+/// separated by `", "` and a line break.
+fn write_canonical(value: &Value, depth: usize, text: &mut String) {
+    match value {
+        Value::Object(fields) if fields.is_empty() => text.push_str("{}"),
+        Value::Object(fields) => {
+            let indent = " ".repeat(depth * 4);
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+
+            text.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                match (i, depth) {
+                    (0, 0) => text.push(' '),
+                    (0, _) => text.push('\n'),
+                    _ => text.push_str(",\n"),
+                }
+                if i > 0 || depth > 0 {
+                    text.push_str(&indent);
+                    text.push(' ');
+                }
+
+                text.push_str(&Value::String(key.clone()).to_string());
+                text.push(':');
+
+                let value = &fields[key];
+                if matches!(value, Value::Object(fields) if !fields.is_empty()) {
+                    text.push('\n');
+                    text.push_str(&" ".repeat((depth + 1) * 4));
+                }
+                write_canonical(value, depth + 1, text);
+            }
+            text.push('\n');
+            text.push_str(&indent);
+            text.push('}');
+        }
+        Value::Array(values) if values.iter().any(|value| value.is_object()) => {
+            let indent = " ".repeat((depth + 1) * 4);
+
+            text.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    text.push_str(", ");
+                }
+                text.push('\n');
+                text.push_str(&indent);
+                write_canonical(value, depth + 1, text);
+            }
+            text.push(']');
+        }
+        Value::Array(values) => {
+            text.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    text.push_str(", ");
+                }
+                write_canonical(value, depth + 1, text);
+            }
+            text.push(']');
+        }
+        Value::Number(number) => text.push_str(&format_number(number)),
+        _ => text.push_str(&value.to_string()),
+    }
+}
+
+/// Whole numbers without fraction, others in the shortest form. Numbers
+/// that came from `f32` fields are written as the `f32`, eg. 3.14 instead of
+/// 3.140000104904175.
+fn format_number(number: &serde_json::Number) -> String {
+    if !number.is_f64() {
+        return number.to_string();
+    }
+
+    match number.as_f64() {
+        Some(float) if float.fract() == 0.0 && float.abs() < 1e15 => format!("{}", float as i64),
+        Some(float) if f64::from(float as f32) == float => format!("{}", float as f32),
+        _ => number.to_string(),
+    }
+}